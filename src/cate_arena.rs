@@ -1,9 +1,10 @@
 use std::fmt::Debug;
+use std::marker::PhantomData;
 use std::{hash::Hash, iter::FusedIterator};
 
 use crate::id_distributer::IdDistributer;
+use crate::persistent_map::{PersistentMap, ValueCloner};
 use crate::{NodeEnum, NodeIndex};
-use ordermap::OrderMap;
 
 /// A discriminant enum for the CateIndex & NodeEnum
 pub trait NodeDiscriminant:
@@ -43,6 +44,47 @@ pub trait NodeIntoIter<T: NodeEnum>:
 {
 }
 
+/// Finds the [`ValueCloner`] of a concrete node type, intented to be used in macros.
+///
+/// Nodes can only be shared by [`fork`](CateArena::fork), which requires `V: Clone`,
+/// but they are copied on write by methods without this bound, so the cloner is resolved from the type instead.
+/// `(&ClonerProbe::<V>::new()).cloner()` is `Some` if `V: Clone` by [`ProbeClone`], or `None` by [`ProbeNoClone`].
+#[doc(hidden)]
+pub struct ClonerProbe<V>(PhantomData<V>);
+
+impl<V> ClonerProbe<V> {
+  #[allow(clippy::new_without_default)]
+  pub fn new() -> Self {
+    ClonerProbe(PhantomData)
+  }
+}
+
+#[doc(hidden)]
+pub trait ProbeClone {
+  type V;
+  fn cloner(&self) -> Option<ValueCloner<Self::V>>;
+}
+
+impl<V: Clone> ProbeClone for ClonerProbe<V> {
+  type V = V;
+  fn cloner(&self) -> Option<ValueCloner<V>> {
+    Some(V::clone)
+  }
+}
+
+#[doc(hidden)]
+pub trait ProbeNoClone {
+  type V;
+  fn cloner(&self) -> Option<ValueCloner<Self::V>>;
+}
+
+impl<V> ProbeNoClone for &ClonerProbe<V> {
+  type V = V;
+  fn cloner(&self) -> Option<ValueCloner<V>> {
+    None
+  }
+}
+
 pub trait CateArena: 'static {
   // Required
   type V: NodeEnum<Discriminant = Self::D>;
@@ -53,9 +95,11 @@ pub trait CateArena: 'static {
 
   fn new(id_distributer: IdDistributer) -> Self;
   fn new_from_iter(id_distributer: IdDistributer, iter: impl IntoIterator<Item = (NodeIndex, Self::V)>) -> Self;
+  /// The distributer of the indexes, shared with the context
+  fn id_distributer(&self) -> &IdDistributer;
   fn dispatch(&self, i: NodeIndex) -> Option<Self::D>;
-  fn get_container<'a>(&'a self, d: Self::D) -> &'a OrderMap<usize, Self::V>;
-  fn get_container_mut<'a>(&'a mut self, d: Self::D) -> &'a mut OrderMap<usize, Self::V>;
+  fn get_container(&self, d: Self::D) -> &PersistentMap<usize, Self::V>;
+  fn get_container_mut(&mut self, d: Self::D) -> &mut PersistentMap<usize, Self::V>;
   fn alloc(&mut self, d: Self::D) -> NodeIndex;
   fn alloc_untyped(&mut self) -> NodeIndex;
   fn fill_back_untyped(&mut self, i: NodeIndex, item: Self::V);
@@ -63,7 +107,16 @@ pub trait CateArena: 'static {
   where
     Self: Sized;
   fn remove(&mut self, i: NodeIndex) -> Option<Self::V>;
+  /// Make a new arena sharing all the nodes in O(1).
+  /// A change in either arena copies only the trie nodes of its container on the path to the changed node,
+  /// see [`PersistentMap`].
+  fn fork(&self) -> Self
+  where
+    Self::V: Clone;
   fn len(&self) -> usize;
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
   fn iter<'a>(&'a self) -> Self::Iter<'a>;
   fn iter_mut<'a>(&'a mut self) -> Self::IterMut<'a>;
   fn into_iter(self) -> Self::IntoIter;
@@ -78,7 +131,7 @@ pub trait CateArena: 'static {
     idx
   }
   fn fill_back(&mut self, i: NodeIndex, item: Self::V) {
-    let d = self.dispatch(i).expect(&format!("Fillback an non-existing index {:?}", i));
+    let d = self.dispatch(i).unwrap_or_else(|| panic!("Fillback an non-existing index {:?}", i));
     if Discriminated::discriminant(&item) != d {
       panic!("Fillback with incompatible type: found{:?} expect{:?}", Discriminated::discriminant(&item), d);
     }
@@ -87,7 +140,7 @@ pub trait CateArena: 'static {
     }
   }
   fn contains(&self, i: NodeIndex) -> bool {
-    self.dispatch(i).map_or(false, |d| self.get_container(d).contains_key(&i.0))
+    self.dispatch(i).is_some_and(|d| self.get_container(d).contains_key(&i.0))
   }
   fn get(&self, i: NodeIndex) -> Option<&Self::V> {
     self.dispatch(i).and_then(|d| self.get_container(d).get(&i.0))
@@ -99,8 +152,8 @@ pub trait CateArena: 'static {
   where
     F: FnOnce(Self::V) -> Self::V,
   {
-    let d = self.dispatch(i).expect(&format!("Update a non-existing node {:?}", i));
-    let x = self.get_container_mut(d).swap_remove(&i.0).expect(&format!("Update a non-existing node {:?}", i));
+    let d = self.dispatch(i).unwrap_or_else(|| panic!("Update a non-existing node {:?}", i));
+    let x = self.get_container_mut(d).remove(&i.0).unwrap_or_else(|| panic!("Update a non-existing node {:?}", i));
     self.get_container_mut(d).insert(i.0, f(x));
   }
 
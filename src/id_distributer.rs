@@ -16,6 +16,16 @@ impl IdDistributer {
     c + 1
   }
 
+  /// Make sure the id will not be allocated again
+  pub(crate) fn reserve(&self, id: usize) {
+    self.cnt.fetch_max(id, Ordering::Relaxed);
+  }
+
+  /// The last allocated or reserved id
+  pub(crate) fn count(&self) -> usize {
+    self.cnt.load(Ordering::Relaxed)
+  }
+
   pub(crate) fn from_count(cnt: usize) -> IdDistributer {
     IdDistributer { cnt: Arc::new(AtomicUsize::new(cnt)) }
   }
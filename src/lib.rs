@@ -527,7 +527,7 @@
 //! Notes:
 //!
 //! + Variants can be inside of multiple or none groups.
-//! + Currently, this method does not provide performance enhancement, as it is only a wrapper on matching the variants according to the group name.
+//! + Nodes of each variant are stored separately, so [`iter_group`](Graph::iter_group) only visits the variants inside the group, and [`len_group`](Graph::len_group) counts them without iterating.
 //!
 //! ```rust
 //! # use ttgraph::*;
@@ -951,6 +951,79 @@
 //! # }
 //! ```
 //!
+//! ## Secondary index
+//!
+//! Data fields can be indexed with `index!` in `node_enum!`, then nodes can be found by the value of the field with [`Graph::lookup`].
+//! An index is named after the field, so fields of the same name in different variants share one index. Groups of variants can also be used.
+//! A `unique` index is checked at the end of each commit, and the commit panics if two nodes share the same key.
+//!
+//! ```rust
+//! use ttgraph::*;
+//! #[derive(TypedNode)]
+//! struct FactoryNode{
+//!   name: String,
+//! }
+//! #[derive(TypedNode)]
+//! struct WorkerNode{
+//!   name: String,
+//!   factory: NodeIndex,
+//! }
+//! node_enum!{
+//!   enum Node{
+//!     Factory(FactoryNode),
+//!     Worker(WorkerNode),
+//!   }
+//!   index!{
+//!     Worker.name,
+//!     Factory.name: unique,
+//!   }
+//! }
+//! # fn main() {
+//! let ctx = Context::new();
+//! let mut graph = Graph::<Node>::new(&ctx);
+//! let mut trans = Transaction::new(&ctx);
+//! let factory = trans.insert(Node::Factory(FactoryNode{ name: "Factory".to_string() }));
+//! let worker = trans.insert(Node::Worker(WorkerNode{ name: "Alice".to_string(), factory }));
+//! graph.commit(trans);
+//!
+//! assert_eq!(Vec::from_iter(graph.lookup("name", &"Alice".to_string())), vec![worker]);
+//! assert_eq!(Vec::from_iter(graph.lookup("name", &"Factory".to_string())), vec![factory]);
+//! # }
+//! ```
+//!
+//! ## Modify data without transaction
+//!
+//! Modifying a node with a transaction re-checks all of its links at commit. If only data fields (i.e. fields that are not links) are changed, [`get_data_mut!`] and [`iter_nodes_data_mut!`] are cheaper.
+//! `TypedNode` derive generates a `NodeTypeData` struct for each node type in its generated mod `ttgraph_gen_node_type`, which has `&mut` references to the data fields, so the links can not be broken through it.
+//!
+//! ```rust
+//! use ttgraph::*;
+//! #[derive(TypedNode)]
+//! struct WorkerNode{
+//!   name: String,
+//!   salary: usize,
+//!   factory: NodeIndex,
+//! }
+//! node_enum!{
+//!   enum Node{
+//!     Worker(WorkerNode),
+//!   }
+//! }
+//! # fn main() {
+//! let ctx = Context::new();
+//! let mut graph = Graph::<Node>::new(&ctx);
+//! let mut trans = Transaction::new(&ctx);
+//! let worker = trans.insert(Node::Worker(WorkerNode{ name: "Alice".to_string(), salary: 100, factory: NodeIndex::empty() }));
+//! graph.commit(trans);
+//!
+//! // data: WorkerNodeData, with fields name: &mut String and salary: &mut usize
+//! for (_, data) in iter_nodes_data_mut!(graph, Node::Worker) {
+//!   *data.salary += 10;
+//! }
+//! assert_eq!(get_node!(graph, Node::Worker, worker).unwrap().salary, 110);
+//! # }
+//! ```
+//!
 //! ## Working In Progress
 //!
 //! + Graph creation macro. A sub-language to simplify great amount of `alloc_node`, `fill_back_node` and `new_node` calls.
@@ -963,6 +1036,8 @@ pub use cate_arena::*;
 
 pub mod id_distributer;
 
+pub mod persistent_map;
+
 // pub mod graph;
 mod typed_graph;
 
@@ -971,3 +1046,6 @@ pub use typed_graph::*;
 pub mod ordermap {
   pub use ordermap::{map, OrderMap};
 }
+
+#[cfg(feature = "rayon")]
+pub use rayon;
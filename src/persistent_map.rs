@@ -0,0 +1,523 @@
+//! A persistent map from indexes to values, which stores the nodes of a graph.
+//!
+//! The map is a 32-way trie over the bits of the index. Copies made by [`share`](PersistentMap::share) or `clone`
+//! are O(1) and share all the trie nodes. A write copies only the trie nodes on the path to the written index,
+//! i.e. O(log N) nodes with at most 32 values each, so the unchanged parts stay shared.
+
+use std::fmt::Debug;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::NodeIndex;
+
+const BITS: usize = 5;
+const MASK: usize = (1 << BITS) - 1;
+
+/// Clones a value, used to copy a shared trie node on write
+pub type ValueCloner<V> = fn(&V) -> V;
+
+/// The key of a [`PersistentMap`], which is an index into the trie
+pub trait MapKey: Copy {
+  fn to_index(self) -> usize;
+  fn from_index(i: usize) -> Self;
+}
+
+impl MapKey for usize {
+  fn to_index(self) -> usize {
+    self
+  }
+  fn from_index(i: usize) -> Self {
+    i
+  }
+}
+
+impl MapKey for NodeIndex {
+  fn to_index(self) -> usize {
+    self.0
+  }
+  fn from_index(i: usize) -> Self {
+    NodeIndex(i)
+  }
+}
+
+enum Node<V> {
+  Branch { bitmap: u32, children: Vec<Arc<Node<V>>> },
+  Leaf { bitmap: u32, values: Vec<V> },
+}
+
+impl<V> Node<V> {
+  fn singleton(level: usize, key: usize, value: V) -> Self {
+    let bit = 1 << ((key >> (BITS * level)) & MASK);
+    if level == 0 {
+      Node::Leaf { bitmap: bit, values: vec![value] }
+    } else {
+      Node::Branch { bitmap: bit, children: vec![Arc::new(Node::singleton(level - 1, key, value))] }
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    match self {
+      Node::Branch { bitmap, .. } | Node::Leaf { bitmap, .. } => *bitmap == 0,
+    }
+  }
+
+  fn copy(&self, cloner: Option<ValueCloner<V>>) -> Self {
+    match self {
+      Node::Branch { bitmap, children } => Node::Branch { bitmap: *bitmap, children: children.clone() },
+      Node::Leaf { bitmap, values } => {
+        let cloner = cloner.expect("A shared map must have a value cloner");
+        Node::Leaf { bitmap: *bitmap, values: values.iter().map(cloner).collect() }
+      },
+    }
+  }
+
+  fn make_mut(node: &mut Arc<Self>, cloner: Option<ValueCloner<V>>) -> &mut Self {
+    if Arc::get_mut(node).is_none() {
+      *node = Arc::new(node.copy(cloner));
+    }
+    Arc::get_mut(node).unwrap()
+  }
+
+  fn insert(node: &mut Arc<Self>, level: usize, key: usize, value: V, cloner: Option<ValueCloner<V>>) -> Option<V> {
+    let (bit, pos) = locate(node_bitmap(node), level, key);
+    match Node::make_mut(node, cloner) {
+      Node::Leaf { bitmap, values } => {
+        if *bitmap & bit != 0 {
+          Some(std::mem::replace(&mut values[pos], value))
+        } else {
+          *bitmap |= bit;
+          values.insert(pos, value);
+          None
+        }
+      },
+      Node::Branch { bitmap, children } => {
+        if *bitmap & bit != 0 {
+          Node::insert(&mut children[pos], level - 1, key, value, cloner)
+        } else {
+          *bitmap |= bit;
+          children.insert(pos, Arc::new(Node::singleton(level - 1, key, value)));
+          None
+        }
+      },
+    }
+  }
+
+  fn remove(node: &mut Arc<Self>, level: usize, key: usize, cloner: Option<ValueCloner<V>>) -> Option<V> {
+    let (bit, pos) = locate(node_bitmap(node), level, key);
+    match Node::make_mut(node, cloner) {
+      Node::Leaf { bitmap, values } => {
+        *bitmap &= !bit;
+        Some(values.remove(pos))
+      },
+      Node::Branch { bitmap, children } => {
+        let result = Node::remove(&mut children[pos], level - 1, key, cloner);
+        if children[pos].is_empty() {
+          *bitmap &= !bit;
+          children.remove(pos);
+        }
+        result
+      },
+    }
+  }
+
+  fn get_mut(node: &mut Arc<Self>, level: usize, key: usize, cloner: Option<ValueCloner<V>>) -> &mut V {
+    let (_, pos) = locate(node_bitmap(node), level, key);
+    match Node::make_mut(node, cloner) {
+      Node::Leaf { values, .. } => &mut values[pos],
+      Node::Branch { children, .. } => Node::get_mut(&mut children[pos], level - 1, key, cloner),
+    }
+  }
+
+  fn make_unique(node: &mut Arc<Self>, cloner: Option<ValueCloner<V>>) {
+    if let Node::Branch { children, .. } = Node::make_mut(node, cloner) {
+      for child in children {
+        Node::make_unique(child, cloner);
+      }
+    }
+  }
+
+  fn drain<K: MapKey>(
+    node: Arc<Self>, prefix: usize, level: usize, cloner: Option<ValueCloner<V>>, out: &mut Vec<(K, V)>,
+  ) {
+    match Arc::try_unwrap(node).unwrap_or_else(|node| node.copy(cloner)) {
+      Node::Leaf { mut bitmap, values } => {
+        for v in values {
+          out.push((K::from_index(prefix | bitmap.trailing_zeros() as usize), v));
+          bitmap &= bitmap - 1;
+        }
+      },
+      Node::Branch { mut bitmap, children } => {
+        for child in children {
+          let p = prefix | ((bitmap.trailing_zeros() as usize) << (BITS * level));
+          bitmap &= bitmap - 1;
+          Node::drain(child, p, level - 1, cloner, out);
+        }
+      },
+    }
+  }
+}
+
+fn node_bitmap<V>(node: &Node<V>) -> u32 {
+  match node {
+    Node::Branch { bitmap, .. } | Node::Leaf { bitmap, .. } => *bitmap,
+  }
+}
+
+/// Get the bit of the key at this level, and the position of the child or value in the compressed vec
+fn locate(bitmap: u32, level: usize, key: usize) -> (u32, usize) {
+  let bit = 1u32 << ((key >> (BITS * level)) & MASK);
+  (bit, (bitmap & (bit - 1)).count_ones() as usize)
+}
+
+/// A persistent map keyed by indexes, see the [module documentation](self)
+///
+/// Iteration follows the order of the keys.
+/// The values of a shared trie node are copied with the value cloner given at construction,
+/// writing to a shared map without a cloner panics.
+pub struct PersistentMap<K, V> {
+  root: Option<Arc<Node<V>>>,
+  // The level of the root, the leaves are at level 0
+  height: usize,
+  len: usize,
+  cloner: Option<ValueCloner<V>>,
+  _key: PhantomData<K>,
+}
+
+impl<K: MapKey, V: Clone> PersistentMap<K, V> {
+  /// Make an empty map
+  pub fn new() -> Self {
+    Self::with_cloner(Some(V::clone))
+  }
+}
+
+impl<K: MapKey, V: Clone> Default for PersistentMap<K, V> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K: MapKey, V: Clone> Clone for PersistentMap<K, V> {
+  fn clone(&self) -> Self {
+    self.share()
+  }
+}
+
+impl<K: MapKey, V> PersistentMap<K, V> {
+  /// Make an empty map, which can only be shared if there is a cloner for the values
+  pub fn with_cloner(cloner: Option<ValueCloner<V>>) -> Self {
+    PersistentMap { root: None, height: 0, len: 0, cloner, _key: PhantomData }
+  }
+
+  /// Make a copy sharing all the values in O(1)
+  ///
+  /// Panics on the next write to either map if there is no value cloner.
+  pub fn share(&self) -> Self {
+    PersistentMap {
+      root: self.root.clone(),
+      height: self.height,
+      len: self.len,
+      cloner: self.cloner,
+      _key: PhantomData,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  fn fits(&self, key: usize) -> bool {
+    let bits = BITS * (self.height + 1);
+    bits >= usize::BITS as usize || key >> bits == 0
+  }
+
+  pub fn get(&self, key: &K) -> Option<&V> {
+    let key = key.to_index();
+    if !self.fits(key) {
+      return None;
+    }
+    let mut node = self.root.as_deref()?;
+    let mut level = self.height;
+    loop {
+      let (bit, pos) = locate(node_bitmap(node), level, key);
+      match node {
+        Node::Leaf { bitmap, values } => return if bitmap & bit != 0 { Some(&values[pos]) } else { None },
+        Node::Branch { bitmap, children } => {
+          if bitmap & bit == 0 {
+            return None;
+          }
+          node = &children[pos];
+          level -= 1;
+        },
+      }
+    }
+  }
+
+  pub fn contains_key(&self, key: &K) -> bool {
+    self.get(key).is_some()
+  }
+
+  /// Get a mutable reference, copying the shared trie nodes on the path
+  pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+    if !self.contains_key(key) {
+      return None;
+    }
+    Some(Node::get_mut(self.root.as_mut().unwrap(), self.height, key.to_index(), self.cloner))
+  }
+
+  /// Get a mutable reference, inserting the value made by `f` if the key is absent
+  pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce() -> V) -> &mut V {
+    if !self.contains_key(&key) {
+      self.insert(key, f());
+    }
+    self.get_mut(&key).unwrap()
+  }
+
+  /// Insert a value, return the old value if the key is present
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    let key = key.to_index();
+    while !self.fits(key) {
+      if let Some(root) = self.root.take() {
+        self.root = Some(Arc::new(Node::Branch { bitmap: 1, children: vec![root] }));
+      }
+      self.height += 1;
+    }
+    let result = match &mut self.root {
+      Some(root) => Node::insert(root, self.height, key, value, self.cloner),
+      None => {
+        self.root = Some(Arc::new(Node::singleton(self.height, key, value)));
+        None
+      },
+    };
+    if result.is_none() {
+      self.len += 1;
+    }
+    result
+  }
+
+  /// Remove a value, return it if the key is present
+  pub fn remove(&mut self, key: &K) -> Option<V> {
+    if !self.contains_key(key) {
+      return None;
+    }
+    let result = Node::remove(self.root.as_mut().unwrap(), self.height, key.to_index(), self.cloner);
+    self.len -= 1;
+    if self.len == 0 {
+      self.root = None;
+      self.height = 0;
+    }
+    result
+  }
+
+  /// Move all values of `other` into this map, the values of `other` replace the values of the same keys
+  pub fn append(&mut self, other: &mut Self) {
+    let other = std::mem::replace(other, Self::with_cloner(other.cloner));
+    for (k, v) in other {
+      self.insert(k, v);
+    }
+  }
+
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    let mut iter = Iter { stack: Vec::new(), leaf: None, len: self.len, _key: PhantomData };
+    if let Some(root) = &self.root {
+      iter.push(root, 0, self.height);
+    }
+    iter
+  }
+
+  /// Iterate with mutable references, which copies all the shared trie nodes first
+  pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+    let mut iter = IterMut { stack: Vec::new(), leaf: None, len: self.len, _key: PhantomData };
+    if let Some(root) = &mut self.root {
+      Node::make_unique(root, self.cloner);
+      iter.push(root, 0, self.height);
+    }
+    iter
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+    self.iter().map(|(k, _)| k)
+  }
+
+  pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+    self.iter().map(|(_, v)| v)
+  }
+
+}
+
+impl<K: MapKey + Debug, V: Debug> Debug for PersistentMap<K, V> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_map().entries(self.iter()).finish()
+  }
+}
+
+impl<K: MapKey, V: Clone> FromIterator<(K, V)> for PersistentMap<K, V> {
+  fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+    let mut map = Self::new();
+    map.extend(iter);
+    map
+  }
+}
+
+impl<K: MapKey, V> Extend<(K, V)> for PersistentMap<K, V> {
+  fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+    for (k, v) in iter {
+      self.insert(k, v);
+    }
+  }
+}
+
+impl<K: MapKey, V> IntoIterator for PersistentMap<K, V> {
+  type Item = (K, V);
+  type IntoIter = IntoIter<K, V>;
+
+  /// Take the values out, the values of the trie nodes shared with other maps are cloned
+  fn into_iter(self) -> Self::IntoIter {
+    let mut values = Vec::with_capacity(self.len);
+    if let Some(root) = self.root {
+      Node::drain(root, 0, self.height, self.cloner, &mut values);
+    }
+    IntoIter(values.into_iter())
+  }
+}
+
+impl<'a, K: MapKey, V> IntoIterator for &'a PersistentMap<K, V> {
+  type Item = (K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+// (level, prefix of the keys, bitmap of the remaining children, the remaining children)
+type Frame<I> = (usize, usize, u32, I);
+
+/// Iterator of a [`PersistentMap`] in the order of keys
+pub struct Iter<'a, K, V> {
+  stack: Vec<Frame<std::slice::Iter<'a, Arc<Node<V>>>>>,
+  leaf: Option<(usize, u32, std::slice::Iter<'a, V>)>,
+  len: usize,
+  _key: PhantomData<K>,
+}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+  fn clone(&self) -> Self {
+    Iter { stack: self.stack.clone(), leaf: self.leaf.clone(), len: self.len, _key: PhantomData }
+  }
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+  fn push(&mut self, node: &'a Node<V>, prefix: usize, level: usize) {
+    match node {
+      Node::Leaf { bitmap, values } => self.leaf = Some((prefix, *bitmap, values.iter())),
+      Node::Branch { bitmap, children } => self.stack.push((level, prefix, *bitmap, children.iter())),
+    }
+  }
+}
+
+impl<'a, K: MapKey, V> Iterator for Iter<'a, K, V> {
+  type Item = (K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some((prefix, bitmap, values)) = &mut self.leaf {
+        if let Some(v) = values.next() {
+          let key = *prefix | bitmap.trailing_zeros() as usize;
+          *bitmap &= *bitmap - 1;
+          self.len -= 1;
+          return Some((K::from_index(key), v));
+        }
+        self.leaf = None;
+      }
+      let (level, prefix, bitmap, children) = self.stack.last_mut()?;
+      let Some(child) = children.next() else {
+        self.stack.pop();
+        continue;
+      };
+      let (level, p) = (*level - 1, *prefix | ((bitmap.trailing_zeros() as usize) << (BITS * *level)));
+      *bitmap &= *bitmap - 1;
+      self.push(child, p, level);
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.len, Some(self.len))
+  }
+}
+
+impl<K: MapKey, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K: MapKey, V> FusedIterator for Iter<'_, K, V> {}
+
+/// Mutable iterator of a [`PersistentMap`] in the order of keys
+pub struct IterMut<'a, K, V> {
+  stack: Vec<Frame<std::slice::IterMut<'a, Arc<Node<V>>>>>,
+  leaf: Option<(usize, u32, std::slice::IterMut<'a, V>)>,
+  len: usize,
+  _key: PhantomData<K>,
+}
+
+impl<'a, K, V> IterMut<'a, K, V> {
+  // The trie nodes are made unique by iter_mut before iterating
+  fn push(&mut self, node: &'a mut Arc<Node<V>>, prefix: usize, level: usize) {
+    match Arc::get_mut(node).unwrap() {
+      Node::Leaf { bitmap, values } => self.leaf = Some((prefix, *bitmap, values.iter_mut())),
+      Node::Branch { bitmap, children } => self.stack.push((level, prefix, *bitmap, children.iter_mut())),
+    }
+  }
+}
+
+impl<'a, K: MapKey, V> Iterator for IterMut<'a, K, V> {
+  type Item = (K, &'a mut V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some((prefix, bitmap, values)) = &mut self.leaf {
+        if let Some(v) = values.next() {
+          let key = *prefix | bitmap.trailing_zeros() as usize;
+          *bitmap &= *bitmap - 1;
+          self.len -= 1;
+          return Some((K::from_index(key), v));
+        }
+        self.leaf = None;
+      }
+      let (level, prefix, bitmap, children) = self.stack.last_mut()?;
+      let Some(child) = children.next() else {
+        self.stack.pop();
+        continue;
+      };
+      let (level, p) = (*level - 1, *prefix | ((bitmap.trailing_zeros() as usize) << (BITS * *level)));
+      *bitmap &= *bitmap - 1;
+      self.push(child, p, level);
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.len, Some(self.len))
+  }
+}
+
+impl<K: MapKey, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K: MapKey, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// Owning iterator of a [`PersistentMap`] in the order of keys
+pub struct IntoIter<K, V>(std::vec::IntoIter<(K, V)>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+  type Item = (K, V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.0.next()
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.0.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
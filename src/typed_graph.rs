@@ -7,6 +7,7 @@ use std::any::Any;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::iter::FusedIterator;
+use std::sync::Mutex;
 
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,7 @@ use uuid::Uuid;
 use crate::cate_arena::*;
 // use crate::arena::{self, Arena, ArenaIndex};
 use crate::id_distributer::IdDistributer;
+use crate::persistent_map::{self, PersistentMap};
 
 pub mod debug;
 pub mod display;
@@ -26,11 +28,54 @@ pub mod macro_traits;
 pub use macro_traits::*;
 
 mod transaction;
-pub use transaction::Transaction;
+pub use transaction::{AppendConflict, Transaction};
+
+mod send_transaction;
+pub use send_transaction::{SendFuncs, SendMutFunc, SendTransaction, SendTransactionNew, SendUpdateFunc};
 
 pub mod check;
 use check::*;
 
+pub mod index;
+pub use index::IndexVisitor;
+
+pub mod report;
+pub use report::CommitReport;
+
+pub mod snapshot;
+pub use snapshot::GraphSnapshot;
+
+pub mod par_iter;
+
+pub mod version;
+pub use version::Conflict;
+use version::{NodeVersions, VersionLease};
+
+pub mod view;
+pub use view::TransactionView;
+
+mod apply;
+use apply::NodeStore;
+
+pub mod savepoint;
+pub use savepoint::Savepoint;
+
+pub mod op;
+pub use op::Op;
+
+pub mod journal;
+
+pub mod dot;
+
+pub mod interchange;
+
+pub mod diff;
+pub use diff::{graph_diff, GraphDiff};
+pub mod schema;
+pub use schema::{BidirectionalSchema, DataSchema, GraphSchema, GroupSchema, LinkSchema, LinkTypeSchema, VariantSchema};
+pub mod binary;
+use index::IndexStore;
+
 pub mod macros;
 pub use ttgraph_macros::*;
 
@@ -70,6 +115,9 @@ impl Default for NodeIndex {
   }
 }
 
+/// A map from the old [`NodeIndex`] of copied or loaded nodes to their new [`NodeIndex`], in the order of the nodes
+pub type IdMap = OrderMap<NodeIndex, NodeIndex>;
+
 // impl ArenaIndex for NodeIndex {
 //   fn new(id: usize) -> Self {
 //     NodeIndex(id)
@@ -86,6 +134,9 @@ impl Display for NodeIndex {
   }
 }
 
+/// The links pointing to each node, as `(source node, source field)`
+type BackLinks<NodeT> = PersistentMap<NodeIndex, OrderSet<(NodeIndex, <NodeT as NodeEnum>::SourceEnum)>>;
+
 /// A graph with typed nodes
 ///
 /// The graph can only by modified by commiting a transaction, which avoids mutable borrow of the graph
@@ -130,7 +181,10 @@ where
 {
   ctx_id: Uuid,
   nodes: Arena,
-  back_links: OrderMap<NodeIndex, OrderSet<(NodeIndex, NodeT::SourceEnum)>>,
+  back_links: BackLinks<NodeT>,
+  indexes: Mutex<IndexStore>,
+  version: u64,
+  node_versions: NodeVersions,
 }
 
 impl<NodeT, Arena> Graph<NodeT, Arena>
@@ -143,7 +197,10 @@ where
     Graph {
       ctx_id: context.id,
       nodes: Arena::new(context.node_dist.clone()),
-      back_links: OrderMap::new(),
+      back_links: PersistentMap::new(),
+      indexes: Mutex::default(),
+      version: 0,
+      node_versions: NodeVersions::default(),
     }
   }
 
@@ -188,6 +245,51 @@ where
     self.nodes.get(idx)
   }
 
+  /// Get a mutable view of the data fields of a node, without a transaction. For convinience, if the type of the node is previously known, use [`get_data_mut!()`](crate::get_data_mut!) instead.
+  ///
+  /// Only the data fields (i.e. fields that are not links) can be modified through the view, so the links and back links are always consistent.
+  /// Indexes on the modified node are refreshed at the next lookup or commit,
+  /// and unique indexes are checked at the next commit.
+  /// The node is regarded as changed for [conflict detection](Transaction::begin) even if nothing is written through the view.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  ///
+  /// #[derive(TypedNode)]
+  /// struct NodeA{
+  ///   data: usize,
+  ///   link: NodeIndex,
+  /// }
+  ///
+  /// node_enum!{
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let idx = trans.insert(Node::A(NodeA{ data: 1, link: NodeIndex::empty() }));
+  /// graph.commit(trans);
+  ///
+  /// // view: Option<NodeDataMut>
+  /// if let Some(ttgraph_gen_node::NodeDataMut::A(view)) = graph.data_mut(idx) {
+  ///   *view.data = 2;
+  /// }
+  /// assert_eq!(get_node!(graph, Node::A, idx).unwrap().data, 2);
+  /// # }
+  /// ```
+  pub fn data_mut(&mut self, idx: NodeIndex) -> Option<NodeT::DataMut<'_>> {
+    let node = self.nodes.get_mut(idx)?;
+    self.indexes.get_mut().unwrap().mark_dirty(idx, node);
+    self.version += 1;
+    self.node_versions.insert(idx, self.version);
+    Some(node.data_mut())
+  }
+
   /// Iterate all nodes in the graph following the order of NodeIndex.
   ///
   /// If only a type of node is wanted, use [`iter_nodes!`](`crate::iter_nodes!`) instead.
@@ -281,12 +383,29 @@ where
   /// ```
   pub fn iter_type<'a>(
     &'a self, d: NodeT::Discriminant,
-  ) -> NodeIterator<'a, NodeT, ordermap::map::Iter<'a, usize, NodeT>> {
+  ) -> NodeIterator<'a, NodeT, persistent_map::Iter<'a, usize, NodeT>> {
     NodeIterator { iter: self.nodes.get_container(d).iter() }
   }
 
+  /// Iterate a type of nodes with mutable views of their data fields. For convinience, use [`iter_nodes_data_mut!`](crate::iter_nodes_data_mut!) instead.
+  ///
+  /// See [`data_mut`](Graph::data_mut) for more information.
+  pub fn iter_type_data_mut(&mut self, d: NodeT::Discriminant) -> impl Iterator<Item = (NodeIndex, NodeT::DataMut<'_>)> {
+    let indexes = self.indexes.get_mut().unwrap();
+    self.version += 1;
+    let version = self.version;
+    let node_versions = &mut self.node_versions;
+    self.nodes.get_container_mut(d).iter_mut().map(move |(k, v)| {
+      indexes.mark_dirty(NodeIndex(k), v);
+      node_versions.insert(NodeIndex(k), version);
+      (NodeIndex(k), v.data_mut())
+    })
+  }
+
   /// Iterate all nodes within the named group
   ///
+  /// Only the variants inside the group are visited. Nodes are iterated variant by variant, in the order of the variants in the NodeEnum.
+  ///
   /// # Example
   /// ```
   /// use ttgraph::*;
@@ -342,7 +461,50 @@ where
   /// # }
   /// ```
   pub fn iter_group(&self, name: &'static str) -> impl Iterator<Item = (NodeIndex, &NodeT)> {
-    self.iter().filter(move |(_, n)| n.in_group(name))
+    NodeT::group_discriminants(name)
+      .iter()
+      .flat_map(move |d| self.nodes.get_container(*d).iter().map(|(k, v)| (NodeIndex(k), v)))
+  }
+
+  /// Get the number of nodes within the named group
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode)]
+  /// struct NodeA{
+  ///   a: usize,
+  /// }
+  /// #[derive(TypedNode)]
+  /// struct NodeB{
+  ///   b: usize,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     A(NodeA),
+  ///     B(NodeB),
+  ///   }
+  ///   group!{
+  ///     first{A},
+  ///     all{A, B},
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// trans.insert(Node::A(NodeA{a: 1}));
+  /// trans.insert(Node::A(NodeA{a: 2}));
+  /// trans.insert(Node::B(NodeB{b: 3}));
+  /// graph.commit(trans);
+  /// assert_eq!(graph.len_group("first"), 2);
+  /// assert_eq!(graph.len_group("all"), 3);
+  /// assert_eq!(graph.len_group("unknown"), 0);
+  /// # }
+  /// ```
+  pub fn len_group(&self, name: &'static str) -> usize {
+    NodeT::group_discriminants(name).iter().map(|d| self.nodes.get_container(*d).len()).sum()
   }
 
   /// Get the number of nodes in a graph
@@ -381,6 +543,100 @@ where
     self.len() == 0
   }
 
+  /// Check if a node is in the graph
+  pub fn contains(&self, idx: NodeIndex) -> bool {
+    self.nodes.contains(idx)
+  }
+
+  /// Iterate the nodes linking to the given node, as `(source node, link)`
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode)]
+  /// struct NodeA{
+  ///   to: NodeIndex,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let a = trans.insert(Node::A(NodeA{ to: NodeIndex::empty() }));
+  /// let b = trans.insert(Node::A(NodeA{ to: a }));
+  /// graph.commit(trans);
+  ///
+  /// assert_eq!(Vec::from_iter(graph.back_links(a).map(|(x, _)| x)), vec![b]);
+  /// assert_eq!(graph.back_links(b).count(), 0);
+  /// # }
+  /// ```
+  pub fn back_links(&self, idx: NodeIndex) -> impl Iterator<Item = (NodeIndex, NodeT::LinkMirrorEnum)> + '_ {
+    self.back_links.get(&idx).into_iter().flatten().map(|(x, s)| (*x, NodeT::to_link_mirror_enum(*s)))
+  }
+
+  /// Find the nodes whose indexed data field equals to the key. The index is declared by `index!` in [`node_enum!`](crate::node_enum!).
+  ///
+  /// The key should have exactly the same type as the field, e.g. `&String` instead of `&str` for a `String` field.
+  ///
+  /// # Panics
+  /// Panics if the key has another type than the indexed fields. Before any node with the field is inserted, the type
+  /// is unknown and nothing is found.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode)]
+  /// struct FactoryNode{
+  ///   name: String,
+  /// }
+  /// #[derive(TypedNode)]
+  /// struct WorkerNode{
+  ///   name: String,
+  ///   factory: NodeIndex,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     Factory(FactoryNode),
+  ///     Worker(WorkerNode),
+  ///   }
+  ///   index!{
+  ///     Worker.name,
+  ///     Factory.name: unique,
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let factory = trans.insert(Node::Factory(FactoryNode{ name: "Factory".to_string() }));
+  /// let alice = trans.insert(Node::Worker(WorkerNode{ name: "Alice".to_string(), factory }));
+  /// graph.commit(trans);
+  ///
+  /// assert_eq!(Vec::from_iter(graph.lookup("name", &"Alice".to_string())), vec![alice]);
+  /// assert_eq!(graph.lookup("name", &"Factory".to_string()).next(), Some(factory));
+  /// assert_eq!(graph.lookup("name", &"Bob".to_string()).next(), None);
+  ///
+  /// let mut trans = Transaction::new(&ctx);
+  /// mut_node!(trans, Node::Worker, alice, |x| { x.name = "Bob".to_string(); });
+  /// graph.commit(trans);
+  /// assert_eq!(graph.lookup("name", &"Alice".to_string()).next(), None);
+  /// assert_eq!(graph.lookup("name", &"Bob".to_string()).next(), Some(alice));
+  /// # }
+  /// ```
+  pub fn lookup<K: Any + Hash + Eq>(&self, name: &str, key: &K) -> impl Iterator<Item = NodeIndex> + '_ {
+    let mut indexes = self.indexes.lock().unwrap();
+    // Nodes changed by data_mut are indexed again by the first lookup after the change
+    indexes.refresh(&self.nodes);
+    let result = Vec::from_iter(indexes.lookup(name, key).into_iter().flat_map(|nodes| nodes.iter().copied()));
+    result.into_iter()
+  }
+
   /// Commit an [`Transaction`] to modify the graph
   ///
   /// Operation order:
@@ -398,6 +654,7 @@ where
   ///
   /// + the transaction and the graph have different context
   /// + there are multiple choices to make a bidirectional link (i.e. a.x <-> {b.y, b.z}, found a.x, don't know if b.y=x or b.z=x)
+  /// + multiple nodes have the same key in a unique index
   ///
   /// # Example
   /// ```
@@ -423,15 +680,68 @@ where
   pub fn commit(&mut self, t: Transaction<NodeT, Arena>) {
     let lcr = self.do_commit(t);
     self.check_link_type(&lcr);
+    self.check_unique_indexes();
+  }
+
+  /// Similar to [`commit()`](Graph::commit), but returns a [`CommitReport`] of the changed nodes and links.
+  ///
+  /// Useful to keep caches or UIs in sync with the graph.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// use std::collections::BTreeSet;
+  /// #[derive(TypedNode)]
+  /// struct FactoryNode{
+  ///   workers: BTreeSet<NodeIndex>,
+  /// }
+  /// #[derive(TypedNode)]
+  /// struct WorkerNode{
+  ///   factory: NodeIndex,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     Factory(FactoryNode),
+  ///     Worker(WorkerNode),
+  ///   }
+  ///   bidirectional!{
+  ///     Factory.workers <-> Worker.factory,
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let factory = trans.insert(Node::Factory(FactoryNode{ workers: BTreeSet::new() }));
+  /// graph.commit(trans);
+  ///
+  /// let mut trans = Transaction::new(&ctx);
+  /// let worker = trans.insert(Node::Worker(WorkerNode{ factory }));
+  /// let report = graph.commit_with_report(trans);
+  ///
+  /// assert_eq!(Vec::from_iter(report.inserted), vec![worker]);
+  /// // The factory is modified to maintain the bidirectional link
+  /// assert_eq!(Vec::from_iter(report.modified), vec![factory]);
+  /// assert_eq!(report.added_links.len(), 2);
+  /// # }
+  /// ```
+  pub fn commit_with_report(&mut self, t: Transaction<NodeT, Arena>) -> CommitReport<NodeT> {
+    let mut report = CommitReport::new(&t);
+    let lcr = self.do_commit(t);
+    self.check_link_type(&lcr);
+    self.check_unique_indexes();
+    report.add_link_changes(lcr);
+    report
   }
 
   /// Similar to [`commit()`](Graph::commit), but with additional checks on the changed nodes and links.
   ///
   /// See [`GraphCheck`] for more information.
-  #[cfg(feature = "debug")]
   pub fn commit_checked(&mut self, t: Transaction<NodeT, Arena>, checks: &GraphCheck<NodeT>) {
     let lcr = self.do_commit(t);
     self.check_link_type(&lcr);
+    self.check_unique_indexes();
     let result = self.check_change(&lcr, checks);
     if !result.is_empty() {
       panic!("Check failed: {:?}", &result);
@@ -457,7 +767,7 @@ where
     }
 
     for (id, new_id) in &id_map {
-      for (y, s) in &self.back_links[id] {
+      for (y, s) in self.back_links.get(id).unwrap() {
         new_nodes.get_mut(id_map[y]).unwrap().modify_link(*s, *id, *new_id);
       }
     }
@@ -465,13 +775,17 @@ where
     let mut result = Graph {
       ctx_id: new_ctx.id,
       nodes: Arena::new(new_ctx.node_dist.clone()),
-      back_links: OrderMap::new(),
+      back_links: PersistentMap::new(),
+      indexes: Mutex::default(),
+      version: 0,
+      node_versions: NodeVersions::default(),
     };
 
     let mut lcr = LinkChangeRecorder::default();
-    result.merge_nodes(new_nodes, &mut lcr);
-    result.apply_bidirectional_links(&lcr);
+    apply::insert_nodes(&mut result, new_nodes.into_iter(), &mut lcr);
+    apply::apply_bidirectional_links(&mut result, &mut lcr);
     result.check_link_type(&lcr);
+    result.check_unique_indexes();
     result
   }
 
@@ -492,17 +806,17 @@ where
   #[cfg(feature = "debug")]
   #[doc(hidden)]
   pub fn check_backlinks(&self) {
-    let mut back_links: OrderMap<NodeIndex, OrderSet<(NodeIndex, NodeT::SourceEnum)>> = OrderMap::new();
+    let mut back_links: BackLinks<NodeT> = PersistentMap::new();
     for (x, n) in self.nodes.iter() {
-      back_links.entry(x).or_default();
+      back_links.get_or_insert_with(x, OrderSet::new);
       for (y, s) in n.iter_sources() {
-        back_links.entry(y).or_default().insert((x, s));
+        back_links.get_or_insert_with(y, OrderSet::new).insert((x, s));
         let links = self.back_links.get(&y).unwrap_or_else(|| panic!("Node {} have no backlink!", x.0));
         debug_assert!(links.contains(&(x, s)));
       }
     }
     for (k, v) in back_links.iter() {
-      let Some(v2) = self.back_links.get(k) else { panic!("Key {:?} not in back_links {:?}", k, self.back_links) };
+      let Some(v2) = self.back_links.get(&k) else { panic!("Key {:?} not in back_links {:?}", k, self.back_links) };
       if !v2.set_eq(v) {
         panic!("Backlink not equal {:?} expect {:?}", v2, v);
       }
@@ -513,211 +827,67 @@ where
   pub fn check_backlinks(&self) {}
 
   fn do_commit(&mut self, t: Transaction<NodeT, Arena>) -> LinkChangeRecorder<NodeT> {
-    debug_assert!(t.ctx_id == self.ctx_id, "The transaction and the graph are from different context!");
-    debug_assert!(t.alloc_nodes.is_empty(), "There are unfilled allocated nodes");
-
-    let mut lcr = LinkChangeRecorder::default();
-
-    self.redirect_links_vec(t.redirect_links_vec, &mut lcr);
-    self.merge_nodes(t.inc_nodes, &mut lcr);
-    for (i, f) in t.mut_nodes {
-      self.modify_node(i, f, &mut lcr);
-    }
-    for (i, f) in t.update_nodes {
-      self.update_node(i, f, &mut lcr);
-    }
-    self.redirect_links_vec(t.redirect_all_links_vec, &mut lcr);
-    for n in &t.dec_nodes {
-      self.remove_node(*n, &mut lcr);
-    }
-
-    self.apply_bidirectional_links(&lcr);
-    lcr
-  }
-
-  fn merge_nodes(&mut self, nodes: Arena, lcr: &mut LinkChangeRecorder<NodeT>) {
-    for (x, n) in nodes.iter() {
-      self.add_back_links(x, n);
-      for (y, s) in n.iter_sources() {
-        lcr.add_link(x, y, NodeT::to_link_mirror_enum(s));
-      }
-    }
-
-    self.nodes.merge(nodes);
-  }
-
-  fn remove_node(&mut self, x: NodeIndex, lcr: &mut LinkChangeRecorder<NodeT>) {
-    let n = self.nodes.remove(x).expect("Remove a non-existing node!");
-    for (y, s) in n.iter_sources() {
-      lcr.remove_link(x, y, NodeT::to_link_mirror_enum(s));
-    }
-    self.remove_back_links(x, &n);
-    for (y, s) in self.back_links.swap_remove(&x).unwrap() {
-      self.nodes.get_mut(y).unwrap().modify_link(s, x, NodeIndex::empty());
-      lcr.remove_link(y, x, NodeT::to_link_mirror_enum(s));
-    }
-  }
-
-  fn modify_node<F>(&mut self, x: NodeIndex, f: F, lcr: &mut LinkChangeRecorder<NodeT>)
-  where
-    F: FnOnce(&mut NodeT),
-  {
-    for (y, s) in self.nodes.get(x).unwrap().iter_sources() {
-      self.back_links.get_mut(&y).unwrap().swap_remove(&(x, s));
-      lcr.remove_link(x, y, NodeT::to_link_mirror_enum(s));
-    }
-
-    f(self.nodes.get_mut(x).unwrap());
-
-    for (y, s) in self.nodes.get(x).unwrap().iter_sources() {
-      self.back_links.get_mut(&y).unwrap().insert((x, s));
-      lcr.add_link(x, y, NodeT::to_link_mirror_enum(s));
-    }
-  }
-
-  fn update_node<F>(&mut self, x: NodeIndex, f: F, lcr: &mut LinkChangeRecorder<NodeT>)
-  where
-    F: FnOnce(NodeT) -> NodeT,
-  {
-    for (y, s) in self.nodes.get(x).unwrap().iter_sources() {
-      self.back_links.get_mut(&y).unwrap().swap_remove(&(x, s));
-      lcr.remove_link(x, y, NodeT::to_link_mirror_enum(s));
-    }
-
-    self.nodes.update_with(x, f);
-
-    for (y, s) in self.nodes.get(x).unwrap().iter_sources() {
-      self.back_links.get_mut(&y).unwrap().insert((x, s));
-      lcr.add_link(x, y, NodeT::to_link_mirror_enum(s));
-    }
-  }
-
-  fn redirect_links(&mut self, old_node: NodeIndex, new_node: NodeIndex, lcr: &mut LinkChangeRecorder<NodeT>) {
-    let old_link = self.back_links.swap_remove(&old_node).unwrap();
-    self.back_links.insert(old_node, OrderSet::new());
-
-    let new_link = self.back_links.entry(new_node).or_default();
-    for (y, s) in old_link {
-      new_link.insert((y, s));
-      let result = self.nodes.get_mut(y).unwrap().modify_link(s, old_node, new_node);
-      // add: if (added) {new_idx} else {ttgraph::NodeIndex::empty()},
-      // remove: if (removed) {old_idx} else {ttgraph::NodeIndex::empty()},
-      if result.added {
-        lcr.add_link(y, new_node, NodeT::to_link_mirror_enum(s));
-      }
-      if result.removed {
-        lcr.remove_link(y, old_node, NodeT::to_link_mirror_enum(s));
-      }
+    if let Err(conflict) = self.check_conflict(&t) {
+      panic!("{}", conflict);
     }
+    self.apply_commit(t)
   }
 
-  fn redirect_links_vec(&mut self, replacements: Vec<(NodeIndex, NodeIndex)>, lcr: &mut LinkChangeRecorder<NodeT>) {
-    let mut fa = OrderMap::new();
-
-    for (old, new) in &replacements {
-      fa.entry(*old).or_insert(*old);
-      fa.entry(*new).or_insert(*new);
-    }
-
-    for (old, new) in &replacements {
-      let mut x = *new;
-      while fa[&x] != x {
-        x = fa[&x];
-      }
-      assert!(x != *old, "Loop redirection detected!");
-      *fa.get_mut(old).unwrap() = x;
+  /// Commit a transaction already checked by [`check_conflict`](Graph::check_conflict)
+  fn apply_commit(&mut self, t: Transaction<NodeT, Arena>) -> LinkChangeRecorder<NodeT> {
+    debug_assert!(t.ctx_id == self.ctx_id, "The transaction and the graph are from different context!");
+    debug_assert!(t.alloc_nodes.is_empty(), "There are unfilled allocated nodes");
+    // Only possible with indexes given from outside, e.g. by Op::Insert
+    if let Some((x, _)) = t.inc_nodes.iter().find(|(x, _)| self.nodes.contains(*x)) {
+      panic!("Insert an existing index {:?}", x);
     }
 
-    for (old, new) in &replacements {
-      let mut x = *new;
-      let mut y = fa[&x];
-      while x != y {
-        x = y;
-        y = fa[&y];
-      }
-
-      self.redirect_links(*old, x, lcr);
+    let mut written = t.written_nodes();
 
-      x = *new;
-      while fa[&x] != y {
-        let z = fa[&x];
-        *fa.get_mut(&x).unwrap() = y;
-        x = z;
-      }
-    }
+    self.refresh_indexes();
+    let changes = apply::Changes {
+      redirect_links_vec: t.redirect_links_vec,
+      inc_nodes: t.inc_nodes.into_iter(),
+      mut_nodes: t.mut_nodes,
+      update_nodes: t.update_nodes,
+      redirect_all_links_vec: t.redirect_all_links_vec,
+      dec_nodes: Vec::from_iter(t.dec_nodes),
+    };
+    let lcr = apply::apply_changes(self, changes);
+    written.extend(lcr.adds.iter().chain(lcr.removes.iter()).flat_map(|(x, y, _)| [*x, *y]));
+    self.record_versions(written);
+    lcr
   }
 
-  fn apply_bidirectional_links(&mut self, lcr: &LinkChangeRecorder<NodeT>) {
-    for &(x, y, l) in &lcr.removes {
-      if !self.nodes.contains(x) || !self.nodes.contains(y) {
-        continue;
-      }
-
-      let bds = self.nodes.get(x).unwrap().get_bidiretional_link_mirrors_of(l);
-      let bds = self.nodes.get(y).unwrap().match_bd_link_group(bds);
-      for link in bds {
-        if self.nodes.get_mut(y).unwrap().remove_link(link, x) {
-          self.remove_back_link(y, x, NodeT::to_source_enum(link));
-        }
-      }
+  /// Link types are checked only with the `debug` feature
+  fn check_link_type(&self, lcr: &LinkChangeRecorder<NodeT>) {
+    if !cfg!(feature = "debug") {
+      return;
     }
-
-    for &(x, y, l) in &lcr.adds {
-      if !self.nodes.contains(x) || !self.nodes.contains(y) {
-        continue;
-      }
-
-      let bds = self.nodes.get(x).unwrap().get_bidiretional_link_mirrors_of(l);
-      let bds = self.nodes.get(y).unwrap().match_bd_link_group(bds);
-      if bds.is_empty() {
-        continue;
-      }
-
-      let node = self.nodes.get(y).unwrap();
-      let found = bds.iter().any(|link| node.contains_link(*link, x));
-
-      if !found {
-        assert!(bds.len() == 1, "Node with multiple choices for bidiretional link detected!");
-        let link = bds.first().unwrap();
-        if self.nodes.get_mut(y).unwrap().add_link(*link, x) {
-          self.add_back_link(y, x, NodeT::to_source_enum(*link));
+    for (_, y, l) in &lcr.adds {
+      if let Some(node) = self.nodes.get(*y) {
+        if let Result::Err(err) = NodeT::check_link_type(node.discriminant(), *l) {
+          panic!("Link type check failed! Link {:?} expect {:?}, found {:?}", err.link, err.expect, err.found);
         }
       }
     }
   }
 
-  fn add_back_link(&mut self, x: NodeIndex, y: NodeIndex, src: NodeT::SourceEnum) {
-    self.back_links.entry(y).or_default().insert((x, src));
-  }
-
-  fn add_back_links(&mut self, x: NodeIndex, n: &NodeT) {
-    self.back_links.entry(x).or_default();
-    for (y, s) in n.iter_sources() {
-      self.back_links.entry(y).or_default().insert((x, s));
-    }
-  }
-
-  fn remove_back_link(&mut self, x: NodeIndex, y: NodeIndex, src: NodeT::SourceEnum) {
-    self.back_links.get_mut(&y).unwrap().swap_remove(&(x, src));
+  fn refresh_indexes(&mut self) {
+    self.indexes.get_mut().unwrap().refresh(&self.nodes);
   }
 
-  fn remove_back_links(&mut self, x: NodeIndex, n: &NodeT) {
-    for (y, s) in n.iter_sources() {
-      self.back_links.get_mut(&y).unwrap().swap_remove(&(x, s));
-    }
-  }
-
-  fn check_link_type(&self, lcr: &LinkChangeRecorder<NodeT>) {
-    for (_, y, l) in &lcr.adds {
-      if let Some(node) = self.nodes.get(*y) {
-        if let Result::Err(err) = NodeT::check_link_type(node.discriminant(), *l) {
-          panic!("Link type check failed! Link {:?} expect {:?}, found {:?}", err.link, err.expect, err.found);
+  fn check_unique_indexes(&mut self) {
+    let indexes = self.indexes.get_mut().unwrap();
+    for x in indexes.take_pending() {
+      if let Some(node) = self.nodes.get(x) {
+        if let Some((name, nodes)) = indexes.check_unique(node).first() {
+          panic!("Unique index check failed! Index {} have multiple nodes {:?} with the same key", name, nodes);
         }
       }
     }
   }
 
-  #[cfg(feature = "debug")]
   fn check_change<'a>(&self, lcr: &LinkChangeRecorder<NodeT>, checks: &'a GraphCheck<NodeT>) -> Vec<&'a str> {
     let mut failed = Vec::new();
     let mut changed_nodes = OrderSet::new();
@@ -752,21 +922,229 @@ where
   }
 
   pub(crate) fn do_deserialize(ctx: &Context, nodes: Vec<(NodeIndex, NodeT)>) -> Self {
-    let arena = Arena::new_from_iter(ctx.node_dist.clone(), nodes);
+    Self::from_arena(ctx, Arena::new_from_iter(ctx.node_dist.clone(), nodes))
+  }
+
+  pub(crate) fn from_arena(ctx: &Context, arena: Arena) -> Self {
     let mut lcr = LinkChangeRecorder::default();
     let mut graph = Self::new(ctx);
-    graph.merge_nodes(arena, &mut lcr);
-    graph.apply_bidirectional_links(&lcr);
+    apply::insert_nodes(&mut graph, arena.into_iter(), &mut lcr);
+    apply::apply_bidirectional_links(&mut graph, &mut lcr);
     graph
   }
 }
 
+impl<NodeT, Arena> Graph<NodeT, Arena>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Make a cheap copy of the graph, which shares the unchanged nodes with this graph.
+  ///
+  /// The nodes, the back links, the node versions and the indexes are stored in [`PersistentMap`]s, so forking is O(1).
+  /// A change in either graph copies only the trie nodes on the path to each changed entry, i.e. O(log N) per change,
+  /// and the rest stays shared.
+  /// Useful for speculative modification: commit to the fork, and simply drop it if the result is not wanted.
+  ///
+  /// The fork has the same context as this graph, so the same transaction can be committed to either of them.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode, Clone)]
+  /// struct NodeA{
+  ///   data: usize,
+  /// }
+  /// node_enum!{
+  ///   #[derive(Clone)]
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let idx = trans.insert(Node::A(NodeA{ data: 1 }));
+  /// graph.commit(trans);
+  ///
+  /// let mut fork = graph.fork();
+  /// let mut trans = Transaction::new(&ctx);
+  /// mut_node!(trans, Node::A, idx, |x| { x.data = 2 });
+  /// fork.commit(trans);
+  ///
+  /// assert_eq!(get_node!(graph, Node::A, idx).unwrap().data, 1);
+  /// assert_eq!(get_node!(fork, Node::A, idx).unwrap().data, 2);
+  /// # }
+  /// ```
+  pub fn fork(&self) -> Self {
+    Graph {
+      ctx_id: self.ctx_id,
+      nodes: self.nodes.fork(),
+      back_links: self.back_links.clone(),
+      indexes: Mutex::new(self.indexes.lock().unwrap().clone()),
+      version: self.version,
+      node_versions: self.node_versions.clone(),
+    }
+  }
+
+  /// Copy a part of the graph into a new graph of the context `ctx`, e.g. to test a pass on a small slice of a large graph.
+  ///
+  /// The copied nodes are the `roots` and the nodes reachable from them by following the links.
+  /// If `links` is given, only the links of these names are followed, so `Some(&[])` copies exactly the `roots`.
+  ///
+  /// The links between the copied nodes point to the copies. The links to the nodes not copied are cut:
+  /// a single link becomes empty, and the target is removed from a set or vec.
+  /// Returns the new graph and the map from the indexes in this graph to the indexes in the new graph.
+  ///
+  /// # Panic
+  /// Panic if a root is not in the graph.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode, Clone)]
+  /// struct NodeA{
+  ///   next: NodeIndex,
+  ///   other: NodeIndex,
+  /// }
+  /// node_enum!{
+  ///   #[derive(Clone)]
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let c = trans.insert(Node::A(NodeA{ next: NodeIndex::empty(), other: NodeIndex::empty() }));
+  /// let b = trans.insert(Node::A(NodeA{ next: NodeIndex::empty(), other: NodeIndex::empty() }));
+  /// let a = trans.insert(Node::A(NodeA{ next: b, other: c }));
+  /// graph.commit(trans);
+  ///
+  /// let ctx2 = Context::new();
+  /// let (sub, id_map) = graph.extract(&ctx2, [a], Some(&["next"]));
+  /// assert_eq!(sub.len(), 2);
+  /// let node = get_node!(sub, Node::A, id_map[&a]).unwrap();
+  /// assert_eq!(node.next, id_map[&b]);
+  /// // c is not copied
+  /// assert!(node.other.is_empty());
+  /// # }
+  /// ```
+  pub fn extract(
+    &self, ctx: &Context, roots: impl IntoIterator<Item = NodeIndex>, links: Option<&[&str]>,
+  ) -> (Graph<NodeT, Arena>, IdMap) {
+    let mut visited = OrderSet::new();
+    let mut queue = Vec::new();
+    for x in roots {
+      assert!(self.contains(x), "Extract from a non-existing node {}!", x);
+      if visited.insert(x) {
+        queue.push(x);
+      }
+    }
+    while let Some(x) = queue.pop() {
+      let node = self.get(x).unwrap();
+      for name in NodeT::link_names_of(node.discriminant()) {
+        if links.is_some_and(|links| !links.contains(name)) {
+          continue;
+        }
+        for y in node.get_links_by_name(name) {
+          if self.contains(y) && visited.insert(y) {
+            queue.push(y);
+          }
+        }
+      }
+    }
+
+    let mut trans = Transaction::<NodeT, Arena>::new(ctx);
+    let mut id_map = IdMap::new();
+    for x in &visited {
+      id_map.insert(*x, trans.alloc(self.get(*x).unwrap().discriminant()));
+    }
+    for (x, new_x) in &id_map {
+      let mut node = self.get(*x).unwrap().clone();
+      remap_links(&mut node, |y| id_map.get(&y).copied());
+      trans.fill_back(*new_x, node);
+    }
+    let mut result = Graph::new(ctx);
+    result.commit(trans);
+    (result, id_map)
+  }
+}
+
+/// Rewrite the non-empty link targets of a node, removing the targets mapped to `None`
+pub(crate) fn remap_links<NodeT: NodeEnum>(node: &mut NodeT, f: impl Fn(NodeIndex) -> Option<NodeIndex>) {
+  for name in NodeT::link_names_of(node.discriminant()) {
+    let targets = node.get_links_by_name(name).filter_map(|y| if y.is_empty() { Some(y) } else { f(y) });
+    let targets = Vec::from_iter(targets);
+    node.set_links_by_name(name, targets);
+  }
+}
+
+impl<NodeT, Arena> NodeStore<NodeT> for Graph<NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  fn node(&self, x: NodeIndex) -> Option<&NodeT> {
+    self.nodes.get(x)
+  }
+
+  fn node_mut(&mut self, x: NodeIndex) -> &mut NodeT {
+    self.nodes.get_mut(x).unwrap()
+  }
+
+  fn insert_node(&mut self, x: NodeIndex, node: NodeT) {
+    self.indexes.get_mut().unwrap().insert(x, &node);
+    self.nodes.fill_back_untyped(x, node);
+  }
+
+  fn remove_node(&mut self, x: NodeIndex) -> NodeT {
+    let n = self.nodes.remove(x).expect("Remove a non-existing node!");
+    self.indexes.get_mut().unwrap().remove(x, &n);
+    n
+  }
+
+  fn update_node(&mut self, x: NodeIndex, f: impl FnOnce(NodeT) -> NodeT) {
+    self.nodes.update_with(x, f);
+  }
+
+  fn back_links_mut(&mut self, x: NodeIndex) -> &mut OrderSet<(NodeIndex, NodeT::SourceEnum)> {
+    self.back_links.get_or_insert_with(x, OrderSet::new)
+  }
+
+  fn take_back_links(&mut self, x: NodeIndex) -> OrderSet<(NodeIndex, NodeT::SourceEnum)> {
+    self.back_links.remove(&x).unwrap()
+  }
+
+  fn unindex(&mut self, x: NodeIndex) {
+    self.indexes.get_mut().unwrap().remove(x, self.nodes.get(x).unwrap());
+  }
+
+  fn index(&mut self, x: NodeIndex) {
+    self.indexes.get_mut().unwrap().insert(x, self.nodes.get(x).unwrap());
+  }
+}
+
+impl<NodeT, Arena> Clone for Graph<NodeT, Arena>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Same as [`fork`](Graph::fork)
+  fn clone(&self) -> Self {
+    self.fork()
+  }
+}
+
 struct LinkChangeRecorder<NodeT: NodeEnum> {
   adds: OrderSet<(NodeIndex, NodeIndex, NodeT::LinkMirrorEnum)>,
   removes: OrderSet<(NodeIndex, NodeIndex, NodeT::LinkMirrorEnum)>,
 }
 impl<NodeT: NodeEnum> LinkChangeRecorder<NodeT> {
-  #[cfg(feature = "debug")]
   fn add_link(&mut self, x: NodeIndex, y: NodeIndex, l: NodeT::LinkMirrorEnum) {
     if y.is_empty() {
       return;
@@ -778,10 +1156,6 @@ impl<NodeT: NodeEnum> LinkChangeRecorder<NodeT> {
     }
   }
 
-  #[cfg(not(feature = "debug"))]
-  fn add_link(&mut self, x: NodeIndex, y: NodeIndex, l: NodeT::LinkMirrorEnum) {}
-
-  #[cfg(feature = "debug")]
   fn remove_link(&mut self, x: NodeIndex, y: NodeIndex, l: NodeT::LinkMirrorEnum) {
     if y.is_empty() {
       return;
@@ -793,8 +1167,14 @@ impl<NodeT: NodeEnum> LinkChangeRecorder<NodeT> {
     }
   }
 
-  #[cfg(not(feature = "debug"))]
-  fn remove_link(&mut self, x: NodeIndex, y: NodeIndex, l: NodeT::LinkMirrorEnum) {}
+  fn merge(&mut self, other: LinkChangeRecorder<NodeT>) {
+    for (x, y, l) in other.removes {
+      self.remove_link(x, y, l);
+    }
+    for (x, y, l) in other.adds {
+      self.add_link(x, y, l);
+    }
+  }
 }
 impl<NodeT: NodeEnum> Default for LinkChangeRecorder<NodeT> {
   fn default() -> Self {
@@ -826,18 +1206,18 @@ impl<'a, T: NodeEnum + 'static, A: CateArena<V = T, D = T::Discriminant>> IntoIt
 pub struct NodeIterator<'a, V, I>
 where
   V: NodeEnum + 'static,
-  I: Iterator<Item = (&'a usize, &'a V)> + ExactSizeIterator + FusedIterator,
+  I: Iterator<Item = (usize, &'a V)> + ExactSizeIterator + FusedIterator,
 {
   iter: I,
 }
 impl<'a, V, I> Iterator for NodeIterator<'a, V, I>
 where
   V: NodeEnum + 'static,
-  I: Iterator<Item = (&'a usize, &'a V)> + ExactSizeIterator + FusedIterator,
+  I: Iterator<Item = (usize, &'a V)> + ExactSizeIterator + FusedIterator,
 {
   type Item = (NodeIndex, &'a V);
   fn next(&mut self) -> Option<Self::Item> {
-    self.iter.next().map(|(k, v)| (NodeIndex(*k), v))
+    self.iter.next().map(|(k, v)| (NodeIndex(k), v))
   }
   fn size_hint(&self) -> (usize, Option<usize>) {
     self.iter.size_hint()
@@ -846,13 +1226,13 @@ where
 impl<'a, V, I> ExactSizeIterator for NodeIterator<'a, V, I>
 where
   V: NodeEnum + 'static,
-  I: Iterator<Item = (&'a usize, &'a V)> + ExactSizeIterator + FusedIterator,
+  I: Iterator<Item = (usize, &'a V)> + ExactSizeIterator + FusedIterator,
 {
 }
 impl<'a, V, I> FusedIterator for NodeIterator<'a, V, I>
 where
   V: NodeEnum + 'static,
-  I: Iterator<Item = (&'a usize, &'a V)> + ExactSizeIterator + FusedIterator,
+  I: Iterator<Item = (usize, &'a V)> + ExactSizeIterator + FusedIterator,
 {
 }
 
@@ -861,6 +1241,56 @@ pub type MutFunc<'a, T> = Box<dyn FnOnce(&mut T) + 'a>;
 /// Type alias to be used in [`update`](Transaction::update), intented to be used in macros
 pub type UpdateFunc<'a, T> = Box<dyn FnOnce(T) -> T + 'a>;
 
+/// The kind of closures stored by a [`Transaction`],
+/// given to [`mutate`](Transaction::mutate) and [`update`](Transaction::update)
+///
+/// [`LocalFuncs`] is the default. [`SendFuncs`] requires the closures to be [`Send`], see [`SendTransaction`].
+pub trait FuncKind<'a, T> {
+  /// The boxed closure of `mutate`
+  type Mut: FnOnce(&mut T);
+  /// The boxed closure of `update`
+  type Update: FnOnce(T) -> T;
+}
+
+/// The default [`FuncKind`], storing [`MutFunc`] and [`UpdateFunc`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalFuncs;
+
+impl<'a, T> FuncKind<'a, T> for LocalFuncs {
+  type Mut = MutFunc<'a, T>;
+  type Update = UpdateFunc<'a, T>;
+}
+
+/// A closure which can be given to [`mutate`](Transaction::mutate) of a transaction storing `Funcs`
+pub trait IntoMutFunc<'a, T, Funcs: FuncKind<'a, T>> {
+  /// Box the closure
+  fn into_func(self) -> Funcs::Mut;
+}
+
+impl<'a, T, F> IntoMutFunc<'a, T, LocalFuncs> for F
+where
+  F: FnOnce(&mut T) + 'a,
+{
+  fn into_func(self) -> MutFunc<'a, T> {
+    Box::new(self)
+  }
+}
+
+/// A closure which can be given to [`update`](Transaction::update) of a transaction storing `Funcs`
+pub trait IntoUpdateFunc<'a, T, Funcs: FuncKind<'a, T>> {
+  /// Box the closure
+  fn into_func(self) -> Funcs::Update;
+}
+
+impl<'a, T, F> IntoUpdateFunc<'a, T, LocalFuncs> for F
+where
+  F: FnOnce(T) -> T + 'a,
+{
+  fn into_func(self) -> UpdateFunc<'a, T> {
+    Box::new(self)
+  }
+}
+
 /// Context for typed graph
 /// Transactions and graph must have the same context to ensure the correctness of NodeIndex
 #[derive(Debug, Clone, Default)]
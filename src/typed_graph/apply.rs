@@ -0,0 +1,225 @@
+//! Apply the changes of a transaction to the nodes and their back links, shared by [`Graph`] and [`TransactionView`]
+
+use super::*;
+
+type BackLinkSet<NodeT> = OrderSet<(NodeIndex, <NodeT as NodeEnum>::SourceEnum)>;
+
+/// The storage of the nodes and the back links changed by a commit
+pub(crate) trait NodeStore<NodeT: NodeEnum> {
+  fn node(&self, x: NodeIndex) -> Option<&NodeT>;
+  /// Get a node for modification, panics if it does not exist
+  fn node_mut(&mut self, x: NodeIndex) -> &mut NodeT;
+  fn insert_node(&mut self, x: NodeIndex, node: NodeT);
+  /// Remove a node, panics if it does not exist
+  fn remove_node(&mut self, x: NodeIndex) -> NodeT;
+  fn update_node(&mut self, x: NodeIndex, f: impl FnOnce(NodeT) -> NodeT);
+  /// Get the back links of a node, an empty set is made if there is none
+  fn back_links_mut(&mut self, x: NodeIndex) -> &mut BackLinkSet<NodeT>;
+  /// Remove the back links of a node, panics if there is none
+  fn take_back_links(&mut self, x: NodeIndex) -> BackLinkSet<NodeT>;
+  /// The data of the node is going to be changed, remove it from the indexes
+  fn unindex(&mut self, _x: NodeIndex) {}
+  /// The data of the node is changed, insert it into the indexes
+  fn index(&mut self, _x: NodeIndex) {}
+}
+
+/// The changes of a transaction, in the order they are applied
+pub(crate) struct Changes<I, M, U> {
+  pub(crate) redirect_links_vec: Vec<(NodeIndex, NodeIndex)>,
+  pub(crate) inc_nodes: I,
+  pub(crate) mut_nodes: M,
+  pub(crate) update_nodes: U,
+  pub(crate) redirect_all_links_vec: Vec<(NodeIndex, NodeIndex)>,
+  pub(crate) dec_nodes: Vec<NodeIndex>,
+}
+
+/// Apply the changes in the order described in [`commit`](Graph::commit), including the bidirectional links
+pub(crate) fn apply_changes<NodeT, S, I, M, MF, U, UF>(
+  store: &mut S, changes: Changes<I, M, U>,
+) -> LinkChangeRecorder<NodeT>
+where
+  NodeT: NodeEnum,
+  S: NodeStore<NodeT>,
+  I: IntoIterator<Item = (NodeIndex, NodeT)>,
+  M: IntoIterator<Item = (NodeIndex, MF)>,
+  MF: FnOnce(&mut NodeT),
+  U: IntoIterator<Item = (NodeIndex, UF)>,
+  UF: FnOnce(NodeT) -> NodeT,
+{
+  let mut lcr = LinkChangeRecorder::default();
+  redirect_links_vec(store, changes.redirect_links_vec, &mut lcr);
+  insert_nodes(store, changes.inc_nodes, &mut lcr);
+  for (i, f) in changes.mut_nodes {
+    modify_node(store, i, f, &mut lcr);
+  }
+  for (i, f) in changes.update_nodes {
+    update_node(store, i, f, &mut lcr);
+  }
+  redirect_links_vec(store, changes.redirect_all_links_vec, &mut lcr);
+  for n in changes.dec_nodes {
+    remove_node(store, n, &mut lcr);
+  }
+  apply_bidirectional_links(store, &mut lcr);
+  lcr
+}
+
+pub(crate) fn insert_nodes<NodeT: NodeEnum, S: NodeStore<NodeT>>(
+  store: &mut S, nodes: impl IntoIterator<Item = (NodeIndex, NodeT)>, lcr: &mut LinkChangeRecorder<NodeT>,
+) {
+  for (x, n) in nodes {
+    store.back_links_mut(x);
+    for (y, s) in n.iter_sources() {
+      store.back_links_mut(y).insert((x, s));
+      lcr.add_link(x, y, NodeT::to_link_mirror_enum(s));
+    }
+    store.insert_node(x, n);
+  }
+}
+
+fn remove_node<NodeT: NodeEnum, S: NodeStore<NodeT>>(store: &mut S, x: NodeIndex, lcr: &mut LinkChangeRecorder<NodeT>) {
+  let n = store.remove_node(x);
+  for (y, s) in n.iter_sources() {
+    lcr.remove_link(x, y, NodeT::to_link_mirror_enum(s));
+    store.back_links_mut(y).swap_remove(&(x, s));
+  }
+  for (y, s) in store.take_back_links(x) {
+    store.node_mut(y).modify_link(s, x, NodeIndex::empty());
+    lcr.remove_link(y, x, NodeT::to_link_mirror_enum(s));
+  }
+}
+
+/// Remove the back links of the links from `x`, before the links are changed
+fn unlink<NodeT: NodeEnum, S: NodeStore<NodeT>>(store: &mut S, x: NodeIndex, lcr: &mut LinkChangeRecorder<NodeT>) {
+  for (y, s) in store.node(x).unwrap().iter_sources() {
+    store.back_links_mut(y).swap_remove(&(x, s));
+    lcr.remove_link(x, y, NodeT::to_link_mirror_enum(s));
+  }
+  store.unindex(x);
+}
+
+/// Add the back links of the links from `x`, after the links are changed
+fn relink<NodeT: NodeEnum, S: NodeStore<NodeT>>(store: &mut S, x: NodeIndex, lcr: &mut LinkChangeRecorder<NodeT>) {
+  for (y, s) in store.node(x).unwrap().iter_sources() {
+    store.back_links_mut(y).insert((x, s));
+    lcr.add_link(x, y, NodeT::to_link_mirror_enum(s));
+  }
+  store.index(x);
+}
+
+fn modify_node<NodeT: NodeEnum, S: NodeStore<NodeT>>(
+  store: &mut S, x: NodeIndex, f: impl FnOnce(&mut NodeT), lcr: &mut LinkChangeRecorder<NodeT>,
+) {
+  unlink(store, x, lcr);
+  f(store.node_mut(x));
+  relink(store, x, lcr);
+}
+
+fn update_node<NodeT: NodeEnum, S: NodeStore<NodeT>>(
+  store: &mut S, x: NodeIndex, f: impl FnOnce(NodeT) -> NodeT, lcr: &mut LinkChangeRecorder<NodeT>,
+) {
+  unlink(store, x, lcr);
+  store.update_node(x, f);
+  relink(store, x, lcr);
+}
+
+fn redirect_links<NodeT: NodeEnum, S: NodeStore<NodeT>>(
+  store: &mut S, old_node: NodeIndex, new_node: NodeIndex, lcr: &mut LinkChangeRecorder<NodeT>,
+) {
+  let old_link = store.take_back_links(old_node);
+  store.back_links_mut(old_node);
+
+  for (y, s) in old_link {
+    store.back_links_mut(new_node).insert((y, s));
+    let result = store.node_mut(y).modify_link(s, old_node, new_node);
+    // add: if (added) {new_idx} else {ttgraph::NodeIndex::empty()},
+    // remove: if (removed) {old_idx} else {ttgraph::NodeIndex::empty()},
+    if result.added {
+      lcr.add_link(y, new_node, NodeT::to_link_mirror_enum(s));
+    }
+    if result.removed {
+      lcr.remove_link(y, old_node, NodeT::to_link_mirror_enum(s));
+    }
+  }
+}
+
+fn redirect_links_vec<NodeT: NodeEnum, S: NodeStore<NodeT>>(
+  store: &mut S, replacements: Vec<(NodeIndex, NodeIndex)>, lcr: &mut LinkChangeRecorder<NodeT>,
+) {
+  let mut fa = OrderMap::new();
+
+  for (old, new) in &replacements {
+    fa.entry(*old).or_insert(*old);
+    fa.entry(*new).or_insert(*new);
+  }
+
+  for (old, new) in &replacements {
+    let mut x = *new;
+    while fa[&x] != x {
+      x = fa[&x];
+    }
+    assert!(x != *old, "Loop redirection detected!");
+    *fa.get_mut(old).unwrap() = x;
+  }
+
+  for (old, new) in &replacements {
+    let mut x = *new;
+    let mut y = fa[&x];
+    while x != y {
+      x = y;
+      y = fa[&y];
+    }
+
+    redirect_links(store, *old, x, lcr);
+
+    x = *new;
+    while fa[&x] != y {
+      let z = fa[&x];
+      *fa.get_mut(&x).unwrap() = y;
+      x = z;
+    }
+  }
+}
+
+pub(crate) fn apply_bidirectional_links<NodeT: NodeEnum, S: NodeStore<NodeT>>(
+  store: &mut S, lcr: &mut LinkChangeRecorder<NodeT>,
+) {
+  // Links changed here are recorded separately, then merged into lcr
+  let mut fixups = LinkChangeRecorder::default();
+  for &(x, y, l) in &lcr.removes {
+    let (Some(nx), Some(ny)) = (store.node(x), store.node(y)) else {
+      continue;
+    };
+
+    let bds = ny.match_bd_link_group(nx.get_bidiretional_link_mirrors_of(l));
+    for link in bds {
+      if store.node_mut(y).remove_link(link, x) {
+        store.back_links_mut(x).swap_remove(&(y, NodeT::to_source_enum(link)));
+        fixups.remove_link(y, x, link);
+      }
+    }
+  }
+
+  for &(x, y, l) in &lcr.adds {
+    let (Some(nx), Some(ny)) = (store.node(x), store.node(y)) else {
+      continue;
+    };
+
+    let bds = ny.match_bd_link_group(nx.get_bidiretional_link_mirrors_of(l));
+    if bds.is_empty() {
+      continue;
+    }
+
+    let found = bds.iter().any(|link| ny.contains_link(*link, x));
+
+    if !found {
+      assert!(bds.len() == 1, "Node with multiple choices for bidiretional link detected!");
+      let link = bds.first().unwrap();
+      if store.node_mut(y).add_link(*link, x) {
+        store.back_links_mut(x).insert((y, NodeT::to_source_enum(*link)));
+        fixups.add_link(y, x, *link);
+      }
+    }
+  }
+
+  lcr.merge(fixups);
+}
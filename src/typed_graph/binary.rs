@@ -0,0 +1,284 @@
+//! A compact binary format of a [`Graph`], written and read as a stream
+//!
+//! [`write_binary`] writes the nodes directly from the graph, and [`read_binary`] puts them directly into a new graph,
+//! so no intermediate `Vec` of nodes like [`GraphSerializer`](crate::serialize::GraphSerializer) is needed.
+//!
+//! The layout is:
+//! + The magic `TTGB` and the format version 2
+//! + The 16 bytes of the context id, and the [`hash`](GraphSchema::hash) of the schema as 8 little endian bytes
+//! + The number of sections, one for each variant in the order of declaration, same as the containers of the [`CateArena`]
+//! + In each section: the number of nodes, then for each node the difference from the previous index, the links,
+//!   the length of the payload and the payload
+//! + The links of a node: for each link of the variant in the order of declaration, the number of targets,
+//!   then the difference of each target from the index of the node
+//!
+//! All numbers except the hash are varints.
+//! The differences are zigzag encoded, as the nodes are in the order of the arena and the targets are usually close to
+//! their sources.
+//! The payload is the struct inside the variant, with its links left empty, encoded by a [`BinaryCodec`].
+//! The codec can wrap any serde serializer, e.g. `bincode` or `postcard`.
+//!
+//! The graph read by [`read_binary`] has a new [`Context`] with the same id, and is checked like
+//! [`try_deserialize_graph`](crate::serialize::try_deserialize_graph).
+//! A file of a different schema is rejected, since the payloads of a binary codec can not be migrated.
+
+use std::io::{self, Read, Write};
+
+use serde::de::{DeserializeOwned, DeserializeSeed};
+
+use super::serialize::{validate_links, DeserializeError};
+use super::*;
+
+mod payload;
+use payload::{Payload, PayloadSeed};
+
+const MAGIC: &[u8; 4] = b"TTGB";
+const FORMAT_VERSION: u8 = 2;
+
+/// Encode and decode the payload of a node, see [`binary`](self)
+///
+/// # Example
+/// A codec using `bincode` 1.x:
+/// ```ignore
+/// use bincode::Options;
+/// struct Bincode;
+/// impl BinaryCodec for Bincode {
+///   fn encode<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> io::Result<()> {
+///     bincode::options().serialize_into(buf, value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+///   }
+///   fn decode<'de, S: DeserializeSeed<'de>>(&self, seed: S, buf: &'de [u8]) -> io::Result<S::Value> {
+///     bincode::options().deserialize_seed(seed, buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+///   }
+/// }
+/// ```
+pub trait BinaryCodec {
+  /// Append the encoded value to `buf`
+  fn encode<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> io::Result<()>;
+  /// Decode a value from the whole `buf` with a seed, which provides the variant of the node
+  fn decode<'de, S: DeserializeSeed<'de>>(&self, seed: S, buf: &'de [u8]) -> io::Result<S::Value>;
+}
+
+/// A [`BinaryCodec`] using `serde_json`, available without other dependencies
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl BinaryCodec for JsonCodec {
+  fn encode<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> io::Result<()> {
+    serde_json::to_writer(buf, value).map_err(io::Error::from)
+  }
+
+  fn decode<'de, S: DeserializeSeed<'de>>(&self, seed: S, buf: &'de [u8]) -> io::Result<S::Value> {
+    let mut deserializer = serde_json::Deserializer::from_slice(buf);
+    let value = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+  }
+}
+
+/// Write the graph in the binary format, see [`binary`](self)
+///
+/// The writer is used for many small writes, wrap it with a [`BufWriter`](std::io::BufWriter) if it is a file.
+///
+/// # Example
+/// ```
+/// use ttgraph::{*, binary::*};
+/// use serde::{Serialize, Deserialize};
+/// #[derive(TypedNode, Serialize, Deserialize)]
+/// struct NodeA{
+///   next: NodeIndex,
+///   data: usize,
+/// }
+/// node_enum!{
+///   #[derive(Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let a = trans.insert(Node::A(NodeA{ next: NodeIndex::empty(), data: 1 }));
+/// let b = trans.insert(Node::A(NodeA{ next: a, data: 2 }));
+/// graph.commit(trans);
+///
+/// let mut buf = Vec::new();
+/// write_binary(&graph, &mut buf, &JsonCodec).unwrap();
+/// let (ctx2, graph2) = read_binary::<Node, _, _>(buf.as_slice(), &JsonCodec).unwrap();
+/// assert_eq!(get_node!(graph2, Node::A, b).unwrap().next, a);
+/// # }
+/// ```
+pub fn write_binary<NodeT, Arena, W, C>(graph: &Graph<NodeT, Arena>, mut writer: W, codec: &C) -> io::Result<()>
+where
+  NodeT: NodeEnum + Serialize,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  W: Write,
+  C: BinaryCodec,
+{
+  writer.write_all(MAGIC)?;
+  writer.write_all(&[FORMAT_VERSION])?;
+  writer.write_all(graph.ctx_id.as_bytes())?;
+  writer.write_all(&NodeT::schema().hash().to_le_bytes())?;
+
+  let discriminants = all_discriminants::<NodeT>();
+  write_varint(&mut writer, discriminants.len() as u64)?;
+  let mut buf = Vec::new();
+  for d in discriminants {
+    let container = graph.nodes.get_container(d);
+    write_varint(&mut writer, container.len() as u64)?;
+    let mut prev = 0;
+    for (idx, node) in container {
+      write_varint(&mut writer, zigzag(idx as i64 - prev as i64))?;
+      prev = idx;
+      for (name, ty) in NodeT::link_names_of(d).iter().zip(NodeT::link_types_of(d)) {
+        let targets = Vec::from_iter(node.get_links_by_name(name).filter(|y| *ty != LinkType::Point || !y.is_empty()));
+        write_varint(&mut writer, targets.len() as u64)?;
+        for y in targets {
+          write_varint(&mut writer, zigzag(y.0 as i64 - idx as i64))?;
+        }
+      }
+      buf.clear();
+      codec.encode(&Payload(node), &mut buf)?;
+      write_varint(&mut writer, buf.len() as u64)?;
+      writer.write_all(&buf)?;
+    }
+  }
+  Ok(())
+}
+
+/// Read a graph in the binary format and construct a context for it, see [`binary`](self) and [`write_binary`]
+///
+/// The reader is used for many small reads, wrap it with a [`BufReader`](std::io::BufReader) if it is a file.
+/// Malformed input and invalid graphs are reported as [`InvalidData`](io::ErrorKind::InvalidData),
+/// with a [`DeserializeError`] as the inner error for the latter.
+pub fn read_binary<NodeT, R, C>(mut reader: R, codec: &C) -> io::Result<(Context, Graph<NodeT>)>
+where
+  NodeT: NodeEnum + DeserializeOwned,
+  R: Read,
+  C: BinaryCodec,
+{
+  let mut magic = [0; 5];
+  reader.read_exact(&mut magic)?;
+  if &magic[..4] != MAGIC || magic[4] != FORMAT_VERSION {
+    return Err(invalid_data("Not a ttgraph binary file of a known version"));
+  }
+  let mut ctx_id = [0; 16];
+  reader.read_exact(&mut ctx_id)?;
+  let mut schema = [0; 8];
+  reader.read_exact(&mut schema)?;
+  if u64::from_le_bytes(schema) != NodeT::schema().hash() {
+    return Err(invalid_data("The file is written with a different schema"));
+  }
+
+  let discriminants = all_discriminants::<NodeT>();
+  if read_varint(&mut reader)? != discriminants.len() as u64 {
+    return Err(invalid_data("The number of sections does not match the node enum"));
+  }
+  let ctx = Context::from_id(Uuid::from_bytes(ctx_id), 0);
+  let mut arena = NodeT::GenArena::new(ctx.node_dist.clone());
+  let mut buf = Vec::new();
+  for (variant, d) in discriminants.into_iter().enumerate() {
+    let cnt = read_varint(&mut reader)?;
+    let mut prev: i64 = 0;
+    for _ in 0..cnt {
+      prev = prev
+        .checked_add(unzigzag(read_varint(&mut reader)?))
+        .filter(|x| *x >= 0)
+        .ok_or_else(|| invalid_data("Invalid node index"))?;
+      let idx = NodeIndex(prev as usize);
+      if idx.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, DeserializeError::EmptyIndex));
+      }
+      if arena.contains(idx) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, DeserializeError::DuplicateIndex(idx)));
+      }
+
+      let mut links = Vec::new();
+      for (name, ty) in NodeT::link_names_of(d).iter().zip(NodeT::link_types_of(d)) {
+        let cnt = read_varint(&mut reader)?;
+        if *ty == LinkType::Point && cnt > 1 {
+          return Err(invalid_data("Multiple targets of a single link"));
+        }
+        let mut targets = Vec::new();
+        for _ in 0..cnt {
+          let y = (idx.0 as i64)
+            .checked_add(unzigzag(read_varint(&mut reader)?))
+            .filter(|x| *x >= 0)
+            .ok_or_else(|| invalid_data("Invalid link target"))?;
+          targets.push(NodeIndex(y as usize));
+        }
+        links.push((*name, targets));
+      }
+
+      let len = read_varint(&mut reader)?;
+      buf.clear();
+      (&mut reader).take(len).read_to_end(&mut buf)?;
+      if buf.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+      }
+      let mut node: NodeT = codec.decode(PayloadSeed::new(variant as u32), &buf)?;
+      for (name, targets) in links {
+        node.set_links_by_name(name, targets);
+      }
+      ctx.node_dist.reserve(idx.0);
+      arena.fill_back_untyped(idx, node);
+    }
+  }
+
+  validate_links(&mut arena, false, true).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+  let graph = Graph::from_arena(&ctx, arena);
+  Ok((ctx, graph))
+}
+
+fn all_discriminants<NodeT: NodeEnum>() -> Vec<NodeT::Discriminant> {
+  let mut result = Vec::new();
+  let mut cur = Some(NodeT::Discriminant::first());
+  while let Some(d) = cur {
+    result.push(d);
+    cur = d.next();
+  }
+  result
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn zigzag(x: i64) -> u64 {
+  ((x << 1) ^ (x >> 63)) as u64
+}
+
+fn unzigzag(x: u64) -> i64 {
+  ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut x: u64) -> io::Result<()> {
+  let mut buf = [0; 10];
+  let mut len = 0;
+  loop {
+    let byte = (x & 0x7f) as u8;
+    x >>= 7;
+    if x == 0 {
+      buf[len] = byte;
+      len += 1;
+      break;
+    }
+    buf[len] = byte | 0x80;
+    len += 1;
+  }
+  writer.write_all(&buf[..len])
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+  let mut result = 0;
+  for shift in (0..64).step_by(7) {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    result |= ((byte[0] & 0x7f) as u64) << shift;
+    if byte[0] & 0x80 == 0 {
+      return Ok(result);
+    }
+  }
+  Err(invalid_data("Varint is too long"))
+}
@@ -0,0 +1,215 @@
+//! The serde adapters between a node enum and its payload, the inner struct of the variant with its links left empty
+
+use std::marker::PhantomData;
+
+use serde::de::{self, value::U32Deserializer, DeserializeSeed, Deserializer, EnumAccess, VariantAccess, Visitor};
+use serde::ser::{self, Impossible, SerializeStruct, Serializer};
+
+use super::*;
+
+const UNEXPECTED: &str = "A node enum must be newtype variants of structs";
+
+/// Serialize a node as its payload
+///
+/// The variant is skipped, as it is known from the section.
+/// The links are replaced by empty placeholders, as they are written in the framing.
+pub(super) struct Payload<'a, NodeT>(pub(super) &'a NodeT);
+
+impl<NodeT: NodeEnum + Serialize> Serialize for Payload<'_, NodeT> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let d = self.0.discriminant();
+    self.0.serialize(PayloadSerializer {
+      inner: serializer,
+      links: NodeT::link_names_of(d),
+      types: NodeT::link_types_of(d),
+      in_variant: false,
+    })
+  }
+}
+
+/// Unwraps the newtype variant, then replaces the links of the struct inside
+struct PayloadSerializer<S> {
+  inner: S,
+  links: &'static [&'static str],
+  types: &'static [LinkType],
+  in_variant: bool,
+}
+
+macro_rules! reject {
+  ($($method:ident($($arg:ty),*) -> $ret:ty;)*) => {
+    $(
+      fn $method(self, $(_: $arg),*) -> Result<$ret, Self::Error> {
+        Err(ser::Error::custom(UNEXPECTED))
+      }
+    )*
+  };
+}
+
+impl<S: Serializer> Serializer for PayloadSerializer<S> {
+  type Ok = S::Ok;
+  type Error = S::Error;
+  type SerializeSeq = Impossible<S::Ok, S::Error>;
+  type SerializeTuple = Impossible<S::Ok, S::Error>;
+  type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+  type SerializeTupleVariant = Impossible<S::Ok, S::Error>;
+  type SerializeMap = Impossible<S::Ok, S::Error>;
+  type SerializeStruct = LinkFilter<S::SerializeStruct>;
+  type SerializeStructVariant = Impossible<S::Ok, S::Error>;
+
+  fn serialize_newtype_variant<T: ?Sized + Serialize>(
+    self, _name: &'static str, _index: u32, _variant: &'static str, value: &T,
+  ) -> Result<S::Ok, S::Error> {
+    if self.in_variant {
+      return Err(ser::Error::custom(UNEXPECTED));
+    }
+    value.serialize(PayloadSerializer { in_variant: true, ..self })
+  }
+
+  fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, S::Error> {
+    if !self.in_variant {
+      return Err(ser::Error::custom(UNEXPECTED));
+    }
+    Ok(LinkFilter { inner: self.inner.serialize_struct(name, len)?, links: self.links, types: self.types })
+  }
+
+  fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<S::Ok, S::Error> {
+    Err(ser::Error::custom(UNEXPECTED))
+  }
+
+  fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _: &'static str, _: &T) -> Result<S::Ok, S::Error> {
+    Err(ser::Error::custom(UNEXPECTED))
+  }
+
+  reject! {
+    serialize_bool(bool) -> S::Ok;
+    serialize_i8(i8) -> S::Ok;
+    serialize_i16(i16) -> S::Ok;
+    serialize_i32(i32) -> S::Ok;
+    serialize_i64(i64) -> S::Ok;
+    serialize_u8(u8) -> S::Ok;
+    serialize_u16(u16) -> S::Ok;
+    serialize_u32(u32) -> S::Ok;
+    serialize_u64(u64) -> S::Ok;
+    serialize_f32(f32) -> S::Ok;
+    serialize_f64(f64) -> S::Ok;
+    serialize_char(char) -> S::Ok;
+    serialize_str(&str) -> S::Ok;
+    serialize_bytes(&[u8]) -> S::Ok;
+    serialize_none() -> S::Ok;
+    serialize_unit() -> S::Ok;
+    serialize_unit_struct(&'static str) -> S::Ok;
+    serialize_unit_variant(&'static str, u32, &'static str) -> S::Ok;
+    serialize_seq(Option<usize>) -> Self::SerializeSeq;
+    serialize_tuple(usize) -> Self::SerializeTuple;
+    serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+    serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+    serialize_map(Option<usize>) -> Self::SerializeMap;
+    serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+  }
+}
+
+/// Write an empty placeholder instead of each link, so the struct is still complete for the codecs without field names
+struct LinkFilter<SS> {
+  inner: SS,
+  links: &'static [&'static str],
+  types: &'static [LinkType],
+}
+
+impl<SS: SerializeStruct> SerializeStruct for LinkFilter<SS> {
+  type Ok = SS::Ok;
+  type Error = SS::Error;
+
+  fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), SS::Error> {
+    match self.links.iter().position(|x| *x == key).map(|pos| self.types[pos]) {
+      Some(LinkType::Point) => self.inner.serialize_field(key, &NodeIndex::empty()),
+      Some(LinkType::Set | LinkType::Vec) => self.inner.serialize_field(key, &[] as &[NodeIndex]),
+      None => self.inner.serialize_field(key, value),
+    }
+  }
+
+  fn skip_field(&mut self, key: &'static str) -> Result<(), SS::Error> {
+    self.inner.skip_field(key)
+  }
+
+  fn end(self) -> Result<SS::Ok, SS::Error> {
+    self.inner.end()
+  }
+}
+
+/// Deserialize a node of a known variant from its payload
+pub(super) struct PayloadSeed<NodeT> {
+  /// The position of the variant in the declaration
+  variant: u32,
+  _marker: PhantomData<NodeT>,
+}
+
+impl<NodeT> PayloadSeed<NodeT> {
+  pub(super) fn new(variant: u32) -> Self {
+    PayloadSeed { variant, _marker: PhantomData }
+  }
+}
+
+impl<'de, NodeT: Deserialize<'de>> DeserializeSeed<'de> for PayloadSeed<NodeT> {
+  type Value = NodeT;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<NodeT, D::Error> {
+    NodeT::deserialize(VariantDeserializer { inner: deserializer, variant: self.variant })
+  }
+}
+
+/// Provides the variant to the node enum, and the payload as the content of the variant
+struct VariantDeserializer<D> {
+  inner: D,
+  variant: u32,
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for VariantDeserializer<D> {
+  type Error = D::Error;
+
+  fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, D::Error> {
+    Err(de::Error::custom(UNEXPECTED))
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self, _name: &'static str, _variants: &'static [&'static str], visitor: V,
+  ) -> Result<V::Value, D::Error> {
+    visitor.visit_enum(self)
+  }
+
+  serde::forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+    newtype_struct seq tuple tuple_struct map struct identifier ignored_any
+  }
+}
+
+impl<'de, D: Deserializer<'de>> EnumAccess<'de> for VariantDeserializer<D> {
+  type Error = D::Error;
+  type Variant = Self;
+
+  fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), D::Error> {
+    let variant = seed.deserialize(U32Deserializer::<D::Error>::new(self.variant))?;
+    Ok((variant, self))
+  }
+}
+
+impl<'de, D: Deserializer<'de>> VariantAccess<'de> for VariantDeserializer<D> {
+  type Error = D::Error;
+
+  fn unit_variant(self) -> Result<(), D::Error> {
+    Err(de::Error::custom(UNEXPECTED))
+  }
+
+  fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, D::Error> {
+    seed.deserialize(self.inner)
+  }
+
+  fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, D::Error> {
+    Err(de::Error::custom(UNEXPECTED))
+  }
+
+  fn struct_variant<V: Visitor<'de>>(
+    self, _fields: &'static [&'static str], _visitor: V,
+  ) -> Result<V::Value, D::Error> {
+    Err(de::Error::custom(UNEXPECTED))
+  }
+}
@@ -1,4 +1,3 @@
-#![cfg(feature = "debug")]
 use super::*;
 use std::collections::BTreeMap;
 
@@ -0,0 +1,171 @@
+//! Structural difference between two graphs, see [`graph_diff`]
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::*;
+
+/// The difference from graph `a` to graph `b`, made by [`graph_diff`]
+///
+/// + `added`: nodes only in `b`
+/// + `removed`: nodes only in `a`
+/// + `changed`: nodes in both graphs, whose variant or data fields are different
+/// + `added_links` and `removed_links`: the link targets only in `b` or only in `a`, as `(source, target)` grouped by the link name.
+///   The links of the added and removed nodes are included. The order of the targets in a `Vec` link is ignored.
+///
+/// [`Display`] prints one line for each difference, useful in the message of a failed test.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+  pub added: BTreeSet<NodeIndex>,
+  pub removed: BTreeSet<NodeIndex>,
+  pub changed: BTreeSet<NodeIndex>,
+  pub added_links: BTreeMap<&'static str, Vec<(NodeIndex, NodeIndex)>>,
+  pub removed_links: BTreeMap<&'static str, Vec<(NodeIndex, NodeIndex)>>,
+}
+
+impl GraphDiff {
+  /// Check if the graphs are the same
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty()
+      && self.removed.is_empty()
+      && self.changed.is_empty()
+      && self.added_links.is_empty()
+      && self.removed_links.is_empty()
+  }
+}
+
+impl Display for GraphDiff {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.is_empty() {
+      return writeln!(f, "no difference");
+    }
+    for x in &self.added {
+      writeln!(f, "+ node {}", x)?;
+    }
+    for x in &self.removed {
+      writeln!(f, "- node {}", x)?;
+    }
+    for x in &self.changed {
+      writeln!(f, "~ node {}", x)?;
+    }
+    for (name, links) in &self.added_links {
+      for (x, y) in links {
+        writeln!(f, "+ link {}.{} -> {}", x, name, y)?;
+      }
+    }
+    for (name, links) in &self.removed_links {
+      for (x, y) in links {
+        writeln!(f, "- link {}.{} -> {}", x, name, y)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Compare two graphs sharing the same [`Context`], e.g. a graph and its [`fork`](Graph::fork) before a pass.
+///
+/// # Panic
+/// Panic if the graphs have different contexts.
+///
+/// # Example
+/// ```
+/// use ttgraph::*;
+/// #[derive(TypedNode, Clone, PartialEq)]
+/// struct NodeA{
+///   next: NodeIndex,
+///   data: usize,
+/// }
+/// node_enum!{
+///   #[derive(Clone, PartialEq)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let a = trans.insert(Node::A(NodeA{ next: NodeIndex::empty(), data: 1 }));
+/// graph.commit(trans);
+///
+/// let before = graph.fork();
+/// let mut trans = Transaction::new(&ctx);
+/// let b = trans.insert(Node::A(NodeA{ next: NodeIndex::empty(), data: 2 }));
+/// mut_node!(trans, Node::A, a, move |x| { x.next = b; });
+/// graph.commit(trans);
+///
+/// let diff = graph_diff(&before, &graph);
+/// assert_eq!(Vec::from_iter(diff.added.iter().copied()), vec![b]);
+/// // Only the link of a is changed, not its data
+/// assert!(diff.changed.is_empty());
+/// assert_eq!(diff.added_links["next"], vec![(a, b)]);
+/// assert_eq!(diff.to_string(), format!("+ node {}\n+ link {}.next -> {}\n", b, a, b));
+/// # }
+/// ```
+pub fn graph_diff<NodeT, Arena>(a: &Graph<NodeT, Arena>, b: &Graph<NodeT, Arena>) -> GraphDiff
+where
+  NodeT: NodeEnum + PartialEq + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  assert!(a.ctx_id == b.ctx_id, "The graphs are from different context!");
+  let mut result = GraphDiff::default();
+  for (x, node_a) in a.iter() {
+    match b.get(x) {
+      None => {
+        result.removed.insert(x);
+        diff_links(&mut result, x, Some(node_a), None);
+      },
+      Some(node_b) if node_a != node_b => {
+        if strip_links(node_a) != strip_links(node_b) {
+          result.changed.insert(x);
+        }
+        diff_links(&mut result, x, Some(node_a), Some(node_b));
+      },
+      _ => {},
+    }
+  }
+  for (x, node_b) in b.iter() {
+    if !a.contains(x) {
+      result.added.insert(x);
+      diff_links(&mut result, x, None, Some(node_b));
+    }
+  }
+  result
+}
+
+/// A copy of the node with all links emptied, to compare the variant and data fields only
+fn strip_links<NodeT: NodeEnum + Clone>(node: &NodeT) -> NodeT {
+  let mut result = node.clone();
+  for name in NodeT::link_names_of(node.discriminant()) {
+    result.set_links_by_name(name, Vec::new());
+  }
+  result
+}
+
+/// Count the link targets of a node by link name and target
+fn count_links<NodeT: NodeEnum>(node: Option<&NodeT>) -> BTreeMap<(&'static str, NodeIndex), usize> {
+  let mut result = BTreeMap::new();
+  for (y, s) in node.into_iter().flat_map(|n| n.iter_sources()) {
+    if !y.is_empty() {
+      *result.entry((NodeT::link_name(NodeT::to_link_mirror_enum(s)), y)).or_default() += 1;
+    }
+  }
+  result
+}
+
+fn diff_links<NodeT: NodeEnum>(result: &mut GraphDiff, x: NodeIndex, a: Option<&NodeT>, b: Option<&NodeT>) {
+  let links_a = count_links(a);
+  let links_b = count_links(b);
+  for ((name, y), cnt) in &links_a {
+    let remain = links_b.get(&(*name, *y)).copied().unwrap_or(0);
+    for _ in remain..*cnt {
+      result.removed_links.entry(name).or_default().push((x, *y));
+    }
+  }
+  for ((name, y), cnt) in &links_b {
+    let remain = links_a.get(&(*name, *y)).copied().unwrap_or(0);
+    for _ in remain..*cnt {
+      result.added_links.entry(name).or_default().push((x, *y));
+    }
+  }
+}
@@ -0,0 +1,186 @@
+//! Render a [`Graph`] to the [DOT language](https://graphviz.org/doc/info/lang.html) of Graphviz, see [`Dot`]
+
+use std::fmt;
+
+use super::*;
+
+/// Fill colors of the nodes, chosen by the discriminant
+const PALETTE: [&str; 12] = [
+  "lightblue",
+  "lightpink",
+  "palegreen",
+  "lightyellow",
+  "plum",
+  "lightsalmon",
+  "lightcyan",
+  "wheat",
+  "thistle",
+  "khaki",
+  "aquamarine",
+  "lightgray",
+];
+
+/// Type alias of the node label closure used in [`Dot::label`]
+pub type DotLabelFunc<'a, NodeT> = Box<dyn Fn(NodeIndex, &NodeT) -> String + 'a>;
+
+/// A DOT rendering of a [`Graph`], use [`Display`] or [`to_string`](ToString::to_string) to get the text.
+///
+/// + Nodes are filled with a color by their discriminant, and labeled by `discriminant index` unless a [`label`](Dot::label) closure is given.
+/// + Edges are labeled by the link names.
+/// + Nodes are clustered by the groups declared in [`node_enum!`], a node is put in the first group it belongs to. Use [`clusters`](Dot::clusters) to turn it off.
+///
+/// # Example
+/// ```
+/// use ttgraph::{*, dot::Dot};
+/// #[derive(TypedNode)]
+/// struct NodeA{
+///   next: NodeIndex,
+///   data: usize,
+/// }
+/// #[derive(TypedNode)]
+/// struct NodeB{
+///   data: usize,
+/// }
+/// node_enum!{
+///   enum Node{
+///     A(NodeA),
+///     B(NodeB),
+///   }
+///   group!{
+///     leaf{B},
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let b = trans.insert(Node::B(NodeB{ data: 2 }));
+/// trans.insert(Node::A(NodeA{ next: b, data: 1 }));
+/// graph.commit(trans);
+///
+/// let dot = Dot::new(&graph)
+///   .label(|idx, node| match node {
+///     Node::A(x) => format!("A{} = {}", idx, x.data),
+///     Node::B(x) => format!("B{} = {}", idx, x.data),
+///   })
+///   .to_string();
+/// assert!(dot.contains("n2 -> n1 [label=\"next\"];"));
+/// assert!(dot.contains("subgraph cluster_0 {"));
+/// assert!(dot.contains("label=\"A2 = 1\""));
+/// // Pipe it into `dot -Tsvg` to draw the graph
+/// # }
+/// ```
+pub struct Dot<'a, NodeT, Arena = <NodeT as NodeEnum>::GenArena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  graph: &'a Graph<NodeT, Arena>,
+  name: String,
+  label: Option<DotLabelFunc<'a, NodeT>>,
+  clusters: bool,
+}
+
+impl<'a, NodeT, Arena> Dot<'a, NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Make a DOT rendering of the graph with the default options
+  pub fn new(graph: &'a Graph<NodeT, Arena>) -> Self {
+    Dot { graph, name: "G".to_string(), label: None, clusters: true }
+  }
+
+  /// Set the name of the digraph, the default is `G`
+  pub fn name(mut self, name: impl Into<String>) -> Self {
+    self.name = name.into();
+    self
+  }
+
+  /// Label the nodes by a closure `Fn(NodeIndex, &NodeT) -> String`
+  pub fn label(mut self, func: impl Fn(NodeIndex, &NodeT) -> String + 'a) -> Self {
+    self.label = Some(Box::new(func));
+    self
+  }
+
+  /// Set whether the nodes are clustered by groups, the default is true
+  pub fn clusters(mut self, clusters: bool) -> Self {
+    self.clusters = clusters;
+    self
+  }
+
+  fn write_node(&self, f: &mut fmt::Formatter<'_>, indent: &str, idx: NodeIndex, node: &NodeT) -> fmt::Result {
+    let d = node.discriminant();
+    let label = match &self.label {
+      Some(func) => func(idx, node),
+      None => format!("{:?} {}", d, idx),
+    };
+    writeln!(
+      f,
+      "{}n{} [label=\"{}\", style=filled, fillcolor={}];",
+      indent,
+      idx.0,
+      escape(&label),
+      PALETTE[discriminant_position(d) % PALETTE.len()]
+    )
+  }
+}
+
+impl<'a, NodeT, Arena> Display for Dot<'a, NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "digraph \"{}\" {{", escape(&self.name))?;
+
+    let mut clustered = OrderSet::new();
+    if self.clusters {
+      for (i, group) in NodeT::group_names().iter().enumerate() {
+        let members = Vec::from_iter(self.graph.iter_group(group).filter(|(idx, _)| !clustered.contains(idx)));
+        if members.is_empty() {
+          continue;
+        }
+        writeln!(f, "  subgraph cluster_{} {{", i)?;
+        writeln!(f, "    label=\"{}\";", escape(group))?;
+        for (idx, node) in members {
+          self.write_node(f, "    ", idx, node)?;
+          clustered.insert(idx);
+        }
+        writeln!(f, "  }}")?;
+      }
+    }
+    for (idx, node) in self.graph.iter() {
+      if !clustered.contains(&idx) {
+        self.write_node(f, "  ", idx, node)?;
+      }
+    }
+
+    for (idx, node) in self.graph.iter() {
+      for (y, s) in node.iter_sources() {
+        if self.graph.contains(y) {
+          let name = NodeT::link_name(NodeT::to_link_mirror_enum(s));
+          writeln!(f, "  n{} -> n{} [label=\"{}\"];", idx.0, y.0, escape(name))?;
+        }
+      }
+    }
+    writeln!(f, "}}")
+  }
+}
+
+/// The position of the discriminant in the declaration order
+fn discriminant_position<D: NodeDiscriminant>(d: D) -> usize {
+  let mut cur = D::first();
+  let mut result = 0;
+  while cur != d {
+    cur = cur.next().unwrap();
+    result += 1;
+  }
+  result
+}
+
+/// Escape a string to be used inside double quotes
+fn escape(s: &str) -> String {
+  s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
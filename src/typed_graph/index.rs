@@ -0,0 +1,210 @@
+//! Secondary indexes on the data fields of nodes.
+//!
+//! Indexes are declared by `index!` inside [`node_enum!`](crate::node_enum!), and maintained by the [`Graph`] while committing.
+//! An index is named after the field, so `Worker.name` and `Factory.name` share the index `"name"`, and fields sharing an index should have the same type.
+//! If any field of an index is declared as `unique`, the whole index is unique.
+
+use std::any::Any;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+
+use ordermap::{OrderMap, OrderSet};
+
+use super::*;
+
+/// A visitor over the indexed data fields of a node, intented to be used in macros
+pub trait IndexVisitor {
+  /// Visit an indexed field, `name` is the name of the index and `key` is the value of the field
+  fn visit<K: Any + Hash + Eq + Clone + Send + Sync>(&mut self, name: &'static str, unique: bool, key: &K);
+}
+
+/// Bits of the hash used as the bucket of a key, the keys of the same bucket are searched linearly
+const BUCKET_BITS: u32 = 20;
+
+/// A map from the keys to the nodes, the keys are hashed into the buckets of a [`PersistentMap`]
+/// so a forked index is shared until its buckets are changed.
+#[derive(Clone)]
+struct IndexMap<K> {
+  buckets: PersistentMap<usize, Vec<(K, OrderSet<NodeIndex>)>>,
+}
+
+impl<K: Hash + Eq> IndexMap<K> {
+  fn bucket(key: &K) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() & ((1 << BUCKET_BITS) - 1)) as usize
+  }
+
+  fn get(&self, key: &K) -> Option<&OrderSet<NodeIndex>> {
+    let bucket = self.buckets.get(&Self::bucket(key))?;
+    bucket.iter().find(|(k, _)| k == key).map(|(_, nodes)| nodes)
+  }
+}
+
+impl<K: Hash + Eq + Clone> IndexMap<K> {
+  fn new() -> Self {
+    IndexMap { buckets: PersistentMap::new() }
+  }
+
+  /// Insert a node under the key, return the number of nodes with the key
+  fn insert(&mut self, key: &K, x: NodeIndex) -> usize {
+    let bucket = self.buckets.get_or_insert_with(Self::bucket(key), Vec::new);
+    let pos = bucket.iter().position(|(k, _)| k == key).unwrap_or_else(|| {
+      bucket.push((key.clone(), OrderSet::new()));
+      bucket.len() - 1
+    });
+    bucket[pos].1.insert(x);
+    bucket[pos].1.len()
+  }
+
+  fn remove(&mut self, key: &K, x: NodeIndex) {
+    let b = Self::bucket(key);
+    let found = self.buckets.get(&b).and_then(|bucket| bucket.iter().position(|(k, _)| k == key));
+    let Some(pos) = found else {
+      return;
+    };
+    let bucket = self.buckets.get_mut(&b).unwrap();
+    bucket[pos].1.swap_remove(&x);
+    if bucket[pos].1.is_empty() {
+      bucket.swap_remove(pos);
+      if bucket.is_empty() {
+        self.buckets.remove(&b);
+      }
+    }
+  }
+}
+
+/// A type erased [`IndexMap`], which can be shared by forked graphs
+trait AnyIndex: Any + Send + Sync {
+  fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+  fn clone_arc(&self) -> Arc<dyn AnyIndex>;
+}
+
+impl<K: Any + Hash + Eq + Clone + Send + Sync> AnyIndex for IndexMap<K> {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+  fn clone_arc(&self) -> Arc<dyn AnyIndex> {
+    Arc::new(self.clone())
+  }
+}
+
+/// Get the index for modification, share its buckets with a new copy if it is shared
+fn make_mut<K: Any>(index: &mut Arc<dyn AnyIndex>) -> Option<&mut IndexMap<K>> {
+  if Arc::get_mut(index).is_none() {
+    *index = index.clone_arc();
+  }
+  Arc::get_mut(index).unwrap().as_any_mut().downcast_mut::<IndexMap<K>>()
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct IndexStore {
+  indexes: OrderMap<&'static str, Arc<dyn AnyIndex>>,
+  pending: OrderSet<NodeIndex>,
+  /// Nodes whose data may be changed outside of a transaction, their keys are not inside the indexes
+  dirty: OrderSet<NodeIndex>,
+}
+
+impl IndexStore {
+  pub(crate) fn insert<NodeT: NodeEnum>(&mut self, x: NodeIndex, node: &NodeT) {
+    node.visit_indexes(&mut IndexInserter { store: self, x });
+  }
+
+  pub(crate) fn remove<NodeT: NodeEnum>(&mut self, x: NodeIndex, node: &NodeT) {
+    node.visit_indexes(&mut IndexRemover { store: self, x, visited: false });
+  }
+
+  /// Remove the keys of a node which data is going to be changed, insert it back by [`refresh`](IndexStore::refresh)
+  pub(crate) fn mark_dirty<NodeT: NodeEnum>(&mut self, x: NodeIndex, node: &NodeT) {
+    if self.dirty.contains(&x) {
+      return;
+    }
+    let mut remover = IndexRemover { store: self, x, visited: false };
+    node.visit_indexes(&mut remover);
+    if remover.visited {
+      self.dirty.insert(x);
+    }
+  }
+
+  /// Insert the nodes marked by [`mark_dirty`](IndexStore::mark_dirty) back into the indexes
+  pub(crate) fn refresh<A: CateArena>(&mut self, nodes: &A) {
+    for x in std::mem::take(&mut self.dirty) {
+      self.insert(x, nodes.get(x).unwrap());
+    }
+  }
+
+  /// Panics if the index exists with another key type
+  pub(crate) fn lookup<K: Any + Hash + Eq>(&self, name: &str, key: &K) -> Option<&OrderSet<NodeIndex>> {
+    let index = self.indexes.get(name)?;
+    let index = index.as_any().downcast_ref::<IndexMap<K>>().unwrap_or_else(|| {
+      panic!("Index {} is looked up by the key type {}, not the type of the fields!", name, std::any::type_name::<K>())
+    });
+    index.get(key)
+  }
+
+  /// Take the nodes which may break a unique index
+  pub(crate) fn take_pending(&mut self) -> OrderSet<NodeIndex> {
+    std::mem::take(&mut self.pending)
+  }
+
+  /// Find the unique indexes broken by the node, returns `(index name, nodes with the same key)`
+  pub(crate) fn check_unique<NodeT: NodeEnum>(&self, node: &NodeT) -> Vec<(&'static str, Vec<NodeIndex>)> {
+    let mut checker = UniqueChecker { store: self, violations: Vec::new() };
+    node.visit_indexes(&mut checker);
+    checker.violations
+  }
+}
+
+struct IndexInserter<'a> {
+  store: &'a mut IndexStore,
+  x: NodeIndex,
+}
+
+impl<'a> IndexVisitor for IndexInserter<'a> {
+  fn visit<K: Any + Hash + Eq + Clone + Send + Sync>(&mut self, name: &'static str, unique: bool, key: &K) {
+    let index = self.store.indexes.entry(name).or_insert_with(|| Arc::new(IndexMap::<K>::new()));
+    let index = make_mut::<K>(index)
+      .unwrap_or_else(|| panic!("Fields of index {} have different types!", name));
+    if index.insert(key, self.x) > 1 && unique {
+      self.store.pending.insert(self.x);
+    }
+  }
+}
+
+struct IndexRemover<'a> {
+  store: &'a mut IndexStore,
+  x: NodeIndex,
+  visited: bool,
+}
+
+impl<'a> IndexVisitor for IndexRemover<'a> {
+  fn visit<K: Any + Hash + Eq + Clone + Send + Sync>(&mut self, name: &'static str, _unique: bool, key: &K) {
+    self.visited = true;
+    let Some(index) = self.store.indexes.get_mut(name).and_then(make_mut::<K>) else {
+      return;
+    };
+    index.remove(key, self.x);
+  }
+}
+
+struct UniqueChecker<'a> {
+  store: &'a IndexStore,
+  violations: Vec<(&'static str, Vec<NodeIndex>)>,
+}
+
+impl<'a> IndexVisitor for UniqueChecker<'a> {
+  fn visit<K: Any + Hash + Eq + Clone + Send + Sync>(&mut self, name: &'static str, unique: bool, key: &K) {
+    if !unique {
+      return;
+    }
+    if let Some(nodes) = self.store.lookup(name, key) {
+      if nodes.len() > 1 {
+        self.violations.push((name, Vec::from_iter(nodes.iter().copied())));
+      }
+    }
+  }
+}
@@ -0,0 +1,291 @@
+//! Export and import a [`Graph`] in formats understood by other graph tools
+//!
+//! + Node-link JSON, the format of `networkx.node_link_data` and d3.js: [`to_node_link`] and [`from_node_link`].
+//! + GraphML, with the `graphml` feature: `to_graphml` and `from_graphml`.
+//!
+//! Both formats map the graph in the same way:
+//! + A node has a `type` attribute, the name of its variant, and one attribute for each data field, see [`TypedNode::data_names`].
+//!   Data fields named `id` or `type` are written as `data.id` and `data.type`,
+//!   so they do not conflict with the node attributes.
+//! + Each link target is an edge, with a `link` attribute of the link name. Empty links have no edge.
+//! + The node ids are the [`NodeIndex`] numbers.
+//!
+//! The data fields are converted with serde, so `NodeT` must be [`Serialize`] to export, and [`Deserialize`] to import.
+//! The node enum should use the default (externally tagged) representation of serde.
+//! A graph imported by these functions has a new [`Context`], like [`deserialize_graph`](crate::serialize::deserialize_graph).
+
+use std::error::Error;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use super::*;
+
+#[cfg(feature = "graphml")]
+mod graphml;
+#[cfg(feature = "graphml")]
+pub use graphml::*;
+
+/// The error of importing a graph
+#[derive(Debug)]
+pub enum ImportError {
+  /// The input is not well-formed JSON or XML
+  Syntax(String),
+  /// The input does not describe a graph of this node type
+  Format(String),
+  /// A node can not be deserialized from its attributes
+  Node(NodeIndex, serde_json::Error),
+}
+
+impl Display for ImportError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ImportError::Syntax(msg) => write!(f, "Syntax error: {}", msg),
+      ImportError::Format(msg) => write!(f, "Format error: {}", msg),
+      ImportError::Node(idx, e) => write!(f, "Failed to deserialize node {}: {}", idx, e),
+    }
+  }
+}
+
+impl Error for ImportError {}
+
+/// The node attributes which are not data fields
+const NODE_ATTRS: [&str; 2] = ["id", "type"];
+
+/// The attribute name of a data field, prefixed by `data.` if it is the name of a node attribute
+pub(crate) fn attr_name(name: &str) -> String {
+  if NODE_ATTRS.contains(&name) {
+    format!("data.{}", name)
+  } else {
+    name.to_string()
+  }
+}
+
+/// A node split into its variant name and data fields
+pub(crate) struct FlatNode {
+  pub(crate) id: NodeIndex,
+  pub(crate) variant: String,
+  pub(crate) data: Vec<(&'static str, Value)>,
+}
+
+/// A link target, as `(source, target, link name)`
+pub(crate) type FlatEdge = (NodeIndex, NodeIndex, String);
+
+/// Split the nodes of a graph into variant names, data fields and edges
+pub(crate) fn flatten<NodeT, Arena>(graph: &Graph<NodeT, Arena>) -> (Vec<FlatNode>, Vec<FlatEdge>)
+where
+  NodeT: NodeEnum + Serialize,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  let mut nodes = Vec::new();
+  let mut edges = Vec::new();
+  for (idx, node) in graph.iter() {
+    let d = node.discriminant();
+    let value = serde_json::to_value(node).expect("Failed to serialize a node");
+    let fields = match value {
+      Value::Object(obj) if obj.len() == 1 => obj.into_iter().next().unwrap().1,
+      _ => panic!("The node enum should be serialized as an externally tagged enum"),
+    };
+    let mut data = Vec::new();
+    for name in NodeT::data_names_of(d) {
+      data.push((*name, fields.get(*name).cloned().unwrap_or(Value::Null)));
+    }
+    nodes.push(FlatNode { id: idx, variant: format!("{:?}", d), data });
+    for (y, s) in node.iter_sources() {
+      if !y.is_empty() {
+        edges.push((idx, y, NodeT::link_name(NodeT::to_link_mirror_enum(s)).to_string()));
+      }
+    }
+  }
+  (nodes, edges)
+}
+
+/// Rebuild the nodes from variant names, data fields and edges, then make a graph with a new context
+pub(crate) fn unflatten<NodeT>(
+  nodes: Vec<FlatNode>, edges: Vec<FlatEdge>,
+) -> Result<(Context, Graph<NodeT>), ImportError>
+where
+  NodeT: NodeEnum + DeserializeOwned,
+{
+  let empty = serde_json::to_value(NodeIndex::empty()).unwrap();
+  let mut objects = OrderMap::new();
+  for FlatNode { id, variant, data } in nodes {
+    if id.is_empty() {
+      return Err(ImportError::Format("Node id 0 is reserved for the empty index".to_string()));
+    }
+    let d = discriminant_by_name::<NodeT>(&variant)
+      .ok_or_else(|| ImportError::Format(format!("Unknown node type {} of node {}", variant, id)))?;
+    let mut fields = Map::new();
+    for (name, value) in data {
+      fields.insert(name.to_string(), value);
+    }
+    for (name, ty) in NodeT::link_names_of(d).iter().zip(NodeT::link_types_of(d)) {
+      let init = match ty {
+        LinkType::Point => empty.clone(),
+        LinkType::Set | LinkType::Vec => Value::Array(Vec::new()),
+      };
+      fields.insert(name.to_string(), init);
+    }
+    if objects.insert(id, (variant, d, fields)).is_some() {
+      return Err(ImportError::Format(format!("Duplicated node {}", id)));
+    }
+  }
+
+  for (x, y, link) in edges {
+    if !objects.contains_key(&y) {
+      return Err(ImportError::Format(format!("Edge from {} to an unknown node {}", x, y)));
+    }
+    let (_, d, fields) =
+      objects.get_mut(&x).ok_or_else(|| ImportError::Format(format!("Edge from an unknown node {}", x)))?;
+    let pos = NodeT::link_names_of(*d)
+      .iter()
+      .position(|name| *name == link)
+      .ok_or_else(|| ImportError::Format(format!("Node {} has no link {}", x, link)))?;
+    let target = serde_json::to_value(y).unwrap();
+    match NodeT::link_types_of(*d)[pos] {
+      LinkType::Point => {
+        let old = fields.insert(link.clone(), target).unwrap();
+        if old != empty {
+          return Err(ImportError::Format(format!("Multiple edges of the single link {} of node {}", link, x)));
+        }
+      },
+      LinkType::Set | LinkType::Vec => {
+        fields.get_mut(&link).unwrap().as_array_mut().unwrap().push(target);
+      },
+    }
+  }
+
+  let mut result = Vec::new();
+  for (id, (variant, _, fields)) in objects {
+    let mut value = Map::new();
+    value.insert(variant, Value::Object(fields));
+    let node = serde_json::from_value(Value::Object(value)).map_err(|e| ImportError::Node(id, e))?;
+    result.push((id, node));
+  }
+  let cnt = result.iter().map(|(idx, _)| idx.0).max().unwrap_or(0);
+  let ctx = Context::from_id(Uuid::new_v4(), cnt);
+  let graph = Graph::do_deserialize(&ctx, result);
+  Ok((ctx, graph))
+}
+
+fn discriminant_by_name<NodeT: NodeEnum>(name: &str) -> Option<NodeT::Discriminant> {
+  let mut cur = Some(NodeT::Discriminant::first());
+  while let Some(d) = cur {
+    if format!("{:?}", d) == name {
+      return Some(d);
+    }
+    cur = d.next();
+  }
+  None
+}
+
+/// Export the graph to node-link JSON
+///
+/// The result looks like:
+/// ```json
+/// {
+///   "directed": true,
+///   "multigraph": true,
+///   "graph": {},
+///   "nodes": [{"id": 1, "type": "A", "data": 1}, {"id": 2, "type": "A", "data": 2}],
+///   "links": [{"source": 1, "target": 2, "link": "to"}]
+/// }
+/// ```
+///
+/// # Example
+/// ```
+/// use ttgraph::{*, interchange::*};
+/// use serde::{Serialize, Deserialize};
+/// #[derive(TypedNode, Serialize, Deserialize)]
+/// struct NodeA{
+///   to: Vec<NodeIndex>,
+///   data: usize,
+/// }
+/// node_enum!{
+///   #[derive(Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let a = trans.insert(Node::A(NodeA{ to: Vec::new(), data: 1 }));
+/// let b = trans.insert(Node::A(NodeA{ to: vec![a], data: 2 }));
+/// graph.commit(trans);
+///
+/// let json = to_node_link(&graph);
+/// assert_eq!(json["nodes"][0], serde_json::json!({"id": a, "type": "A", "data": 1}));
+/// assert_eq!(json["links"][0], serde_json::json!({"source": b, "target": a, "link": "to"}));
+///
+/// let (ctx2, graph2) = from_node_link::<Node>(&json).unwrap();
+/// assert_eq!(get_node!(graph2, Node::A, b).unwrap().to, vec![a]);
+/// # }
+/// ```
+pub fn to_node_link<NodeT, Arena>(graph: &Graph<NodeT, Arena>) -> Value
+where
+  NodeT: NodeEnum + Serialize,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  let (nodes, edges) = flatten(graph);
+  let nodes = Vec::from_iter(nodes.into_iter().map(|FlatNode { id, variant, data }| {
+    let mut obj = Map::new();
+    obj.insert("id".to_string(), Value::from(id.0));
+    obj.insert("type".to_string(), Value::from(variant));
+    for (name, value) in data {
+      obj.insert(attr_name(name), value);
+    }
+    Value::Object(obj)
+  }));
+  let links = Vec::from_iter(edges.into_iter().map(|(x, y, link)| {
+    let mut obj = Map::new();
+    obj.insert("source".to_string(), Value::from(x.0));
+    obj.insert("target".to_string(), Value::from(y.0));
+    obj.insert("link".to_string(), Value::from(link));
+    Value::Object(obj)
+  }));
+
+  let mut result = Map::new();
+  result.insert("directed".to_string(), Value::Bool(true));
+  result.insert("multigraph".to_string(), Value::Bool(true));
+  result.insert("graph".to_string(), Value::Object(Map::new()));
+  result.insert("nodes".to_string(), Value::Array(nodes));
+  result.insert("links".to_string(), Value::Array(links));
+  Value::Object(result)
+}
+
+/// Import a graph from node-link JSON, see [`to_node_link`]
+///
+/// The ids must be positive integers. Edges may be listed under `links` or `edges`. Attributes not belonging to the node type are ignored.
+pub fn from_node_link<NodeT>(json: &Value) -> Result<(Context, Graph<NodeT>), ImportError>
+where
+  NodeT: NodeEnum + DeserializeOwned,
+{
+  let format_err = |msg: &str| ImportError::Format(msg.to_string());
+  let id_of = |value: &Value| value.as_u64().map(|x| NodeIndex(x as usize)).ok_or_else(|| format_err("Invalid node id"));
+
+  let mut nodes = Vec::new();
+  for node in json.get("nodes").and_then(Value::as_array).ok_or_else(|| format_err("Missing nodes"))? {
+    let id = id_of(node.get("id").ok_or_else(|| format_err("Missing node id"))?)?;
+    let variant = node.get("type").and_then(Value::as_str).ok_or_else(|| format_err("Missing node type"))?;
+    let d = discriminant_by_name::<NodeT>(variant)
+      .ok_or_else(|| ImportError::Format(format!("Unknown node type {} of node {}", variant, id)))?;
+    let data = Vec::from_iter(
+      NodeT::data_names_of(d).iter().filter_map(|name| node.get(attr_name(name)).map(|value| (*name, value.clone()))),
+    );
+    nodes.push(FlatNode { id, variant: variant.to_string(), data });
+  }
+
+  let mut edges = Vec::new();
+  let links = json.get("links").or_else(|| json.get("edges")).and_then(Value::as_array);
+  for edge in links.ok_or_else(|| format_err("Missing links"))? {
+    let x = id_of(edge.get("source").ok_or_else(|| format_err("Missing edge source"))?)?;
+    let y = id_of(edge.get("target").ok_or_else(|| format_err("Missing edge target"))?)?;
+    let link = edge.get("link").and_then(Value::as_str).ok_or_else(|| format_err("Missing edge link"))?;
+    edges.push((x, y, link.to_string()));
+  }
+
+  unflatten(nodes, edges)
+}
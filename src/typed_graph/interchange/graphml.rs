@@ -0,0 +1,238 @@
+use std::fmt::Write;
+
+use super::*;
+
+const GRAPHML_NS: &str = "http://graphml.graphdrawing.org/xmlns";
+
+/// Export the graph to GraphML
+///
+/// The nodes have ids `n<NodeIndex>`.
+/// A data field is written with the matching `attr.type` if all its values are strings, booleans, integers or floats.
+/// Otherwise, e.g. for lists, structs and options, its values are written as JSON text,
+/// and the key is marked by `<desc>json</desc>`.
+///
+/// # Example
+/// ```
+/// use ttgraph::{*, interchange::*};
+/// use serde::{Serialize, Deserialize};
+/// #[derive(TypedNode, Serialize, Deserialize)]
+/// struct NodeA{
+///   to: NodeIndex,
+///   name: String,
+/// }
+/// node_enum!{
+///   #[derive(Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let a = trans.insert(Node::A(NodeA{ to: NodeIndex::empty(), name: "a".to_string() }));
+/// graph.commit(trans);
+///
+/// let xml = to_graphml(&graph);
+/// assert!(xml.contains("attr.name=\"name\" attr.type=\"string\""));
+/// assert!(xml.contains("<data key=\"d0\">a</data>"));
+/// let (ctx2, graph2) = from_graphml::<Node>(&xml).unwrap();
+/// assert_eq!(get_node!(graph2, Node::A, a).unwrap().name, "a");
+/// # }
+/// ```
+pub fn to_graphml<NodeT, Arena>(graph: &Graph<NodeT, Arena>) -> String
+where
+  NodeT: NodeEnum + Serialize,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  let (nodes, edges) = flatten(graph);
+  let keys = data_keys::<NodeT>();
+  let mut types = vec![None; keys.len()];
+  for (name, value) in nodes.iter().flat_map(|node| node.data.iter()) {
+    let key = keys.iter().position(|x| x == name).unwrap();
+    let ty = ValueType::of(value);
+    types[key] = match types[key] {
+      Some(x) if x != ty => Some(ValueType::Json),
+      _ => Some(ty),
+    };
+  }
+  let types = Vec::from_iter(types.into_iter().map(|ty| ty.unwrap_or(ValueType::String)));
+
+  let mut result = String::new();
+  writeln!(result, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+  writeln!(result, "<graphml xmlns=\"{}\">", GRAPHML_NS).unwrap();
+  writeln!(result, "  <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>").unwrap();
+  for (i, (name, ty)) in keys.iter().zip(&types).enumerate() {
+    let (name, ty_name) = (escape(&attr_name(name)), ty.attr_type());
+    write!(result, "  <key id=\"d{}\" for=\"node\" attr.name=\"{}\" attr.type=\"{}\"", i, name, ty_name).unwrap();
+    if *ty == ValueType::Json {
+      writeln!(result, "><desc>json</desc></key>").unwrap();
+    } else {
+      writeln!(result, "/>").unwrap();
+    }
+  }
+  writeln!(result, "  <key id=\"link\" for=\"edge\" attr.name=\"link\" attr.type=\"string\"/>").unwrap();
+  writeln!(result, "  <graph id=\"G\" edgedefault=\"directed\">").unwrap();
+  for FlatNode { id, variant, data } in nodes {
+    writeln!(result, "    <node id=\"n{}\">", id.0).unwrap();
+    writeln!(result, "      <data key=\"type\">{}</data>", escape(&variant)).unwrap();
+    for (name, value) in data {
+      let key = keys.iter().position(|x| *x == name).unwrap();
+      let text = match value {
+        Value::String(s) if types[key] == ValueType::String => s,
+        value => value.to_string(),
+      };
+      writeln!(result, "      <data key=\"d{}\">{}</data>", key, escape(&text)).unwrap();
+    }
+    writeln!(result, "    </node>").unwrap();
+  }
+  for (i, (x, y, link)) in edges.into_iter().enumerate() {
+    writeln!(result, "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">", i, x.0, y.0).unwrap();
+    writeln!(result, "      <data key=\"link\">{}</data>", escape(&link)).unwrap();
+    writeln!(result, "    </edge>").unwrap();
+  }
+  writeln!(result, "  </graph>").unwrap();
+  writeln!(result, "</graphml>").unwrap();
+  result
+}
+
+/// Import a graph from GraphML, see [`to_graphml`]
+///
+/// The node ids must be `n<number>` or `<number>` with a positive number. Keys are matched by `attr.name`.
+/// Data values are read by the `attr.type` of their keys, or as JSON text if the key is marked by `<desc>json</desc>`.
+pub fn from_graphml<NodeT>(xml: &str) -> Result<(Context, Graph<NodeT>), ImportError>
+where
+  NodeT: NodeEnum + DeserializeOwned,
+{
+  let doc = roxmltree::Document::parse(xml).map_err(|e| ImportError::Syntax(e.to_string()))?;
+  let format_err = |msg: String| ImportError::Format(msg);
+
+  let mut key_names = OrderMap::new();
+  for key in doc.root_element().children().filter(|x| x.has_tag_name((GRAPHML_NS, "key"))) {
+    if let (Some(id), Some(name)) = (key.attribute("id"), key.attribute("attr.name")) {
+      let json = key.children().any(|x| x.has_tag_name((GRAPHML_NS, "desc")) && x.text() == Some("json"));
+      let ty = if json { "json" } else { key.attribute("attr.type").unwrap_or("string") };
+      key_names.insert(id, (name, ty));
+    }
+  }
+  let graph = doc
+    .root_element()
+    .children()
+    .find(|x| x.has_tag_name((GRAPHML_NS, "graph")))
+    .ok_or_else(|| format_err("Missing graph".to_string()))?;
+  let id_of = |node: roxmltree::Node, attr: &str| {
+    let id = node.attribute(attr).ok_or_else(|| format_err(format!("Missing {}", attr)))?;
+    let number = id.strip_prefix('n').unwrap_or(id);
+    number.parse::<usize>().map(NodeIndex).map_err(|_| format_err(format!("Invalid node id {}", id)))
+  };
+  let data_of = |node: roxmltree::Node| {
+    Vec::from_iter(node.children().filter(|x| x.has_tag_name((GRAPHML_NS, "data"))).filter_map(|x| {
+      let (name, ty) = x.attribute("key").and_then(|key| key_names.get(key))?;
+      Some((name.to_string(), *ty, x.text().unwrap_or_default().to_string()))
+    }))
+  };
+
+  let mut nodes = Vec::new();
+  let mut edges = Vec::new();
+  for x in graph.children() {
+    if x.has_tag_name((GRAPHML_NS, "node")) {
+      let id = id_of(x, "id")?;
+      let attrs = data_of(x);
+      let variant = attrs
+        .iter()
+        .find(|(name, _, _)| name == "type")
+        .map(|(_, _, text)| text.as_str())
+        .ok_or_else(|| format_err(format!("Missing type of node {}", id)))?;
+      let d = discriminant_by_name::<NodeT>(variant)
+        .ok_or_else(|| format_err(format!("Unknown node type {} of node {}", variant, id)))?;
+      let mut data = Vec::new();
+      for name in NodeT::data_names_of(d) {
+        if let Some((_, ty, text)) = attrs.iter().find(|(x, _, _)| *x == attr_name(name)) {
+          let value = parse_value(text, ty)
+            .ok_or_else(|| format_err(format!("Invalid {} value {} of {} in node {}", ty, text, name, id)))?;
+          data.push((*name, value));
+        }
+      }
+      nodes.push(FlatNode { id, variant: variant.to_string(), data });
+    } else if x.has_tag_name((GRAPHML_NS, "edge")) {
+      let source = id_of(x, "source")?;
+      let target = id_of(x, "target")?;
+      let link = data_of(x)
+        .into_iter()
+        .find(|(name, _, _)| name == "link")
+        .map(|(_, _, text)| text)
+        .ok_or_else(|| format_err(format!("Missing link of edge from {} to {}", source, target)))?;
+      edges.push((source, target, link));
+    }
+  }
+
+  unflatten(nodes, edges)
+}
+
+/// How the values of a data field are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+  Boolean,
+  Long,
+  Double,
+  String,
+  /// Any other value, or values of different types
+  Json,
+}
+
+impl ValueType {
+  fn of(value: &Value) -> Self {
+    match value {
+      Value::Bool(_) => ValueType::Boolean,
+      Value::Number(x) if x.is_i64() => ValueType::Long,
+      Value::Number(x) if x.is_f64() => ValueType::Double,
+      Value::String(_) => ValueType::String,
+      _ => ValueType::Json,
+    }
+  }
+
+  fn attr_type(self) -> &'static str {
+    match self {
+      ValueType::Boolean => "boolean",
+      ValueType::Long => "long",
+      ValueType::Double => "double",
+      ValueType::String | ValueType::Json => "string",
+    }
+  }
+}
+
+/// Read a data value by the type of its key, `json` for the keys marked by `<desc>json</desc>`
+fn parse_value(text: &str, ty: &str) -> Option<Value> {
+  match ty {
+    "json" => serde_json::from_str(text).ok(),
+    "boolean" => text.trim().parse::<bool>().ok().map(Value::from),
+    "int" | "long" => text.trim().parse::<i64>().ok().map(Value::from),
+    "float" | "double" => text.trim().parse::<f64>().ok().map(Value::from),
+    _ => Some(Value::from(text)),
+  }
+}
+
+/// The names of all data fields, in the order of the variants
+fn data_keys<NodeT: NodeEnum>() -> Vec<&'static str> {
+  let mut result = Vec::new();
+  let mut cur = Some(NodeT::Discriminant::first());
+  while let Some(d) = cur {
+    for name in NodeT::data_names_of(d) {
+      if !result.contains(name) {
+        result.push(*name);
+      }
+    }
+    cur = d.next();
+  }
+  result
+}
+
+/// Escape a string to be used in XML text or attributes
+fn escape(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
@@ -0,0 +1,258 @@
+//! Persist a graph to a local directory with a snapshot and a journal of the committed changes, see [`Journal`]
+//!
+//! The directory contains two files:
+//! + `snapshot.json`: the graph serialized by [`GraphSerializer`], with the sequence number of the last journal record it contains.
+//! + `journal.jsonl`: one JSON record per line, each record is the [`Op`] list of a commit, see [`CommitReport::to_ops`].
+//!
+//! A snapshot is written to a temporary file and renamed, then the journal is cleared.
+//! Records already contained by the snapshot are skipped when recovering, so a crash at any point leaves a consistent state.
+//! If the process crashes when writing a record, the truncated last record is discarded when the journal is opened again.
+//!
+//! Only the commits through [`Journal::commit`] are recorded. The changes made by [`Graph::data_mut`] and
+//! [`iter_nodes_data_mut!`](crate::iter_nodes_data_mut!) bypass the journal,
+//! so they are lost unless a [`snapshot`](Journal::snapshot) is written after them.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use super::serialize::{try_deserialize_graph, GraphSerializer};
+use super::*;
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const SNAPSHOT_TMP_FILE: &str = "snapshot.json.tmp";
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+#[derive(Serialize)]
+struct SnapshotWrite<'a, G> {
+  seq: u64,
+  graph: &'a G,
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "NodeT: DeserializeOwned"))]
+struct SnapshotRead<NodeT: NodeEnum> {
+  seq: u64,
+  graph: GraphSerializer<NodeT>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record<NodeT> {
+  seq: u64,
+  ops: Vec<Op<NodeT>>,
+}
+
+/// A journal persisting a [`Graph`] to a local directory, so that the graph can be recovered after the process exits or crashes.
+///
+/// Commit the transactions through [`commit`](Journal::commit), the changes are appended to the journal file before it returns.
+/// Changes made by [`Graph::data_mut`] are not recorded, write a [`snapshot`](Journal::snapshot) after them.
+/// A snapshot of the whole graph is written every [`snapshot_interval`](Journal::set_snapshot_interval) records, or by [`snapshot`](Journal::snapshot).
+///
+/// See the [module documentation](self) for the files.
+///
+/// # Example
+/// ```
+/// use ttgraph::{*, journal::Journal};
+/// use serde::{Serialize, Deserialize};
+/// #[derive(TypedNode, Clone, Serialize, Deserialize)]
+/// struct NodeA{
+///   data: usize,
+/// }
+/// node_enum!{
+///   #[derive(Clone, Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let dir = std::env::temp_dir().join(format!("ttgraph_journal_doc_{}", std::process::id()));
+/// # let _ = std::fs::remove_dir_all(&dir);
+/// let (mut journal, ctx, mut graph) = Journal::<Node>::open(&dir).unwrap();
+/// let mut trans = Transaction::new(&ctx);
+/// let idx = trans.insert(Node::A(NodeA{ data: 1 }));
+/// journal.commit(&mut graph, trans).unwrap();
+/// drop(journal);
+///
+/// // Reopen the directory, the graph is recovered
+/// let (journal, ctx, graph) = Journal::<Node>::open(&dir).unwrap();
+/// assert_eq!(get_node!(graph, Node::A, idx).unwrap().data, 1);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Journal<NodeT> {
+  dir: PathBuf,
+  file: File,
+  ctx_id: Uuid,
+  seq: u64,
+  unsnapshotted: usize,
+  snapshot_interval: usize,
+  _phantom: PhantomData<NodeT>,
+}
+
+impl<NodeT> Journal<NodeT>
+where
+  NodeT: NodeEnum + Clone + Serialize + DeserializeOwned + 'static,
+{
+  /// Open the journal in a directory, and recover the context and the graph from the snapshot and the journal.
+  ///
+  /// The directory is created with an empty graph if it does not exist.
+  /// A truncated last record is discarded. Other broken records and an invalid snapshot are reported as
+  /// [`io::ErrorKind::InvalidData`], the latter with a [`DeserializeError`](super::serialize::DeserializeError) inside.
+  pub fn open(dir: impl AsRef<Path>) -> io::Result<(Self, Context, Graph<NodeT>)> {
+    let dir = dir.as_ref().to_path_buf();
+    fs::create_dir_all(&dir)?;
+
+    let snapshot_path = dir.join(SNAPSHOT_FILE);
+    let (mut seq, ctx, mut graph) = if snapshot_path.exists() {
+      let snapshot: SnapshotRead<NodeT> = serde_json::from_reader(BufReader::new(File::open(&snapshot_path)?))?;
+      let (ctx, graph) = try_deserialize_graph(snapshot.graph, false, true)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+      (snapshot.seq, ctx, graph)
+    } else {
+      let ctx = Context::new();
+      let graph = Graph::new(&ctx);
+      write_snapshot(&dir, 0, &graph)?;
+      (0, ctx, graph)
+    };
+
+    let mut file =
+      OpenOptions::new().read(true).write(true).create(true).truncate(false).open(dir.join(JOURNAL_FILE))?;
+    let records = read_records::<NodeT>(&mut file)?;
+    let mut unsnapshotted = 0;
+    for record in records {
+      if record.seq <= seq {
+        continue;
+      }
+      let mut trans = Transaction::new(&ctx);
+      trans.apply_ops(record.ops);
+      graph.commit(trans);
+      seq = record.seq;
+      unsnapshotted += 1;
+    }
+
+    let journal = Journal {
+      dir,
+      file,
+      ctx_id: ctx.id,
+      seq,
+      unsnapshotted,
+      snapshot_interval: 1000,
+      _phantom: PhantomData,
+    };
+    Ok((journal, ctx, graph))
+  }
+
+  /// Commit a transaction to the graph, and append the changes to the journal.
+  ///
+  /// The graph is committed even if writing the journal fails. Nothing is written if the commit changes nothing.
+  /// If writing fails, the partially written record is removed, so the journal can still be recovered,
+  /// and the next commit reuses the sequence number of the failed record.
+  ///
+  /// # Panic
+  /// Panic if the graph is not the one opened by this journal.
+  pub fn commit(&mut self, graph: &mut Graph<NodeT>, t: Transaction<NodeT>) -> io::Result<CommitReport<NodeT>> {
+    assert!(graph.ctx_id == self.ctx_id, "The graph is not opened by this journal!");
+    let report = graph.commit_with_report(t);
+    if report.is_empty() {
+      return Ok(report);
+    }
+
+    let record = Record { seq: self.seq + 1, ops: report.to_ops(graph) };
+    let mut line = serde_json::to_vec(&record)?;
+    line.push(b'\n');
+    let offset = self.file.stream_position()?;
+    if let Err(e) = self.file.write_all(&line).and_then(|_| self.file.sync_data()) {
+      // Remove the partial record, so it does not break the records after it
+      self.file.set_len(offset)?;
+      self.file.seek(SeekFrom::Start(offset))?;
+      return Err(e);
+    }
+
+    self.seq += 1;
+    self.unsnapshotted += 1;
+    if self.snapshot_interval != 0 && self.unsnapshotted >= self.snapshot_interval {
+      self.snapshot(graph)?;
+    }
+    Ok(report)
+  }
+
+  /// Write a snapshot of the graph and clear the journal
+  ///
+  /// # Panic
+  /// Panic if the graph is not the one opened by this journal.
+  pub fn snapshot(&mut self, graph: &Graph<NodeT>) -> io::Result<()> {
+    assert!(graph.ctx_id == self.ctx_id, "The graph is not opened by this journal!");
+    write_snapshot(&self.dir, self.seq, graph)?;
+    self.file.set_len(0)?;
+    self.file.seek(SeekFrom::Start(0))?;
+    self.file.sync_data()?;
+    self.unsnapshotted = 0;
+    Ok(())
+  }
+
+  /// Set the number of records between automatic snapshots, 0 means never. The default is 1000.
+  pub fn set_snapshot_interval(&mut self, interval: usize) {
+    self.snapshot_interval = interval;
+  }
+
+  /// Get the number of records between automatic snapshots
+  pub fn snapshot_interval(&self) -> usize {
+    self.snapshot_interval
+  }
+
+  /// Get the number of records written to the journal since the last snapshot
+  pub fn unsnapshotted(&self) -> usize {
+    self.unsnapshotted
+  }
+}
+
+/// Write the snapshot to a temporary file, then rename it, so that the old snapshot is kept if the process crashes
+fn write_snapshot<NodeT>(dir: &Path, seq: u64, graph: &Graph<NodeT>) -> io::Result<()>
+where
+  NodeT: NodeEnum + Serialize + 'static,
+{
+  let tmp_path = dir.join(SNAPSHOT_TMP_FILE);
+  let mut tmp = File::create(&tmp_path)?;
+  serde_json::to_writer(&mut tmp, &SnapshotWrite { seq, graph })?;
+  tmp.sync_all()?;
+  fs::rename(tmp_path, dir.join(SNAPSHOT_FILE))
+}
+
+/// Read all records of the journal, discard the truncated last record, and leave the file cursor at the end of the valid records
+fn read_records<NodeT: DeserializeOwned>(file: &mut File) -> io::Result<Vec<Record<NodeT>>> {
+  let mut lines = Vec::new();
+  let mut reader = BufReader::new(&mut *file);
+  loop {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+      break;
+    }
+    lines.push(line);
+  }
+  drop(reader);
+
+  let mut records = Vec::new();
+  let mut valid_len = 0;
+  let count = lines.len();
+  for (i, line) in lines.into_iter().enumerate() {
+    let complete = line.ends_with(b"\n");
+    match serde_json::from_slice::<Record<NodeT>>(&line) {
+      Err(e) if i + 1 < count => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+      Ok(record) if complete => {
+        valid_len += line.len() as u64;
+        records.push(record);
+      },
+      // The last record is truncated
+      _ => break,
+    }
+  }
+
+  file.set_len(valid_len)?;
+  file.seek(SeekFrom::Start(valid_len))?;
+  Ok(records)
+}
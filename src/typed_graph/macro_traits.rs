@@ -24,6 +24,10 @@ pub trait TypedNode {
   type Source: Copy + Clone + Eq + PartialEq + Debug + Hash + PartialOrd + Ord + Sized + 'static;
   type LinkMirror: Copy + Clone + Eq + PartialEq + Debug + Hash + PartialOrd + Ord + Sized + 'static;
   type LoGMirror: Copy + Clone + Eq + PartialEq + Debug + Hash + PartialOrd + Ord + Sized + 'static;
+  /// A view with mutable references to the data fields (i.e. all fields except links)
+  type DataMut<'a>
+  where
+    Self: 'a;
   // type Iter: SourceIterator<Self, Source = Self::Source>;
 
   /// Iterate the links and its source reflection
@@ -45,8 +49,14 @@ pub trait TypedNode {
   fn link_mirrors() -> &'static [Self::LinkMirror];
   /// Get the name of the links
   fn link_names() -> &'static [&'static str];
+  /// Get the name of a link
+  fn link_name(link: Self::LinkMirror) -> &'static str {
+    Self::link_names()[Self::link_mirrors().iter().position(|x| *x == link).unwrap()]
+  }
   /// Get the links by name
   fn get_links_by_name(&self, name: &'static str) -> Box<dyn Iterator<Item = NodeIndex> + '_>;
+  /// Replace the targets of a link by name, return false if there is no such link
+  fn set_links_by_name(&mut self, name: &str, targets: Vec<NodeIndex>) -> bool;
   /// Get the links by group name
   fn get_links_by_group(&self, name: &'static str) -> Vec<NodeIndex>;
 
@@ -55,8 +65,12 @@ pub trait TypedNode {
   // fn data_types() -> [TypeId];
   /// Get the name of the data
   fn data_names() -> &'static [&'static str];
+  /// Get the type of the data as written in the struct, in the same order as [`data_names`](TypedNode::data_names)
+  fn data_type_names() -> &'static [&'static str];
   /// Try to get the reference of a data by name
   fn data_ref_by_name<T: Any>(&self, name: &'static str) -> Option<&T>;
+  /// Get a mutable view of the data fields, the links can not be modified through it
+  fn data_mut(&mut self) -> Self::DataMut<'_>;
 
   /// Convert Source to LinkMirror
   fn to_source(input: Self::LinkMirror) -> Self::Source;
@@ -97,6 +111,10 @@ pub trait NodeEnum: Discriminated<Self::Discriminant> {
   type LoGMirrorEnum: Copy + Clone + Eq + PartialEq + Debug + Hash + PartialOrd + Ord + Sized + 'static;
   type Discriminant: NodeDiscriminant;
   type GenArena: CateArena<V = Self, D = Self::Discriminant> + Sized + 'static;
+  /// An enum of the data views of all variants, see [`TypedNode::DataMut`]
+  type DataMut<'a>
+  where
+    Self: 'a;
   /// Iterate the links and its source reflection
   fn iter_sources(&self) -> Box<dyn Iterator<Item = (NodeIndex, Self::SourceEnum)>>;
   /// Iterate the links and its link reflection
@@ -116,14 +134,31 @@ pub trait NodeEnum: Discriminated<Self::Discriminant> {
 
   /// Get the links by name
   fn get_links_by_name(&self, name: &'static str) -> Box<dyn Iterator<Item = NodeIndex> + '_>;
+  /// Replace the targets of a link by name, return false if there is no such link
+  fn set_links_by_name(&mut self, name: &str, targets: Vec<NodeIndex>) -> bool;
   /// Get the links by group name
   fn get_links_by_group(&self, name: &'static str) -> Vec<NodeIndex>;
 
   /// Tell if this node is inside the named group
   fn in_group(&self, name: &'static str) -> bool;
+  /// Get the discriminants of the variants inside the named group, in the order of declaration
+  fn group_discriminants(name: &str) -> &'static [Self::Discriminant];
+  /// Get the names of all groups, in the order of declaration
+  fn group_names() -> &'static [&'static str];
 
   /// Try to get the reference of a data by name
   fn data_ref_by_name<T: Any>(&self, name: &'static str) -> Option<&T>;
+  /// Get a mutable view of the data fields, the links can not be modified through it
+  fn data_mut(&mut self) -> Self::DataMut<'_>;
+
+  /// Get the name of a link, i.e. the field name
+  fn link_name(link: Self::LinkMirrorEnum) -> &'static str;
+  /// Get the names of the data fields of a variant, see [`TypedNode::data_names`]
+  fn data_names_of(d: Self::Discriminant) -> &'static [&'static str];
+  /// Get the names of the links of a variant, see [`TypedNode::link_names`]
+  fn link_names_of(d: Self::Discriminant) -> &'static [&'static str];
+  /// Get the types of the links of a variant, in the same order as [`link_names_of`](NodeEnum::link_names_of)
+  fn link_types_of(d: Self::Discriminant) -> &'static [LinkType];
 
   /// Convert LinkMirrorEnum to SourceEnum
   fn to_source_enum(input: Self::LinkMirrorEnum) -> Self::SourceEnum;
@@ -161,6 +196,12 @@ pub trait NodeEnum: Discriminated<Self::Discriminant> {
   fn check_link_type_by_group(target: Self::Discriminant, link: Self::LoGMirrorEnum) -> LinkTypeCheckResult<Self>;
 
   fn match_bd_link_group(&self, links: Vec<Self::LinkMirrorEnum>) -> Vec<Self::LinkMirrorEnum>;
+
+  /// Visit the data fields declared in `index!`
+  fn visit_indexes<V: IndexVisitor>(&self, visitor: &mut V);
+
+  /// Get the schema of the node enum, see [`GraphSchema`]
+  fn schema() -> GraphSchema;
 }
 
 pub type BidirectionalLinks<LinkMirrorT> = Vec<(Vec<NodeIndex>, Vec<LinkMirrorT>)>;
@@ -175,7 +216,7 @@ pub struct ModifyResult<LinkMirrorT> {
 }
 
 /// Types of links in a `TypeNode`
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum LinkType {
   Point, // Single NodeIndex
   Set,  // HashSet or BTreeSet or OrderSet or IndexSet
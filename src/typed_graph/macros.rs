@@ -90,6 +90,107 @@ macro_rules! iter_nodes {
   };
 }
 
+/// Get a mutable view of the data fields of a node, assume it is $var variant of the NodeEnum. Returns `Option<NodeTypeData>`
+///
+/// `NodeTypeData` is generated by `TypedNode` derive, with `&mut` to every field except links. See [`data_mut`](crate::Graph::data_mut) for more information.
+///
+/// # Example
+/// ```
+/// use ttgraph::*;
+///
+/// #[derive(TypedNode)]
+/// struct NodeA{
+///   a: usize,
+///   name: String,
+///   link: NodeIndex,
+/// }
+///
+/// node_enum!{
+///   enum MyNodeEnum{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<MyNodeEnum>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+///
+/// let x = trans.insert(MyNodeEnum::A(NodeA{ a: 1, name: "x".to_string(), link: NodeIndex::empty() }));
+/// graph.commit(trans);
+///
+/// // a: Option<NodeAData>
+/// let a = get_data_mut!(graph, MyNodeEnum::A, x).unwrap();
+/// *a.a = 2;
+/// a.name.push('y');
+/// // Links can not be accessed
+/// // *a.link = x;
+///
+/// let a = get_node!(graph, MyNodeEnum::A, x).unwrap();
+/// assert_eq!(a.a, 2);
+/// assert_eq!(a.name, "xy");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! get_data_mut {
+  ($graph: expr, $var: path, $idx: expr) => {
+    $graph.data_mut($idx).and_then(|x| $crate::data_mut_of!($var, x))
+  };
+}
+
+/// Iterate a type of nodes with mutable views of their data fields, assume they are $var variant of the NodeEnum. Returns `impl Iterator<Item = (NodeIndex, NodeTypeData)>`
+///
+/// See [`get_data_mut!`](crate::get_data_mut!) and [`data_mut`](crate::Graph::data_mut) for more information.
+///
+/// # Example
+/// ```
+/// use ttgraph::*;
+///
+/// #[derive(TypedNode)]
+/// struct NodeA{
+///   a: usize
+/// }
+/// #[derive(TypedNode)]
+/// struct NodeB{
+///   b: usize
+/// }
+///
+/// node_enum!{
+///   enum MyNodeEnum{
+///     A(NodeA),
+///     B(NodeB),
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<MyNodeEnum>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+///
+/// trans.insert(MyNodeEnum::A(NodeA{ a: 1 }));
+/// trans.insert(MyNodeEnum::A(NodeA{ a: 2 }));
+/// trans.insert(MyNodeEnum::B(NodeB{ b: 0 }));
+/// graph.commit(trans);
+///
+/// for (_, a) in iter_nodes_data_mut!(graph, MyNodeEnum::A) {
+///   *a.a *= 10;
+/// }
+/// assert_eq!(Vec::from_iter(iter_nodes!(graph, MyNodeEnum::A).map(|(_, x)| x.a)), vec![10, 20]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! iter_nodes_data_mut {
+  ($graph: expr, $p: path) => {
+    $graph.iter_type_data_mut($crate::discriminant!($p)).map(|(idx, x)| {
+      if let Some(x) = $crate::data_mut_of!($p, x) {
+        (idx, x)
+      } else {
+        panic!()
+      }
+    })
+  };
+}
+
 /// Use the [`mutate`](crate::Transaction::mutate) method of the transaction, assume the node is $var variant of the NodeEnum.
 /// Panics if the enum does not match.
 ///
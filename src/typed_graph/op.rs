@@ -0,0 +1,126 @@
+//! Data-only operations which can be serialized, see [`Op`]
+
+use super::*;
+
+/// A data-only operation on a graph, which can be serialized with serde and applied to a [`Transaction`] by [`apply_op`](Transaction::apply_op).
+///
+/// Unlike the closures of [`mutate`](Transaction::mutate) and [`update`](Transaction::update), operations can be sent to another process and replayed.
+/// The graph receiving the operations should use the same [`Context`], e.g. deserialized from the sender by [`deserialize_graph`](crate::serialize::deserialize_graph), so that the [`NodeIndex`] are the same.
+///
+/// + `Insert`: insert a node with a known index
+/// + `Remove`: remove a node
+/// + `SetLink`: replace all targets of a link, the link is named by the field name
+/// + `Replace`: replace a node with a new value
+/// + `Redirect`: redirect all links pointing to `old` to `new`, same as [`redirect_all_links`](Transaction::redirect_all_links)
+///
+/// # Example
+/// ```
+/// use ttgraph::*;
+/// use serde::{Serialize, Deserialize};
+/// use std::collections::BTreeSet;
+/// #[derive(TypedNode, Serialize, Deserialize)]
+/// struct NodeA{
+///   data: usize,
+///   to: BTreeSet<NodeIndex>,
+/// }
+/// node_enum!{
+///   #[derive(Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let a = trans.insert(Node::A(NodeA{ data: 1, to: BTreeSet::new() }));
+/// let b = trans.insert(Node::A(NodeA{ data: 2, to: BTreeSet::new() }));
+/// graph.commit(trans);
+///
+/// let ops = vec![
+///   Op::SetLink{ node: a, link: "to".to_string(), targets: vec![b] },
+///   Op::Replace{ node: b, value: Node::A(NodeA{ data: 3, to: BTreeSet::from([a]) }) },
+/// ];
+/// let serialized = serde_json::to_string(&ops).unwrap();
+///
+/// let ops: Vec<Op<Node>> = serde_json::from_str(&serialized).unwrap();
+/// let mut trans = Transaction::new(&ctx);
+/// trans.apply_ops(ops);
+/// graph.commit(trans);
+/// assert_eq!(get_node!(graph, Node::A, a).unwrap().to, BTreeSet::from([b]));
+/// assert_eq!(get_node!(graph, Node::A, b).unwrap().data, 3);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Op<NodeT> {
+  Insert { node: NodeIndex, value: NodeT },
+  Remove { node: NodeIndex },
+  SetLink { node: NodeIndex, link: String, targets: Vec<NodeIndex> },
+  Replace { node: NodeIndex, value: NodeT },
+  Redirect { old: NodeIndex, new: NodeIndex },
+}
+
+impl<'a, NodeT, Arena> Transaction<'a, NodeT, Arena>
+where
+  NodeT: NodeEnum + 'a,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Apply an [`Op`] to the transaction
+  ///
+  /// # Panic
+  /// + `Insert` panics if the index is already used in this transaction.
+  ///   It panics when committed if the index is already in the graph. An index allocated by this transaction is filled back.
+  /// + `SetLink` panics when committed if the node has no such link, or multiple targets are set to a `NodeIndex` link.
+  pub fn apply_op(&mut self, op: Op<NodeT>) {
+    match op {
+      Op::Insert { node, value } => {
+        self.node_dist.reserve(node.0);
+        if self.inc_nodes.contains(node) {
+          panic!("Insert an existing index {:?}", node);
+        }
+        // Fill back the index if it is allocated by this transaction
+        self.inc_nodes.fill_back_untyped(node, value);
+        self.alloc_nodes.swap_remove(&node);
+      },
+      Op::Remove { node } => self.remove(node),
+      Op::SetLink { node, link, targets } => self.mutate(node, move |x| {
+        if !x.set_links_by_name(&link, targets) {
+          panic!("Node {:?} has no link {}", node, link);
+        }
+      }),
+      Op::Replace { node, value } => self.update(node, move |_| value),
+      Op::Redirect { old, new } => self.redirect_all_links(old, new),
+    }
+  }
+
+  /// Apply a sequence of [`Op`] to the transaction in order
+  pub fn apply_ops(&mut self, ops: impl IntoIterator<Item = Op<NodeT>>) {
+    for op in ops {
+      self.apply_op(op);
+    }
+  }
+}
+
+impl<NodeT: NodeEnum + Clone> CommitReport<NodeT> {
+  /// Convert the report into [`Op`] which redo the commit on a copy of the graph before the commit.
+  ///
+  /// The inserted nodes become `Insert`, the modified nodes become `Replace` with their new values, and the removed nodes become `Remove`.
+  /// `graph` should be the graph just committed, where the new values are read.
+  pub fn to_ops<Arena>(&self, graph: &Graph<NodeT, Arena>) -> Vec<Op<NodeT>>
+  where
+    Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  {
+    let mut result = Vec::new();
+    for x in &self.inserted {
+      result.push(Op::Insert { node: *x, value: graph.get(*x).unwrap().clone() });
+    }
+    for x in &self.modified {
+      result.push(Op::Replace { node: *x, value: graph.get(*x).unwrap().clone() });
+    }
+    for x in &self.removed {
+      result.push(Op::Remove { node: *x });
+    }
+    result
+  }
+}
@@ -0,0 +1,75 @@
+//! Parallel iteration over the nodes, requires feature `rayon`
+#![cfg(feature = "rayon")]
+
+use rayon::prelude::*;
+
+use super::*;
+
+impl<NodeT, Arena> Graph<NodeT, Arena>
+where
+  NodeT: NodeEnum + Sync,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Iterate all nodes in parallel with [rayon](https://docs.rs/rayon), requires feature `rayon`
+  ///
+  /// The nodes are gathered from the persistent containers first, then split into parallel jobs.
+  /// The order of nodes is not preserved,
+  /// use [`collect`](ParallelIterator::collect) into an ordered container if needed.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// use rayon::prelude::*;
+  /// #[derive(TypedNode)]
+  /// struct NodeA{
+  ///   a: usize,
+  /// }
+  /// #[derive(TypedNode)]
+  /// struct NodeB{
+  ///   b: usize,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     A(NodeA),
+  ///     B(NodeB),
+  ///   }
+  ///   group!{
+  ///     first{A},
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// trans.extend((0..1000).map(|a| Node::A(NodeA{ a })));
+  /// trans.extend((0..1000).map(|b| Node::B(NodeB{ b })));
+  /// graph.commit(trans);
+  ///
+  /// assert_eq!(graph.par_iter().count(), 2000);
+  /// let sum: usize = graph.par_iter_type(discriminant!(Node::A)).map(|(_, x)| match x {
+  ///   Node::A(x) => x.a,
+  ///   _ => panic!(),
+  /// }).sum();
+  /// assert_eq!(sum, 499500);
+  /// assert_eq!(graph.par_iter_group("first").count(), 1000);
+  /// # }
+  /// ```
+  pub fn par_iter(&self) -> impl ParallelIterator<Item = (NodeIndex, &NodeT)> {
+    Vec::from_iter(self.iter()).into_par_iter()
+  }
+
+  /// Iterate a type of nodes in parallel, requires feature `rayon`
+  ///
+  /// See [`par_iter`](Graph::par_iter) for the example.
+  pub fn par_iter_type(&self, d: NodeT::Discriminant) -> impl IndexedParallelIterator<Item = (NodeIndex, &NodeT)> {
+    Vec::from_iter(self.nodes.get_container(d).iter().map(|(k, v)| (NodeIndex(k), v))).into_par_iter()
+  }
+
+  /// Iterate all nodes within the named group in parallel, requires feature `rayon`
+  ///
+  /// See [`par_iter`](Graph::par_iter) for the example.
+  pub fn par_iter_group(&self, name: &'static str) -> impl ParallelIterator<Item = (NodeIndex, &NodeT)> {
+    Vec::from_iter(self.iter_group(name)).into_par_iter()
+  }
+}
@@ -0,0 +1,63 @@
+//! Report of the changes made by a commit, see [`Graph::commit_with_report`]
+
+use super::*;
+
+/// The changes made by a commit, returned by [`commit_with_report`](Graph::commit_with_report)
+///
+/// + `inserted`: nodes inserted by the transaction
+/// + `removed`: nodes removed by the transaction
+/// + `modified`: existing nodes changed by the transaction, including nodes mutated or updated, and nodes whose links are changed by redirection, removal of the linked node, or bidirectional links
+/// + `added_links` and `removed_links`: every link added or removed, as `(source node, target node, link)`, including the ones maintained for bidirectional links
+///
+/// A node inserted and removed in the same transaction does not appear in the report.
+#[derive(Debug, Clone)]
+pub struct CommitReport<NodeT: NodeEnum> {
+  pub inserted: OrderSet<NodeIndex>,
+  pub removed: OrderSet<NodeIndex>,
+  pub modified: OrderSet<NodeIndex>,
+  pub added_links: OrderSet<(NodeIndex, NodeIndex, NodeT::LinkMirrorEnum)>,
+  pub removed_links: OrderSet<(NodeIndex, NodeIndex, NodeT::LinkMirrorEnum)>,
+}
+
+impl<NodeT: NodeEnum> CommitReport<NodeT> {
+  /// Collect the nodes touched by the transaction, should be called before the commit
+  pub(crate) fn new<Arena>(t: &Transaction<NodeT, Arena>) -> Self
+  where
+    Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  {
+    let inserted = OrderSet::from_iter(t.inc_nodes.iter().map(|(x, _)| x));
+    let removed = OrderSet::from_iter(t.dec_nodes.iter().copied());
+    let mut modified = OrderSet::new();
+    modified.extend(t.mut_nodes.iter().map(|(x, _)| *x));
+    modified.extend(t.update_nodes.iter().map(|(x, _)| *x));
+    CommitReport {
+      inserted,
+      removed,
+      modified,
+      added_links: OrderSet::new(),
+      removed_links: OrderSet::new(),
+    }
+  }
+
+  /// Fill the link changes after the commit
+  pub(super) fn add_link_changes(&mut self, lcr: LinkChangeRecorder<NodeT>) {
+    for (x, _, _) in lcr.adds.iter().chain(lcr.removes.iter()) {
+      self.modified.insert(*x);
+    }
+    self.modified.retain(|x| !self.inserted.contains(x) && !self.removed.contains(x));
+    let transient: OrderSet<NodeIndex> = OrderSet::from_iter(self.inserted.intersection(&self.removed).copied());
+    self.inserted.retain(|x| !transient.contains(x));
+    self.removed.retain(|x| !transient.contains(x));
+    self.added_links = lcr.adds;
+    self.removed_links = lcr.removes;
+  }
+
+  /// Check if nothing is changed
+  pub fn is_empty(&self) -> bool {
+    self.inserted.is_empty()
+      && self.removed.is_empty()
+      && self.modified.is_empty()
+      && self.added_links.is_empty()
+      && self.removed_links.is_empty()
+  }
+}
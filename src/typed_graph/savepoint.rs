@@ -0,0 +1,165 @@
+//! Savepoints and child transactions, to abandon a part of a transaction
+
+use super::*;
+
+/// The state of a transaction made by [`savepoint`](Transaction::savepoint), used to [`rollback_to`](Transaction::rollback_to)
+pub struct Savepoint<NodeT, Arena = <NodeT as NodeEnum>::GenArena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  trans_id: Uuid,
+  alloc_nodes: OrderSet<NodeIndex>,
+  inc_nodes: Arena,
+  dec_nodes: OrderSet<NodeIndex>,
+  dropped_nodes: OrderSet<NodeIndex>,
+  read_nodes: OrderSet<NodeIndex>,
+  base_version: Option<u64>,
+  mut_nodes_len: usize,
+  update_nodes_len: usize,
+  redirect_all_links_len: usize,
+  redirect_links_len: usize,
+}
+
+impl<'a, NodeT, Arena, Funcs> Transaction<'a, NodeT, Arena, Funcs>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  Funcs: FuncKind<'a, NodeT>,
+{
+  /// Remember the current state of the transaction, so that the operations after it can be discarded by [`rollback_to`](Transaction::rollback_to).
+  ///
+  /// The new nodes are shared with the savepoint until they are changed, like [`fork`](Graph::fork).
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode, Clone)]
+  /// struct NodeA{
+  ///   data: usize,
+  /// }
+  /// node_enum!{
+  ///   #[derive(Clone)]
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let a = trans.insert(Node::A(NodeA{ data: 1 }));
+  ///
+  /// let savepoint = trans.savepoint();
+  /// let b = trans.insert(Node::A(NodeA{ data: 2 }));
+  /// mut_node!(trans, Node::A, a, |x| { x.data = 10 });
+  /// // Give up the operations after the savepoint
+  /// trans.rollback_to(savepoint);
+  ///
+  /// graph.commit(trans);
+  /// assert_eq!(get_node!(graph, Node::A, a).unwrap().data, 1);
+  /// assert!(graph.get(b).is_none());
+  /// # }
+  /// ```
+  pub fn savepoint(&self) -> Savepoint<NodeT, Arena> {
+    Savepoint {
+      trans_id: self.trans_id,
+      alloc_nodes: self.alloc_nodes.clone(),
+      inc_nodes: self.inc_nodes.fork(),
+      dec_nodes: self.dec_nodes.clone(),
+      dropped_nodes: self.dropped_nodes.clone(),
+      read_nodes: self.read_nodes.clone(),
+      base_version: self.base_version,
+      mut_nodes_len: self.mut_nodes.len(),
+      update_nodes_len: self.update_nodes.len(),
+      redirect_all_links_len: self.redirect_all_links_vec.len(),
+      redirect_links_len: self.redirect_links_vec.len(),
+    }
+  }
+
+  /// Discard all operations after the savepoint was made
+  ///
+  /// A savepoint can be used only once. Savepoints made after it become invalid.
+  /// The base version of [`begin`](Transaction::begin) is restored,
+  /// in case it is lowered by [`absorb`](Transaction::absorb).
+  ///
+  /// # Panic
+  /// Panic if the savepoint is not made by this transaction, or it is made after another savepoint which has been rolled back to.
+  pub fn rollback_to(&mut self, savepoint: Savepoint<NodeT, Arena>) {
+    assert!(
+      savepoint.trans_id == self.trans_id
+        && savepoint.mut_nodes_len <= self.mut_nodes.len()
+        && savepoint.update_nodes_len <= self.update_nodes.len()
+        && savepoint.redirect_all_links_len <= self.redirect_all_links_vec.len()
+        && savepoint.redirect_links_len <= self.redirect_links_vec.len(),
+      "Rollback to a savepoint not belonging to this transaction!"
+    );
+    self.alloc_nodes = savepoint.alloc_nodes;
+    self.inc_nodes = savepoint.inc_nodes;
+    self.dec_nodes = savepoint.dec_nodes;
+    self.dropped_nodes = savepoint.dropped_nodes;
+    self.read_nodes = savepoint.read_nodes;
+    // The base version may be lowered by absorbing a child transaction began earlier
+    self.base_version = savepoint.base_version;
+    self.mut_nodes.truncate(savepoint.mut_nodes_len);
+    self.update_nodes.truncate(savepoint.update_nodes_len);
+    self.redirect_all_links_vec.truncate(savepoint.redirect_all_links_len);
+    self.redirect_links_vec.truncate(savepoint.redirect_links_len);
+  }
+}
+
+impl<'a, NodeT, Arena, Funcs> Transaction<'a, NodeT, Arena, Funcs>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  Funcs: FuncKind<'a, NodeT>,
+{
+  /// Fold a child transaction into this transaction, as if all operations of the child were done on this transaction after the existing ones.
+  ///
+  /// Same as [`append`](Transaction::append), but the contradictory operations are not reported.
+  ///
+  /// A child transaction is any transaction from the same [`Context`]. It can be built separately, and simply dropped if the attempt fails.
+  /// The child can remove, mutate or update the nodes inserted by this transaction, if it knows the [`NodeIndex`].
+  ///
+  /// # Panic
+  /// Panic if the transactions have different contexts.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode)]
+  /// struct NodeA{
+  ///   data: usize,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let a = trans.insert(Node::A(NodeA{ data: 1 }));
+  ///
+  /// for i in 0..4 {
+  ///   let mut child = Transaction::new(&ctx);
+  ///   child.insert(Node::A(NodeA{ data: i }));
+  ///   mut_node!(child, Node::A, a, move |x| { x.data += i });
+  ///   // Only keep the attempts with even i
+  ///   if i % 2 == 0 {
+  ///     trans.absorb(child);
+  ///   }
+  /// }
+  ///
+  /// graph.commit(trans);
+  /// assert_eq!(graph.len(), 3);
+  /// assert_eq!(get_node!(graph, Node::A, a).unwrap().data, 3);
+  /// # }
+  /// ```
+  pub fn absorb(&mut self, child: Transaction<'a, NodeT, Arena, Funcs>) {
+    self.append(child);
+  }
+}
@@ -0,0 +1,195 @@
+//! Runtime description of a node enum, see [`GraphSchema`]
+
+use super::*;
+
+/// The schema of a [`NodeEnum`], generated by [`node_enum!`] and obtained by [`NodeEnum::schema`]
+///
+/// It describes the variants with their data fields and links, the groups, the `link_type!` constraints and the `bidirectional!` links.
+/// Groups in `link_type!` and `bidirectional!` are expanded to variants.
+/// The schema is serializable, so that tools can work on any graph without knowing the node types.
+///
+/// # Example
+/// ```
+/// use ttgraph::*;
+/// use std::collections::BTreeSet;
+/// #[derive(TypedNode)]
+/// struct FactoryNode{
+///   name: String,
+///   workers: BTreeSet<NodeIndex>,
+/// }
+/// #[derive(TypedNode)]
+/// struct WorkerNode{
+///   factory: NodeIndex,
+/// }
+/// node_enum!{
+///   enum Node{
+///     Factory(FactoryNode),
+///     Worker(WorkerNode),
+///   }
+///   bidirectional!{
+///     Factory.workers <-> Worker.factory,
+///   }
+///   link_type!{
+///     Factory.workers: Worker,
+///   }
+/// }
+///
+/// # fn main() {
+/// let schema = Node::schema();
+/// assert_eq!(schema.name, "Node");
+/// let factory = schema.variant("Factory").unwrap();
+/// assert_eq!(factory.type_name, "FactoryNode");
+/// assert_eq!(factory.data[0], DataSchema{ name: "name".to_string(), type_name: "String".to_string() });
+/// assert_eq!(factory.links[0].link_type, LinkType::Set);
+/// assert_eq!(schema.link_types[0].targets, vec!["Worker".to_string()]);
+/// assert_eq!(schema.bidirectional.len(), 1);
+/// println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphSchema {
+  /// The name of the node enum
+  pub name: String,
+  pub variants: Vec<VariantSchema>,
+  pub groups: Vec<GroupSchema>,
+  pub link_types: Vec<LinkTypeSchema>,
+  pub bidirectional: Vec<BidirectionalSchema>,
+}
+
+impl GraphSchema {
+  /// Find a variant by name
+  pub fn variant(&self, name: &str) -> Option<&VariantSchema> {
+    self.variants.iter().find(|x| x.name == name)
+  }
+
+  /// Find a group by name
+  pub fn group(&self, name: &str) -> Option<&GroupSchema> {
+    self.groups.iter().find(|x| x.name == name)
+  }
+
+  /// A stable hash of the variant names, the names and types of their data fields, and the names and kinds of the links
+  ///
+  /// Only the parts deciding the serialized form of the nodes are hashed.
+  /// The types are compared by the last segment of each path. Renaming the node types,
+  /// writing a type with another path (e.g. `std::string::String` for `String`), or changing the groups or constraints
+  /// keeps the hash.
+  /// It is stored by the serialized graph to detect old files, see [`Migrator`](crate::serialize::Migrator).
+  pub fn hash(&self) -> u64 {
+    let shape = Vec::from_iter(self.variants.iter().map(|v| {
+      (
+        &v.name,
+        Vec::from_iter(v.data.iter().map(|x| (&x.name, normalize_type(&x.type_name)))),
+        Vec::from_iter(v.links.iter().map(|x| (&x.name, x.link_type))),
+      )
+    }));
+    // FNV-1a, which does not change between runs or compiler versions
+    let text = serde_json::to_string(&shape).unwrap();
+    let mut result: u64 = 0xcbf29ce484222325;
+    for b in text.bytes() {
+      result ^= b as u64;
+      result = result.wrapping_mul(0x100000001b3);
+    }
+    result
+  }
+}
+
+/// Keep the last segment of each path in a type, e.g. `BTreeSet<String>` for `std::collections::BTreeSet<String>`
+fn normalize_type(ty: &str) -> String {
+  let mut result = String::new();
+  let mut rest = ty;
+  while let Some(c) = rest.chars().next() {
+    if let Some(after) = rest.strip_prefix("::") {
+      let segment = result.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_').len();
+      result.truncate(segment);
+      rest = after;
+    } else {
+      result.push(c);
+      rest = &rest[c.len_utf8()..];
+    }
+  }
+  result
+}
+
+/// A variant of the node enum, see [`GraphSchema`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariantSchema {
+  /// The name of the variant
+  pub name: String,
+  /// The name of the node type, as written in the node enum
+  pub type_name: String,
+  pub data: Vec<DataSchema>,
+  pub links: Vec<LinkSchema>,
+}
+
+impl VariantSchema {
+  /// Describe a variant of type `T`, intended to be used by [`node_enum!`]
+  pub fn of<T: TypedNode>(name: &str, type_name: &str) -> Self {
+    VariantSchema {
+      name: name.to_string(),
+      type_name: type_name.to_string(),
+      data: Vec::from_iter(
+        T::data_names()
+          .iter()
+          .zip(T::data_type_names())
+          .map(|(name, ty)| DataSchema { name: name.to_string(), type_name: ty.to_string() }),
+      ),
+      links: Vec::from_iter(
+        T::link_names()
+          .iter()
+          .zip(T::link_types())
+          .map(|(name, ty)| LinkSchema { name: name.to_string(), link_type: *ty }),
+      ),
+    }
+  }
+
+  /// Find a data field by name
+  pub fn data(&self, name: &str) -> Option<&DataSchema> {
+    self.data.iter().find(|x| x.name == name)
+  }
+
+  /// Find a link by name
+  pub fn link(&self, name: &str) -> Option<&LinkSchema> {
+    self.links.iter().find(|x| x.name == name)
+  }
+}
+
+/// A data field of a variant, see [`GraphSchema`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSchema {
+  pub name: String,
+  /// The type of the field, as written in the node type
+  pub type_name: String,
+}
+
+/// A link of a variant, see [`GraphSchema`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkSchema {
+  pub name: String,
+  pub link_type: LinkType,
+}
+
+/// A group declared by `group!`, see [`GraphSchema`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupSchema {
+  pub name: String,
+  pub variants: Vec<String>,
+}
+
+/// A constraint declared by `link_type!`: the `link` of `variant` can only point to the `targets` variants
+///
+/// The `link` may be a link group declared by `#[group]` in the node type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkTypeSchema {
+  pub variant: String,
+  pub link: String,
+  pub targets: Vec<String>,
+}
+
+/// A pair of links declared by `bidirectional!`, see [`GraphSchema`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BidirectionalSchema {
+  pub variant1: String,
+  pub link1: String,
+  pub variant2: String,
+  pub link2: String,
+}
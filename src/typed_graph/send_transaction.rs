@@ -0,0 +1,130 @@
+use super::*;
+
+/// Type alias to be used in [`mutate`](Transaction::mutate), the closure must be [`Send`]
+pub type SendMutFunc<'a, T> = Box<dyn FnOnce(&mut T) + Send + 'a>;
+/// Type alias to be used in [`update`](Transaction::update), the closure must be [`Send`]
+pub type SendUpdateFunc<'a, T> = Box<dyn FnOnce(T) -> T + Send + 'a>;
+
+/// The [`FuncKind`] of [`SendTransaction`], storing [`SendMutFunc`] and [`SendUpdateFunc`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SendFuncs;
+
+impl<'a, T> FuncKind<'a, T> for SendFuncs {
+  type Mut = SendMutFunc<'a, T>;
+  type Update = SendUpdateFunc<'a, T>;
+}
+
+impl<'a, T, F> IntoMutFunc<'a, T, SendFuncs> for F
+where
+  F: FnOnce(&mut T) + Send + 'a,
+{
+  fn into_func(self) -> SendMutFunc<'a, T> {
+    Box::new(self)
+  }
+}
+
+impl<'a, T, F> IntoUpdateFunc<'a, T, SendFuncs> for F
+where
+  F: FnOnce(T) -> T + Send + 'a,
+{
+  fn into_func(self) -> SendUpdateFunc<'a, T> {
+    Box::new(self)
+  }
+}
+
+/// A [`Transaction`] which can be sent between threads, so that a large transaction can be built in parallel.
+///
+/// It is a [`Transaction`] storing [`SendFuncs`], so it has the same operations,
+/// but the closures given to [`mutate`](Transaction::mutate) and [`update`](Transaction::update) must be [`Send`].
+/// Transactions built by different threads from the same [`Context`] can be combined by
+/// [`append`](Transaction::append), then converted into a [`Transaction`] to commit.
+/// Its `new` and `begin` are provided by [`SendTransactionNew`].
+///
+/// # Example
+/// ```
+/// use ttgraph::*;
+/// #[derive(TypedNode)]
+/// struct NodeA{
+///   data: usize,
+/// }
+/// node_enum!{
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let workers = Vec::from_iter((0..4).map(|i| {
+///   let ctx = ctx.clone();
+///   std::thread::spawn(move || {
+///     let mut trans = SendTransaction::<Node>::new(&ctx);
+///     for j in 0..10 {
+///       trans.insert(Node::A(NodeA{ data: i * 10 + j }));
+///     }
+///     trans
+///   })
+/// }));
+///
+/// let mut trans = SendTransaction::new(&ctx);
+/// for worker in workers {
+///   assert!(trans.append(worker.join().unwrap()).is_empty());
+/// }
+/// graph.commit(trans.into());
+/// assert_eq!(graph.len(), 40);
+/// # }
+/// ```
+pub type SendTransaction<'a, NodeT, Arena = <NodeT as NodeEnum>::GenArena> = Transaction<'a, NodeT, Arena, SendFuncs>;
+
+/// The constructors of [`SendTransaction`], same as [`Transaction::new`] and [`Transaction::begin`]
+pub trait SendTransactionNew<NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Make a empty transaction, see [`Transaction::new`]
+  fn new(context: &Context) -> Self;
+  /// Make a empty transaction which detects conflicts with other commits to the graph, see [`Transaction::begin`]
+  fn begin(context: &Context, graph: &Graph<NodeT, Arena>) -> Self;
+}
+
+impl<'a, NodeT, Arena> SendTransactionNew<NodeT, Arena> for SendTransaction<'a, NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  fn new(context: &Context) -> Self {
+    Self::empty(context, None)
+  }
+
+  fn begin(context: &Context, graph: &Graph<NodeT, Arena>) -> Self {
+    Self::empty(context, Some(graph.node_versions.lease(graph.version())))
+  }
+}
+
+impl<'a, NodeT, Arena> From<SendTransaction<'a, NodeT, Arena>> for Transaction<'a, NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  fn from(value: SendTransaction<'a, NodeT, Arena>) -> Self {
+    Transaction {
+      ctx_id: value.ctx_id,
+      trans_id: value.trans_id,
+      node_dist: value.node_dist,
+      alloc_nodes: value.alloc_nodes,
+      inc_nodes: value.inc_nodes,
+      dec_nodes: value.dec_nodes,
+      dropped_nodes: value.dropped_nodes,
+      mut_nodes: Vec::from_iter(value.mut_nodes.into_iter().map(|(x, f)| (x, f as MutFunc<'a, NodeT>))),
+      update_nodes: Vec::from_iter(value.update_nodes.into_iter().map(|(x, f)| (x, f as UpdateFunc<'a, NodeT>))),
+      redirect_all_links_vec: value.redirect_all_links_vec,
+      redirect_links_vec: value.redirect_links_vec,
+      base_version: value.base_version,
+      leases: value.leases,
+      read_nodes: value.read_nodes,
+      viewed_nodes: value.viewed_nodes,
+    }
+  }
+}
@@ -5,6 +5,7 @@
 //! + [`Context`] is not serializable or deserializable due to it contains [`Arc`] and atomic counters. [`deserialize_graph()`] constructs a new [`Context`] that is compatible instead.
 //! + If there are multiple deserialized graphs using the same context before they are serialized, use [`switch_context()`](Graph::switch_context) to merge the newly created contexts.
 //! + [`Transaction`] is also not serializable or deserializable, due to it contains closures. Also, it is not reasonable to serialize uncommitted transactions.
+//! + The serialized graph stores the [`hash`](GraphSchema::hash) of the node schema. Use a [`Migrator`] to upgrade the files written with an older schema.
 //! # Example
 //! ```rust
 //! use ttgraph::{*, serialize::*};
@@ -36,11 +37,19 @@
 //! ```
 
 use super::*;
+use crate::id_distributer::IdDistributer;
 use serde::{
   de::Deserialize,
   ser::{Serialize, SerializeSeq, SerializeStruct},
 };
 
+mod migrate;
+pub use migrate::*;
+mod validate;
+pub use validate::DeserializeError;
+use validate::validate_indexes;
+pub(crate) use validate::validate_links;
+
 /// Helper struct to serialzie and deserialzie a [`Graph`]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GraphSerializer<NodeT>
@@ -48,6 +57,12 @@ where
   NodeT: NodeEnum,
 {
   ctx_id: Uuid,
+  /// The schema hash, 0 for the files written before it is stored
+  #[serde(default)]
+  schema: u64,
+  /// The counter of the indexes of the context, `None` for the files written before it is stored
+  #[serde(default)]
+  id_count: Option<usize>,
   nodes: Vec<(NodeIndex, NodeT)>,
 }
 
@@ -59,7 +74,9 @@ where
   fn from(value: Graph<NodeT, Arena>) -> GraphSerializer<NodeT> {
     GraphSerializer {
       ctx_id: value.ctx_id,
-      nodes: Vec::from_iter(value.into_iter()),
+      schema: NodeT::schema().hash(),
+      id_count: Some(value.nodes.id_distributer().count()),
+      nodes: Vec::from_iter(value),
     }
   }
 }
@@ -95,19 +112,143 @@ where
   where
     S: serde::Serializer,
   {
-    let mut graph = serializer.serialize_struct("Graph", 2)?;
+    let mut graph = serializer.serialize_struct("Graph", 4)?;
     graph.serialize_field("ctx_id", &self.ctx_id)?;
+    graph.serialize_field("schema", &NodeT::schema().hash())?;
+    graph.serialize_field("id_count", &Some(self.nodes.id_distributer().count()))?;
     graph.serialize_field("nodes", &NodeSerialize::<NodeT, Arena>(self.iter()))?;
     graph.end()
   }
 }
 
 /// Helper function to deserialize a graph and construct a context for the graph
+///
+/// The schema hash is not checked, the nodes are already deserialized. Use [`Migrator::deserialize_graph`] to upgrade old files.
+///
+/// The missing sides of the bidirectional links are added, as the links are changed in a commit.
+///
+/// # Panic
+/// Panic if the nodes are invalid, see [`try_deserialize_graph`].
 pub fn deserialize_graph<'de, NodeT: NodeEnum + Deserialize<'de>>(
   input: GraphSerializer<NodeT>,
 ) -> (Context, Graph<NodeT>) {
-  let cnt = input.nodes.iter().map(|(idx, _)| idx.0).max().unwrap_or_else(|| 0);
-  let ctx = Context::from_id(input.ctx_id, cnt);
-  let graph = Graph::do_deserialize(&ctx, input.nodes);
-  (ctx, graph)
+  try_deserialize_graph(input, false, false).unwrap_or_else(|e| panic!("Invalid serialized graph! {}", e))
+}
+
+/// Check the nodes, then deserialize a graph and construct a context for the graph
+///
+/// The checks are:
+/// + No node uses the empty index, and no index is used by multiple nodes.
+/// + No link points to a missing node. If `repair_dangling` is true, such targets are removed from the links instead.
+/// + The link targets satisfy `link_type!`.
+/// + If `check_bidirectional` is true, the targets of the bidirectional links link back.
+///   Otherwise, the missing sides are added like [`deserialize_graph`].
+///
+/// The context continues from the stored counter of the indexes, or from the max index for the files written before
+/// it is stored.
+/// # Example
+/// ```
+/// use ttgraph::{*, serialize::*};
+/// use serde::{Serialize, Deserialize};
+/// #[derive(TypedNode, Serialize, Deserialize)]
+/// struct NodeA{
+///   next: NodeIndex,
+/// }
+/// node_enum!{
+///   #[derive(Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let text = r#"{"ctx_id": "5e3a4ba3-6c4b-4b3c-9f7e-0d2a1b3c4d5e", "nodes": [[1, {"A": {"next": 2}}]]}"#;
+/// let result = try_deserialize_graph::<Node>(serde_json::from_str(text).unwrap(), false, true);
+/// assert!(matches!(result, Err(DeserializeError::Dangling { .. })));
+///
+/// let (ctx, graph) = try_deserialize_graph::<Node>(serde_json::from_str(text).unwrap(), true, true).unwrap();
+/// assert!(get_node!(graph, Node::A, NodeIndex(1)).unwrap().next.is_empty());
+/// # }
+/// ```
+pub fn try_deserialize_graph<'de, NodeT: NodeEnum + Deserialize<'de>>(
+  input: GraphSerializer<NodeT>, repair_dangling: bool, check_bidirectional: bool,
+) -> Result<(Context, Graph<NodeT>), DeserializeError> {
+  validate_indexes(&input.nodes)?;
+  let ctx = Context::from_id(input.ctx_id, id_count(input.id_count, &input.nodes));
+  let mut arena = NodeT::GenArena::new_from_iter(ctx.node_dist.clone(), input.nodes);
+  validate_links(&mut arena, repair_dangling, check_bidirectional)?;
+  let graph = Graph::from_arena(&ctx, arena);
+  Ok((ctx, graph))
+}
+
+/// Deserialize the nodes into an existing graph, giving them new indexes of its context
+///
+/// The links between the loaded nodes are rewritten to the new indexes. The loaded nodes are checked like
+/// [`try_deserialize_graph`] first, so the graph is unchanged if there is an error.
+/// Returns the map from the indexes in `input` to the new indexes.
+///
+/// # Panic
+/// Panic if the graph does not belong to the context.
+///
+/// # Example
+/// ```
+/// use ttgraph::{*, serialize::*};
+/// use serde::{Serialize, Deserialize};
+/// #[derive(TypedNode, Serialize, Deserialize)]
+/// struct NodeA{
+///   next: NodeIndex,
+///   data: usize,
+/// }
+/// node_enum!{
+///   #[derive(Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let a = trans.insert(Node::A(NodeA{ next: NodeIndex::empty(), data: 1 }));
+/// let b = trans.insert(Node::A(NodeA{ next: a, data: 2 }));
+/// graph.commit(trans);
+/// let text = serde_json::to_string(&graph).unwrap();
+///
+/// // Load the nodes again into the same graph
+/// let id_map = deserialize_into(&ctx, &mut graph, serde_json::from_str(&text).unwrap()).unwrap();
+/// assert_eq!(graph.len(), 4);
+/// assert_eq!(get_node!(graph, Node::A, id_map[&b]).unwrap().next, id_map[&a]);
+/// # }
+/// ```
+pub fn deserialize_into<'de, NodeT, Arena>(
+  ctx: &Context, graph: &mut Graph<NodeT, Arena>, input: GraphSerializer<NodeT>,
+) -> Result<IdMap, DeserializeError>
+where
+  NodeT: NodeEnum + Deserialize<'de>,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  assert!(graph.ctx_id == ctx.id, "The graph does not belong to the context!");
+  validate_indexes(&input.nodes)?;
+  // The indexes are only used for the checks, so a separated distributer is enough
+  let mut arena = Arena::new_from_iter(IdDistributer::default(), input.nodes);
+  validate_links(&mut arena, false, true)?;
+
+  let mut trans = Transaction::<NodeT, Arena>::new(ctx);
+  let mut id_map = IdMap::new();
+  for (idx, node) in arena.iter() {
+    id_map.insert(idx, trans.alloc(node.discriminant()));
+  }
+  for (idx, mut node) in arena.into_iter() {
+    remap_links(&mut node, |y| Some(id_map.get(&y).copied().unwrap_or(y)));
+    trans.fill_back(id_map[&idx], node);
+  }
+  graph.commit(trans);
+  Ok(id_map)
+}
+
+/// The counter of the indexes for the new context, the max index if it is not stored
+fn id_count<T>(stored: Option<usize>, nodes: &[(NodeIndex, T)]) -> usize {
+  let max = nodes.iter().map(|(idx, _)| idx.0).max().unwrap_or(0);
+  stored.map_or(max, |cnt| cnt.max(max))
 }
@@ -0,0 +1,212 @@
+use std::{collections::BTreeMap, error::Error, marker::PhantomData};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use super::*;
+
+/// A serialized graph with untyped nodes, to be upgraded by a [`Migrator`]
+///
+/// It reads the same form as [`GraphSerializer`], from a self-describing format like JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DynGraph {
+  pub ctx_id: Uuid,
+  /// The schema hash, 0 for the files written before it is stored
+  #[serde(default)]
+  pub schema: u64,
+  /// The counter of the indexes of the context, `None` for the files written before it is stored
+  #[serde(default)]
+  pub id_count: Option<usize>,
+  pub nodes: Vec<(NodeIndex, Value)>,
+}
+
+/// A node of a [`DynGraph`], split into its variant name and fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynNode {
+  pub variant: String,
+  /// The data fields and links, by their names
+  pub fields: Map<String, Value>,
+}
+
+impl DynNode {
+  fn from_value(idx: NodeIndex, value: Value) -> Result<DynNode, MigrateError> {
+    match value {
+      Value::Object(obj) if obj.len() == 1 => {
+        let (variant, fields) = obj.into_iter().next().unwrap();
+        match fields {
+          Value::Object(fields) => Ok(DynNode { variant, fields }),
+          _ => Err(MigrateError::Format(idx)),
+        }
+      },
+      _ => Err(MigrateError::Format(idx)),
+    }
+  }
+
+  fn into_value(self) -> Value {
+    let mut result = Map::new();
+    result.insert(self.variant, Value::Object(self.fields));
+    Value::Object(result)
+  }
+}
+
+/// The error of [`Migrator::deserialize_graph`]
+#[derive(Debug)]
+pub enum MigrateError {
+  /// There is no migration from this schema hash to the current one
+  UnknownSchema(u64),
+  /// The node is not serialized as an externally tagged enum of structs
+  Format(NodeIndex),
+  /// The upgraded node can not be deserialized
+  Node(NodeIndex, serde_json::Error),
+  /// The upgraded nodes do not form a valid graph, see [`try_deserialize_graph`](super::try_deserialize_graph)
+  Invalid(DeserializeError),
+}
+
+impl Display for MigrateError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      MigrateError::UnknownSchema(hash) => write!(f, "No migration from schema {:#018x}", hash),
+      MigrateError::Format(idx) => write!(f, "Node {} is not an externally tagged struct variant", idx),
+      MigrateError::Node(idx, e) => write!(f, "Failed to deserialize node {}: {}", idx, e),
+      MigrateError::Invalid(e) => write!(f, "Invalid graph: {}", e),
+    }
+  }
+}
+
+impl Error for MigrateError {}
+
+/// The closure of a migration step
+pub type MigrateFunc = Box<dyn Fn(&mut DynNode) + Send + Sync>;
+
+/// Upgrade the graphs serialized with older schemas
+///
+/// Each step is registered by [`step`](Migrator::step), from a schema hash to a newer one, with a closure applied to every node.
+/// [`deserialize_graph`](Migrator::deserialize_graph) follows the steps from the hash stored in the file until it reaches the current [`hash`](GraphSchema::hash) of `NodeT`.
+/// Files written before the hash is stored have a hash of 0.
+///
+/// Save the hash of each released schema, e.g. as a constant, to register the steps from it later.
+///
+/// # Example
+/// ```
+/// use ttgraph::{*, serialize::*};
+/// use serde::{Serialize, Deserialize};
+///
+/// mod old {
+///   use ttgraph::*;
+///   use serde::{Serialize, Deserialize};
+///   #[derive(TypedNode, Serialize, Deserialize)]
+///   pub struct NodeA{
+///     pub next: NodeIndex,
+///     pub data: usize,
+///   }
+///   node_enum!{
+///     #[derive(Serialize, Deserialize)]
+///     pub enum Node{
+///       A(NodeA)
+///     }
+///   }
+/// }
+///
+/// #[derive(TypedNode, Serialize, Deserialize)]
+/// struct NodeA{
+///   next: NodeIndex,
+///   value: usize,
+///   note: String,
+/// }
+/// node_enum!{
+///   #[derive(Serialize, Deserialize)]
+///   enum Node{
+///     A(NodeA)
+///   }
+/// }
+///
+/// # fn main() {
+/// // Written by the old version
+/// let ctx = Context::new();
+/// let mut graph = Graph::<old::Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let a = trans.insert(old::Node::A(old::NodeA{ next: NodeIndex::empty(), data: 1 }));
+/// graph.commit(trans);
+/// let text = serde_json::to_string(&graph).unwrap();
+///
+/// // Read by the new version
+/// let migrator = Migrator::<Node>::new().step(old::Node::schema().hash(), Node::schema().hash(), |node| {
+///   let data = node.fields.remove("data").unwrap();
+///   node.fields.insert("value".to_string(), data);
+///   node.fields.insert("note".to_string(), serde_json::json!("migrated"));
+/// });
+/// let (ctx2, graph2) = migrator.deserialize_graph(serde_json::from_str(&text).unwrap()).unwrap();
+/// assert_eq!(get_node!(graph2, Node::A, a).unwrap().value, 1);
+/// # }
+/// ```
+pub struct Migrator<NodeT: NodeEnum> {
+  steps: BTreeMap<u64, (u64, MigrateFunc)>,
+  _marker: PhantomData<NodeT>,
+}
+
+impl<NodeT: NodeEnum> Default for Migrator<NodeT> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<NodeT: NodeEnum> Migrator<NodeT> {
+  /// Create a migrator without steps, which only reads the files of the current schema
+  pub fn new() -> Self {
+    Migrator { steps: BTreeMap::new(), _marker: PhantomData }
+  }
+
+  /// Register a step upgrading the nodes of schema `from` to schema `to`
+  ///
+  /// # Panic
+  /// Panic if there is already a step from `from`, or `from` is the same as `to`.
+  pub fn step(mut self, from: u64, to: u64, func: impl Fn(&mut DynNode) + Send + Sync + 'static) -> Self {
+    assert!(from != to, "The migration step should change the schema");
+    let old = self.steps.insert(from, (to, Box::new(func)));
+    assert!(old.is_none(), "Duplicated migration step from schema {:#018x}", from);
+    self
+  }
+
+  /// Upgrade the nodes to the current schema, then deserialize the graph and construct a context for it, like [`deserialize_graph`](super::deserialize_graph)
+  ///
+  /// The upgraded nodes are checked like [`try_deserialize_graph`](super::try_deserialize_graph), except for the bidirectional links.
+  /// The steps only need to upgrade one side of a new bidirectional link, the other side is added automatically.
+  pub fn deserialize_graph(&self, input: DynGraph) -> Result<(Context, Graph<NodeT>), MigrateError>
+  where
+    NodeT: DeserializeOwned,
+  {
+    let current = NodeT::schema().hash();
+    let mut chain = Vec::new();
+    let mut schema = input.schema;
+    while schema != current {
+      // A step can not be used twice, otherwise the steps form a loop
+      match self.steps.get(&schema) {
+        Some((to, func)) if chain.len() < self.steps.len() => {
+          chain.push(func);
+          schema = *to;
+        },
+        _ => return Err(MigrateError::UnknownSchema(input.schema)),
+      }
+    }
+
+    let mut nodes = Vec::new();
+    for (idx, value) in input.nodes {
+      let value = if chain.is_empty() {
+        value
+      } else {
+        let mut node = DynNode::from_value(idx, value)?;
+        for func in &chain {
+          func(&mut node);
+        }
+        node.into_value()
+      };
+      nodes.push((idx, serde_json::from_value(value).map_err(|e| MigrateError::Node(idx, e))?));
+    }
+    validate_indexes(&nodes).map_err(MigrateError::Invalid)?;
+    let ctx = Context::from_id(input.ctx_id, id_count(input.id_count, &nodes));
+    let mut arena = NodeT::GenArena::new_from_iter(ctx.node_dist.clone(), nodes);
+    validate_links(&mut arena, false, false).map_err(MigrateError::Invalid)?;
+    let graph = Graph::from_arena(&ctx, arena);
+    Ok((ctx, graph))
+  }
+}
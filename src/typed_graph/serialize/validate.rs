@@ -0,0 +1,102 @@
+use std::{collections::BTreeSet, error::Error};
+
+use super::*;
+
+/// The error found by [`try_deserialize_graph`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeserializeError {
+  /// A node uses the empty index 0
+  EmptyIndex,
+  /// Multiple nodes use the same index
+  DuplicateIndex(NodeIndex),
+  /// The link points to an index without node
+  Dangling { source: NodeIndex, link: &'static str, target: NodeIndex },
+  /// The link points to a node of a variant not allowed by `link_type!`
+  LinkType { source: NodeIndex, link: &'static str, target: NodeIndex, found: String },
+  /// The link is bidirectional, but the target does not link back
+  Bidirectional { source: NodeIndex, link: &'static str, target: NodeIndex },
+}
+
+impl Display for DeserializeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DeserializeError::EmptyIndex => write!(f, "A node uses the empty index"),
+      DeserializeError::DuplicateIndex(idx) => write!(f, "Duplicated node {}", idx),
+      DeserializeError::Dangling { source, link, target } => {
+        write!(f, "Link {}.{} points to a missing node {}", source, link, target)
+      },
+      DeserializeError::LinkType { source, link, target, found } => {
+        write!(f, "Link {}.{} points to node {} of an unexpected type {}", source, link, target, found)
+      },
+      DeserializeError::Bidirectional { source, link, target } => {
+        write!(f, "Bidirectional link {}.{} -> {} has no link back", source, link, target)
+      },
+    }
+  }
+}
+
+impl Error for DeserializeError {}
+
+/// Check that the indexes are not empty nor duplicated, before making an arena
+pub(crate) fn validate_indexes<NodeT>(nodes: &[(NodeIndex, NodeT)]) -> Result<(), DeserializeError> {
+  let mut seen = BTreeSet::new();
+  for (idx, _) in nodes {
+    if idx.is_empty() {
+      return Err(DeserializeError::EmptyIndex);
+    }
+    if !seen.insert(*idx) {
+      return Err(DeserializeError::DuplicateIndex(*idx));
+    }
+  }
+  Ok(())
+}
+
+/// Check the links of the nodes before making a graph, see [`try_deserialize_graph`]
+///
+/// Without `check_bidirectional`, the missing back links are left to be added when making the graph.
+pub(crate) fn validate_links<NodeT, Arena>(
+  arena: &mut Arena, repair_dangling: bool, check_bidirectional: bool,
+) -> Result<(), DeserializeError>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  let mut repairs = Vec::new();
+  for (x, node) in arena.iter() {
+    for name in NodeT::link_names_of(node.discriminant()) {
+      let targets = Vec::from_iter(node.get_links_by_name(name));
+      if let Some(y) = targets.iter().find(|y| !y.is_empty() && !arena.contains(**y)) {
+        if !repair_dangling {
+          return Err(DeserializeError::Dangling { source: x, link: name, target: *y });
+        }
+        let targets = Vec::from_iter(targets.into_iter().filter(|y| y.is_empty() || arena.contains(*y)));
+        repairs.push((x, *name, targets));
+      }
+    }
+  }
+  for (x, name, targets) in repairs {
+    arena.get_mut(x).unwrap().set_links_by_name(name, targets);
+  }
+
+  for (x, node) in arena.iter() {
+    for (y, s) in node.iter_sources() {
+      if y.is_empty() {
+        continue;
+      }
+      let link = NodeT::to_link_mirror_enum(s);
+      let target = arena.get(y).unwrap();
+      if NodeT::check_link_type(target.discriminant(), link).is_err() {
+        let found = format!("{:?}", target.discriminant());
+        return Err(DeserializeError::LinkType { source: x, link: NodeT::link_name(link), target: y, found });
+      }
+      if !check_bidirectional {
+        continue;
+      }
+      let bds = target.match_bd_link_group(node.get_bidiretional_link_mirrors_of(link));
+      if !bds.is_empty() && !bds.iter().any(|l| target.contains_link(*l, x)) {
+        return Err(DeserializeError::Bidirectional { source: x, link: NodeT::link_name(link), target: y });
+      }
+    }
+  }
+  Ok(())
+}
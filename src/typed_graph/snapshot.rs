@@ -0,0 +1,105 @@
+//! Immutable snapshots of a graph, see [`Graph::snapshot`]
+
+use std::ops::Deref;
+
+use super::*;
+
+/// An immutable version of a [`Graph`], made by [`snapshot`](Graph::snapshot).
+///
+/// The snapshot is not affected by later commits to the graph, and it can be sent to and shared between threads (if the nodes can).
+/// All the read methods of [`Graph`] are available through [`Deref`].
+pub struct GraphSnapshot<NodeT, Arena = <NodeT as NodeEnum>::GenArena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  graph: Graph<NodeT, Arena>,
+}
+
+impl<NodeT, Arena> Graph<NodeT, Arena>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Take an immutable snapshot of the current version of the graph.
+  ///
+  /// It is as cheap as [`fork`](Graph::fork), and the nodes are shared until they are changed by a commit.
+  ///
+  /// While the snapshot is alive, a commit to the graph copies the O(log N) trie nodes on the path to each
+  /// changed entry, as described in [`fork`](Graph::fork). So a snapshot can be taken after every commit to keep
+  /// the history, and the snapshots only hold the nodes that differ between them.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// use std::sync::Arc;
+  /// #[derive(TypedNode, Clone)]
+  /// struct NodeA{
+  ///   data: usize,
+  /// }
+  /// node_enum!{
+  ///   #[derive(Clone)]
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let idx = trans.insert(Node::A(NodeA{ data: 1 }));
+  /// graph.commit(trans);
+  ///
+  /// let snapshot = Arc::new(graph.snapshot());
+  /// let reader = {
+  ///   let snapshot = snapshot.clone();
+  ///   std::thread::spawn(move || get_node!(snapshot, Node::A, idx).unwrap().data)
+  /// };
+  ///
+  /// let mut trans = Transaction::new(&ctx);
+  /// mut_node!(trans, Node::A, idx, |x| { x.data = 2 });
+  /// graph.commit(trans);
+  ///
+  /// assert_eq!(reader.join().unwrap(), 1);
+  /// assert_eq!(get_node!(snapshot, Node::A, idx).unwrap().data, 1);
+  /// assert_eq!(get_node!(graph, Node::A, idx).unwrap().data, 2);
+  /// # }
+  /// ```
+  pub fn snapshot(&self) -> GraphSnapshot<NodeT, Arena> {
+    GraphSnapshot { graph: self.fork() }
+  }
+}
+
+impl<NodeT, Arena> Deref for GraphSnapshot<NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  type Target = Graph<NodeT, Arena>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.graph
+  }
+}
+
+impl<NodeT, Arena> Clone for GraphSnapshot<NodeT, Arena>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  fn clone(&self) -> Self {
+    GraphSnapshot { graph: self.graph.fork() }
+  }
+}
+
+impl<NodeT, Arena> GraphSnapshot<NodeT, Arena>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Make a mutable graph from the snapshot, which shares the nodes with the snapshot
+  pub fn to_graph(&self) -> Graph<NodeT, Arena> {
+    self.graph.fork()
+  }
+}
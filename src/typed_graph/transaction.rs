@@ -3,25 +3,55 @@ use ordermap::OrderSet;
 use visible::StructFields;
 
 use super::*;
+
+/// A contradictory operation found by [`append`](Transaction::append), where `other` is the appended transaction
+///
+/// The nodes removed by this transaction include the nodes it inserted and then removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppendConflict {
+  /// `other` mutates or updates a node removed by this transaction
+  ModifyRemoved(NodeIndex),
+  /// Both transactions remove the node
+  RemoveTwice(NodeIndex),
+  /// `other` redirects links from or to a node removed by this transaction
+  RedirectToRemoved(NodeIndex),
+  /// A node inserted by `other` links to a node removed by this transaction, as `(new node, removed node)`
+  LinkToRemoved(NodeIndex, NodeIndex),
+}
+
 /// The transaction to modify a [`Graph`].
 ///
 /// It is a operation recorder which have independent lifetime than the graph and does not hold reference to the graph.
 ///
 /// The transaction and the graph should have been created from the same [`Context`] to ensure correctness.
+///
+/// The closures of [`mutate`](Transaction::mutate) and [`update`](Transaction::update) are stored as given by `Funcs`,
+/// see [`FuncKind`] and [`SendTransaction`].
 #[StructFields(pub(crate))]
-pub struct Transaction<'a, NodeT, Arena = <NodeT as NodeEnum>::GenArena>
+pub struct Transaction<'a, NodeT, Arena = <NodeT as NodeEnum>::GenArena, Funcs = LocalFuncs>
 where
   NodeT: NodeEnum,
   Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  Funcs: FuncKind<'a, NodeT>,
 {
   ctx_id: Uuid,
+  /// Unique to each transaction, so a [`Savepoint`] can only be rolled back to by the transaction made it
+  trans_id: Uuid,
+  node_dist: IdDistributer,
   alloc_nodes: OrderSet<NodeIndex>,
   inc_nodes: Arena,
   dec_nodes: OrderSet<NodeIndex>,
-  mut_nodes: Vec<(NodeIndex, MutFunc<'a, NodeT>)>,
-  update_nodes: Vec<(NodeIndex, UpdateFunc<'a, NodeT>)>,
+  dropped_nodes: OrderSet<NodeIndex>,
+  mut_nodes: Vec<(NodeIndex, Funcs::Mut)>,
+  update_nodes: Vec<(NodeIndex, Funcs::Update)>,
   redirect_all_links_vec: Vec<(NodeIndex, NodeIndex)>,
   redirect_links_vec: Vec<(NodeIndex, NodeIndex)>,
+  base_version: Option<u64>,
+  /// Keep the versions of the removed nodes newer than the base versions, including the ones of absorbed transactions
+  leases: Vec<VersionLease>,
+  read_nodes: OrderSet<NodeIndex>,
+  /// Nodes whose closures are replaced by a [`TransactionView`], with the graph version the view read them at
+  viewed_nodes: OrderMap<NodeIndex, u64>,
 }
 
 impl<'a, NodeT, Arena> Transaction<'a, NodeT, Arena>
@@ -56,16 +86,35 @@ where
   /// # }
   /// ```
   pub fn new(context: &Context) -> Self {
+    Self::empty(context, None)
+  }
+}
+
+impl<'a, NodeT, Arena, Funcs> Transaction<'a, NodeT, Arena, Funcs>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  Funcs: FuncKind<'a, NodeT>,
+{
+  /// Make a empty transaction storing `Funcs`, began at the version of the lease if it is given
+  pub(crate) fn empty(context: &Context, lease: Option<VersionLease>) -> Self {
     let node_dist = context.node_dist.clone();
     Transaction {
       ctx_id: context.id,
+      trans_id: Uuid::new_v4(),
+      node_dist: node_dist.clone(),
       alloc_nodes: OrderSet::new(),
       inc_nodes: Arena::new(node_dist),
       dec_nodes: OrderSet::new(),
+      dropped_nodes: OrderSet::new(),
       mut_nodes: Vec::new(),
       update_nodes: Vec::new(),
       redirect_all_links_vec: Vec::new(),
       redirect_links_vec: Vec::new(),
+      base_version: lease.as_ref().map(VersionLease::version),
+      leases: Vec::from_iter(lease),
+      read_nodes: OrderSet::new(),
+      viewed_nodes: OrderMap::new(),
     }
   }
 
@@ -194,7 +243,7 @@ where
   ///
   /// Note: nodes created by [`insert`](Transaction::insert) and [`alloc`](Transaction::alloc) in this uncommitted transaction can also be removed.
   ///
-  /// Currently backed by [OrderMap::swap_remove] for performance, the order is changed after remove.
+  /// The nodes are kept in the order of their indexes, so removing a node does not change the order of the others.
   ///
   /// Example:
   /// ```
@@ -225,7 +274,9 @@ where
   /// # }
   /// ```
   pub fn remove(&mut self, node: NodeIndex) {
-    if self.inc_nodes.remove(node).is_none() && !self.alloc_nodes.swap_remove(&node) {
+    if self.inc_nodes.remove(node).is_some() || self.alloc_nodes.swap_remove(&node) {
+      self.dropped_nodes.insert(node);
+    } else {
       self.dec_nodes.insert(node);
     }
   }
@@ -273,19 +324,20 @@ where
   /// Try not to create a node with a very large connectivity. Merge multiple operations into one.
   pub fn mutate<F>(&mut self, node: NodeIndex, func: F)
   where
-    F: FnOnce(&mut NodeT) + 'a,
+    F: FnOnce(&mut NodeT) + IntoMutFunc<'a, NodeT, Funcs>,
   {
     if self.inc_nodes.contains(node) {
       func(self.inc_nodes.get_mut(node).unwrap());
     } else {
-      self.mut_nodes.push((node, Box::new(func)));
-    }  }
+      self.mut_nodes.push((node, func.into_func()));
+    }
+  }
 
   /// Update a node with a closure `FnOnce(NodeT) -> NodeT`.
   ///
   /// If the type of the node is previously known, use [`update_node!`](crate::update_node!) instead.
   ///
-  /// The node is taken out of the container and will be put back after updating, so the order is changed.
+  /// The node is taken out of the container and put back after updating, at the same place in the order of indexes.
   ///
   /// # Example
   /// ```
@@ -327,12 +379,12 @@ where
   /// Try not to create a node with a very large connectivity. Merge multiple operations into one.
   pub fn update<F>(&mut self, node: NodeIndex, func: F)
   where
-    F: FnOnce(NodeT) -> NodeT + 'a,
+    F: FnOnce(NodeT) -> NodeT + IntoUpdateFunc<'a, NodeT, Funcs>,
   {
     if self.inc_nodes.contains(node) {
       self.inc_nodes.update_with(node, func);
     } else {
-      self.update_nodes.push((node, Box::new(func)));
+      self.update_nodes.push((node, func.into_func()));
     }
   }
 
@@ -498,6 +550,113 @@ where
     self.inc_nodes.merge(graph.nodes);
   }
 
+  /// Append all operations of another transaction after the operations of this transaction, and report the contradictory operations.
+  ///
+  /// The operations are committed in the same order as if they were done on this transaction:
+  /// inserts, mutations, updates and redirects of `other` go after the ones of this transaction, and `other` can remove, mutate or update the nodes inserted by this transaction.
+  /// The contradictory operations are still appended, see [`AppendConflict`] for the detected cases.
+  ///
+  /// # Panic
+  /// Panic if the transactions have different contexts.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode)]
+  /// struct NodeA{
+  ///   data: usize,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let a = trans.insert(Node::A(NodeA{ data: 1 }));
+  /// let b = trans.insert(Node::A(NodeA{ data: 2 }));
+  /// graph.commit(trans);
+  ///
+  /// let mut t1 = Transaction::new(&ctx);
+  /// t1.remove(a);
+  /// let mut t2 = Transaction::new(&ctx);
+  /// mut_node!(t2, Node::A, a, |x| { x.data = 10 });
+  /// mut_node!(t2, Node::A, b, |x| { x.data = 20 });
+  ///
+  /// let conflicts = t1.append(t2);
+  /// assert_eq!(conflicts, vec![AppendConflict::ModifyRemoved(a)]);
+  /// graph.commit(t1);
+  /// assert!(graph.get(a).is_none());
+  /// assert_eq!(get_node!(graph, Node::A, b).unwrap().data, 20);
+  /// # }
+  /// ```
+  pub fn append(&mut self, other: Transaction<'a, NodeT, Arena, Funcs>) -> Vec<AppendConflict> {
+    assert!(self.ctx_id == other.ctx_id, "The transactions are from different context!");
+    // Nodes of the graph removed by self, or nodes inserted and then removed by self
+    let removed = |x: &NodeIndex| self.dec_nodes.contains(x) || self.dropped_nodes.contains(x);
+    let mut conflicts = Vec::new();
+    for x in &other.dec_nodes {
+      if removed(x) {
+        conflicts.push(AppendConflict::RemoveTwice(*x));
+      }
+    }
+    for x in other.mut_nodes.iter().map(|(x, _)| x).chain(other.update_nodes.iter().map(|(x, _)| x)) {
+      if removed(x) {
+        conflicts.push(AppendConflict::ModifyRemoved(*x));
+      }
+    }
+    for (x, y) in other.redirect_links_vec.iter().chain(other.redirect_all_links_vec.iter()) {
+      for z in [x, y] {
+        if removed(z) {
+          conflicts.push(AppendConflict::RedirectToRemoved(*z));
+        }
+      }
+    }
+    for (x, n) in other.inc_nodes.iter() {
+      for (y, _) in n.iter_sources() {
+        if removed(&y) {
+          conflicts.push(AppendConflict::LinkToRemoved(x, y));
+        }
+      }
+    }
+
+    self.inc_nodes.merge(other.inc_nodes);
+    self.alloc_nodes.extend(other.alloc_nodes);
+    self.dropped_nodes.extend(other.dropped_nodes);
+    for x in other.dec_nodes {
+      self.remove(x);
+    }
+    for (x, f) in other.mut_nodes {
+      if let Some(node) = self.inc_nodes.get_mut(x) {
+        f(node);
+      } else {
+        self.mut_nodes.push((x, f));
+      }
+    }
+    for (x, f) in other.update_nodes {
+      if self.inc_nodes.contains(x) {
+        self.inc_nodes.update_with(x, f);
+      } else {
+        self.update_nodes.push((x, f));
+      }
+    }
+    self.redirect_all_links_vec.extend(other.redirect_all_links_vec);
+    self.redirect_links_vec.extend(other.redirect_links_vec);
+    self.read_nodes.extend(other.read_nodes);
+    self.leases.extend(other.leases);
+    for (x, v) in other.viewed_nodes {
+      self.viewed_nodes.entry(x).and_modify(|u| *u = (*u).min(v)).or_insert(v);
+    }
+    self.base_version = match (self.base_version, other.base_version) {
+      (Some(x), Some(y)) => Some(x.min(y)),
+      (x, y) => x.or(y),
+    };
+    conflicts
+  }
+
   /// Give up the transaction. Currently if a transaction is dropped without commit, it does not give a warning or panic. This issue may be fixed in the future.
   ///
   /// Currently this method does nothing.
@@ -557,26 +716,140 @@ where
   }
 }
 
-impl<'a, NodeT: NodeEnum, Arena> Debug for Transaction<'a, NodeT, Arena>
+impl<'a, NodeT, Arena, Funcs> Transaction<'a, NodeT, Arena, Funcs>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  Funcs: FuncKind<'a, NodeT>,
+{
+  /// Insert copies of the given nodes of the graph, e.g. to inline the body of a function.
+  ///
+  /// The links between the given nodes point to the copies, and the links to other nodes are kept.
+  /// Bidirectional links to other nodes are completed at commit as usual, e.g. the copy of a child is added to the children of the father.
+  /// A link to another node is cut if the link back is a single [`NodeIndex`],
+  /// which cannot point to both the original and the copy, e.g. the copy of a factory does not keep the workers not copied.
+  /// Returns the map from the given nodes to their copies.
+  ///
+  /// # Panic
+  /// Panic if the graph and the transaction have different contexts, or a node is not in the graph.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode, Clone)]
+  /// struct InstNode{
+  ///   next: NodeIndex,
+  ///   operand: NodeIndex,
+  /// }
+  /// node_enum!{
+  ///   #[derive(Clone)]
+  ///   enum Node{
+  ///     Inst(InstNode)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let arg = trans.insert(Node::Inst(InstNode{ next: NodeIndex::empty(), operand: NodeIndex::empty() }));
+  /// let ret = trans.insert(Node::Inst(InstNode{ next: NodeIndex::empty(), operand: arg }));
+  /// let entry = trans.insert(Node::Inst(InstNode{ next: ret, operand: arg }));
+  /// let call = trans.insert(Node::Inst(InstNode{ next: NodeIndex::empty(), operand: NodeIndex::empty() }));
+  /// let user = trans.insert(Node::Inst(InstNode{ next: NodeIndex::empty(), operand: call }));
+  /// graph.commit(trans);
+  ///
+  /// // Inline the body, then replace the call by the copy of the entry
+  /// let mut trans = Transaction::new(&ctx);
+  /// let id_map = trans.clone_subgraph(&graph, [entry, ret]);
+  /// trans.redirect_links(call, id_map[&entry]);
+  /// trans.remove(call);
+  /// graph.commit(trans);
+  ///
+  /// let new_entry = get_node!(graph, Node::Inst, id_map[&entry]).unwrap();
+  /// assert_eq!(new_entry.next, id_map[&ret]);
+  /// assert_eq!(new_entry.operand, arg);
+  /// assert_eq!(get_node!(graph, Node::Inst, user).unwrap().operand, id_map[&entry]);
+  /// # }
+  /// ```
+  pub fn clone_subgraph(
+    &mut self, graph: &Graph<NodeT, Arena>, nodes: impl IntoIterator<Item = NodeIndex>,
+  ) -> IdMap {
+    assert!(self.ctx_id == graph.ctx_id, "The graph and the transaction are from different context!");
+    let mut id_map = IdMap::new();
+    for x in nodes {
+      let node = graph.get(x).unwrap_or_else(|| panic!("Clone a non-existing node {}!", x));
+      if !id_map.contains_key(&x) {
+        id_map.insert(x, self.alloc(node.discriminant()));
+      }
+    }
+    for (x, new_x) in &id_map {
+      let mut node = graph.get(*x).unwrap().clone();
+      // The single link back of these nodes already points to the original
+      let cut = Vec::from_iter(
+        node
+          .iter_sources()
+          .map(|(y, s)| (y, NodeT::to_link_mirror_enum(s)))
+          .filter(|(y, l)| !id_map.contains_key(y) && has_point_back_link(graph, &node, *y, *l))
+          .map(|(y, l)| (y, NodeT::link_name(l))),
+      );
+      for name in NodeT::link_names_of(node.discriminant()) {
+        let targets = Vec::from_iter(node.get_links_by_name(name).filter_map(|y| match id_map.get(&y) {
+          Some(new_y) => Some(*new_y),
+          None if cut.contains(&(y, *name)) => None,
+          None => Some(y),
+        }));
+        node.set_links_by_name(name, targets);
+      }
+      self.fill_back(*new_x, node);
+    }
+    id_map
+  }
+}
+
+/// Check if the bidirectional link of `y` back to `node` through `link` is a single [`NodeIndex`]
+fn has_point_back_link<NodeT, Arena>(
+  graph: &Graph<NodeT, Arena>, node: &NodeT, y: NodeIndex, link: NodeT::LinkMirrorEnum,
+) -> bool
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  let Some(target) = graph.get(y) else { return false };
+  let d = target.discriminant();
+  let back = target.match_bd_link_group(node.get_bidiretional_link_mirrors_of(link));
+  back.iter().any(|b| {
+    let name = NodeT::link_name(*b);
+    NodeT::link_names_of(d).iter().zip(NodeT::link_types_of(d)).any(|(n, t)| *n == name && *t == LinkType::Point)
+  })
+}
+
+impl<'a, NodeT: NodeEnum, Arena, Funcs> Debug for Transaction<'a, NodeT, Arena, Funcs>
 where
   NodeT: NodeEnum + Debug,
   Arena: CateArena<V = NodeT, D = NodeT::Discriminant> + Debug,
+  Funcs: FuncKind<'a, NodeT>,
 {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     f.debug_struct(&format!("Transaction<{}>", std::any::type_name::<NodeT>()))
       .field("ctx_id", &self.ctx_id)
+      .field("trans_id", &self.trans_id)
       .field("alloc_nodes", &self.alloc_nodes)
       .field("inc_nodes", &self.inc_nodes)
       .field("dec_nodes", &self.dec_nodes)
+      .field("dropped_nodes", &self.dropped_nodes)
       .field("mut_nodes", &Vec::from_iter(self.mut_nodes.iter().map(|(x, _)| *x)))
       .field("update_nodes", &Vec::from_iter(self.update_nodes.iter().map(|(x, _)| *x)))
       .field("redirect_all_links", &self.redirect_all_links_vec)
       .field("redirect_links", &self.redirect_links_vec)
+      .field("base_version", &self.base_version)
+      .field("read_nodes", &self.read_nodes)
+      .field("viewed_nodes", &self.viewed_nodes)
       .finish()
   }
 }
 
-impl<'a, NodeT: NodeEnum> Extend<NodeT> for Transaction<'a, NodeT> {
+impl<'a, NodeT: NodeEnum, Funcs: FuncKind<'a, NodeT>> Extend<NodeT> for Transaction<'a, NodeT, NodeT::GenArena, Funcs> {
   fn extend<T: IntoIterator<Item = NodeT>>(&mut self, iter: T) {
     for x in iter {
       self.insert(x);
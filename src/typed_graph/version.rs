@@ -0,0 +1,278 @@
+//! Versions of the graph and its nodes, used to detect conflicting transactions
+//!
+//! The graph version is increased by every commit. Each node remembers the version of the last commit which inserted, changed or removed it.
+//! Adding or removing a link counts as a change of both its source and its target.
+//!
+//! A transaction started by [`Transaction::begin`] remembers the graph version, and is rejected when any node it depends on has been changed since then.
+//!
+//! The versions of removed nodes are kept while a transaction which began before the removal is alive,
+//! so it still detects the conflict.
+//! They are dropped by the next commit after all such transactions are committed or dropped,
+//! so the version table only grows with the nodes in the graph.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use super::*;
+
+/// The base versions of the alive transactions made by [`begin`](Transaction::begin), with their counts
+type Leases = Arc<Mutex<BTreeMap<u64, usize>>>;
+
+/// The versions of the nodes, see the [module documentation](crate::version)
+#[derive(Clone, Default)]
+pub(crate) struct NodeVersions {
+  versions: PersistentMap<NodeIndex, u64>,
+  /// The removed nodes in the order of removal, numbered from `removed_head` to `removed_tail`
+  removed: PersistentMap<usize, NodeIndex>,
+  removed_head: usize,
+  removed_tail: usize,
+  /// Shared by the forks of the graph, as a transaction may be committed to any of them
+  leases: Leases,
+}
+
+impl NodeVersions {
+  pub(crate) fn get(&self, x: NodeIndex) -> Option<u64> {
+    self.versions.get(&x).copied()
+  }
+
+  pub(crate) fn insert(&mut self, x: NodeIndex, version: u64) {
+    self.versions.insert(x, version);
+  }
+
+  /// Remember that the node is removed, so its version can be dropped by [`prune`](NodeVersions::prune)
+  fn mark_removed(&mut self, x: NodeIndex) {
+    self.removed.insert(self.removed_tail, x);
+    self.removed_tail += 1;
+  }
+
+  /// Drop the versions of the removed nodes which are not newer than any alive transaction
+  fn prune(&mut self) {
+    let oldest = self.leases.lock().unwrap().keys().next().copied();
+    while self.removed_head < self.removed_tail {
+      let x = *self.removed.get(&self.removed_head).unwrap();
+      match self.versions.get(&x) {
+        Some(v) if oldest.is_some_and(|o| *v > o) => break,
+        _ => {
+          self.versions.remove(&x);
+        },
+      }
+      self.removed.remove(&self.removed_head);
+      self.removed_head += 1;
+    }
+  }
+
+  /// Keep the versions newer than `version` until the lease is dropped
+  pub(crate) fn lease(&self, version: u64) -> VersionLease {
+    *self.leases.lock().unwrap().entry(version).or_default() += 1;
+    VersionLease { leases: self.leases.clone(), version }
+  }
+}
+
+/// Held by a transaction made by [`begin`](Transaction::begin), see [`NodeVersions::lease`]
+pub(crate) struct VersionLease {
+  leases: Leases,
+  version: u64,
+}
+
+impl VersionLease {
+  pub(crate) fn version(&self) -> u64 {
+    self.version
+  }
+}
+
+impl Drop for VersionLease {
+  fn drop(&mut self) {
+    if let Ok(mut leases) = self.leases.lock() {
+      let count = leases.get_mut(&self.version).unwrap();
+      *count -= 1;
+      if *count == 0 {
+        leases.remove(&self.version);
+      }
+    }
+  }
+}
+
+/// The error of committing a transaction which conflicts with the commits after it began, see [`try_commit`](Graph::try_commit)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+  /// The version of the graph when the transaction began, or when a [`TransactionView`] read the changed nodes
+  pub base_version: u64,
+  /// The version of the graph when the transaction is committed
+  pub graph_version: u64,
+  /// The nodes read or written by the transaction, but changed by other commits
+  pub nodes: OrderSet<NodeIndex>,
+}
+
+impl Display for Conflict {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "Transaction conflict: began at version {}, but nodes {:?} are changed before version {}",
+      self.base_version, self.nodes, self.graph_version
+    )
+  }
+}
+
+impl Error for Conflict {}
+
+impl<'a, NodeT, Arena> Transaction<'a, NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Make a empty transaction which detects conflicts with other commits to the graph.
+  ///
+  /// The transaction remembers the current version of the graph.
+  /// When it is committed, if any node it read by [`read`](Transaction::read), removed, mutated, updated, redirected, or linked from its new nodes has been changed by another commit,
+  /// [`commit`](Graph::commit) panics and [`try_commit`](Graph::try_commit) returns a [`Conflict`].
+  ///
+  /// Links added by [`mutate`](Transaction::mutate) and [`update`](Transaction::update) closures are not known before commit, declare them with [`read`](Transaction::read) if needed.
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode)]
+  /// struct NodeA{
+  ///   data: usize,
+  /// }
+  /// node_enum!{
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let idx = trans.insert(Node::A(NodeA{ data: 0 }));
+  /// graph.commit(trans);
+  ///
+  /// let mut t1 = Transaction::begin(&ctx, &graph);
+  /// let mut t2 = Transaction::begin(&ctx, &graph);
+  /// mut_node!(t1, Node::A, idx, |x| { x.data += 1 });
+  /// mut_node!(t2, Node::A, idx, |x| { x.data += 2 });
+  ///
+  /// assert!(graph.try_commit(t1).is_ok());
+  /// // t2 does not see the change of t1
+  /// let conflict = graph.try_commit(t2).unwrap_err();
+  /// assert_eq!(Vec::from_iter(conflict.nodes), vec![idx]);
+  /// assert_eq!(get_node!(graph, Node::A, idx).unwrap().data, 1);
+  /// # }
+  /// ```
+  pub fn begin(context: &Context, graph: &Graph<NodeT, Arena>) -> Self {
+    Self::empty(context, Some(graph.node_versions.lease(graph.version())))
+  }
+}
+
+impl<'a, NodeT, Arena, Funcs> Transaction<'a, NodeT, Arena, Funcs>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+  Funcs: FuncKind<'a, NodeT>,
+{
+  /// Read a node from the graph, and record that the transaction depends on it.
+  ///
+  /// Only useful for transactions made by [`begin`](Transaction::begin).
+  pub fn read<'g>(&mut self, graph: &'g Graph<NodeT, Arena>, idx: NodeIndex) -> Option<&'g NodeT> {
+    self.read_nodes.insert(idx);
+    graph.get(idx)
+  }
+
+  /// Get the version of the graph when the transaction began, or `None` if it is made by [`new`](Transaction::new)
+  pub fn base_version(&self) -> Option<u64> {
+    self.base_version
+  }
+
+  /// The existing nodes this transaction reads or writes
+  fn depended_nodes(&self) -> OrderSet<NodeIndex> {
+    let mut result = self.read_nodes.clone();
+    result.extend(self.dec_nodes.iter().copied());
+    result.extend(self.mut_nodes.iter().map(|(x, _)| *x));
+    result.extend(self.update_nodes.iter().map(|(x, _)| *x));
+    for (x, y) in self.redirect_links_vec.iter().chain(self.redirect_all_links_vec.iter()) {
+      result.insert(*x);
+      result.insert(*y);
+    }
+    for (_, n) in self.inc_nodes.iter() {
+      result.extend(n.iter_sources().map(|(y, _)| y).filter(|y| !y.is_empty() && !self.inc_nodes.contains(*y)));
+    }
+    result
+  }
+
+  /// The nodes this transaction writes directly, not including the nodes changed by links
+  pub(crate) fn written_nodes(&self) -> OrderSet<NodeIndex> {
+    let mut result = OrderSet::new();
+    result.extend(self.inc_nodes.iter().map(|(x, _)| x));
+    result.extend(self.dec_nodes.iter().copied());
+    result.extend(self.mut_nodes.iter().map(|(x, _)| *x));
+    result.extend(self.update_nodes.iter().map(|(x, _)| *x));
+    result
+  }
+}
+
+impl<NodeT, Arena> Graph<NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Get the current version of the graph, which is increased by every commit and every modification by [`data_mut`](Graph::data_mut)
+  pub fn version(&self) -> u64 {
+    self.version
+  }
+
+  /// Get the version when the node is last inserted, changed, linked or removed, or `None` if it is never touched by this graph
+  ///
+  /// The versions of removed nodes are dropped when no transaction began before the removal,
+  /// see the [module documentation](crate::version).
+  pub fn node_version(&self, idx: NodeIndex) -> Option<u64> {
+    self.node_versions.get(idx)
+  }
+
+  /// Similar to [`commit()`](Graph::commit), but returns a [`Conflict`] instead of panic if the transaction conflicts with the commits after it began.
+  ///
+  /// Nothing is changed if there is a conflict. See [`begin`](Transaction::begin) for the example.
+  pub fn try_commit(&mut self, t: Transaction<NodeT, Arena>) -> Result<(), Conflict> {
+    self.check_conflict(&t)?;
+    let lcr = self.apply_commit(t);
+    self.check_link_type(&lcr);
+    self.check_unique_indexes();
+    Ok(())
+  }
+
+  pub(crate) fn check_conflict(&self, t: &Transaction<NodeT, Arena>) -> Result<(), Conflict> {
+    let mut nodes = OrderSet::new();
+    let mut oldest = self.version;
+    if let Some(base_version) = t.base_version.filter(|v| *v != self.version) {
+      nodes.extend(
+        t.depended_nodes().into_iter().filter(|x| self.node_versions.get(*x).is_some_and(|v| v > base_version)),
+      );
+      oldest = base_version;
+    }
+    // The nodes written by a view must be unchanged since, and still in the graph
+    for (x, version) in &t.viewed_nodes {
+      if self.node_versions.get(*x).map_or(!self.contains(*x), |v| v > *version) {
+        nodes.insert(*x);
+        oldest = oldest.min(*version);
+      }
+    }
+    if nodes.is_empty() {
+      Ok(())
+    } else {
+      Err(Conflict { base_version: oldest, graph_version: self.version, nodes })
+    }
+  }
+
+  /// Increase the version, and mark the written nodes and the ends of the changed links with the new version
+  pub(crate) fn record_versions(&mut self, written: OrderSet<NodeIndex>) {
+    self.version += 1;
+    for x in written {
+      self.node_versions.insert(x, self.version);
+      if !self.nodes.contains(x) {
+        self.node_versions.mark_removed(x);
+      }
+    }
+    self.node_versions.prune();
+  }
+}
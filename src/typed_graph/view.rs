@@ -0,0 +1,340 @@
+//! Read the graph as if a transaction were committed, see [`TransactionView`]
+
+use std::cell::RefCell;
+use std::iter::Peekable;
+
+use super::apply::{self, NodeStore};
+use super::*;
+
+type BackLinkSet<NodeT> = OrderSet<(NodeIndex, <NodeT as NodeEnum>::SourceEnum)>;
+
+/// A read-only view of a [`Graph`] with the changes of a [`Transaction`] applied, without committing the transaction.
+///
+/// The view has the read methods of [`Graph`]: [`get`](TransactionView::get),
+/// [`contains`](TransactionView::contains), [`iter`](TransactionView::iter), [`iter_type`](TransactionView::iter_type),
+/// [`back_links`](TransactionView::back_links), [`lookup`](TransactionView::lookup) and so on,
+/// so [`get_node!`](crate::get_node!) and [`iter_nodes!`](crate::iter_nodes!) work on it.
+/// Links maintained by the graph, such as bidirectional links and links to removed nodes, are also applied.
+///
+/// The view borrows the graph, and only keeps the nodes and back links changed by the transaction,
+/// so making a view costs the size of the transaction, not the size of the graph.
+/// It does not change when more operations are added to the transaction. Make a new view to see them.
+///
+/// # Example
+/// ```
+/// use ttgraph::*;
+/// use std::collections::BTreeSet;
+/// #[derive(TypedNode, Clone)]
+/// struct FactoryNode{
+///   workers: BTreeSet<NodeIndex>,
+/// }
+/// #[derive(TypedNode, Clone)]
+/// struct WorkerNode{
+///   salary: usize,
+///   factory: NodeIndex,
+/// }
+/// node_enum!{
+///   #[derive(Clone)]
+///   enum Node{
+///     Factory(FactoryNode),
+///     Worker(WorkerNode),
+///   }
+///   bidirectional!{
+///     Factory.workers <-> Worker.factory,
+///   }
+/// }
+///
+/// # fn main() {
+/// let ctx = Context::new();
+/// let mut graph = Graph::<Node>::new(&ctx);
+/// let mut trans = Transaction::new(&ctx);
+/// let factory = trans.insert(Node::Factory(FactoryNode{ workers: BTreeSet::new() }));
+/// graph.commit(trans);
+///
+/// let mut trans = Transaction::new(&ctx);
+/// let worker = trans.insert(Node::Worker(WorkerNode{ salary: 1, factory }));
+///
+/// // The transaction is not committed, but the view sees its changes
+/// let view = TransactionView::new(&graph, &mut trans);
+/// assert_eq!(get_node!(view, Node::Factory, factory).unwrap().workers, BTreeSet::from([worker]));
+/// assert!(!graph.contains(worker));
+///
+/// // Decide the next step by the view
+/// if view.back_links(factory).count() == 1 {
+///   mut_node!(trans, Node::Worker, worker, |x| { x.salary *= 10 });
+/// }
+/// graph.commit(trans);
+/// assert_eq!(get_node!(graph, Node::Worker, worker).unwrap().salary, 10);
+/// # }
+/// ```
+pub struct TransactionView<'g, NodeT, Arena = <NodeT as NodeEnum>::GenArena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  graph: &'g Graph<NodeT, Arena>,
+  /// Nodes inserted or changed by the transaction
+  nodes: OrderMap<NodeIndex, NodeT>,
+  /// Nodes of the graph removed by the transaction
+  removed: OrderSet<NodeIndex>,
+  /// Back links changed by the transaction, `None` if they are removed with the node
+  back_links: OrderMap<NodeIndex, Option<BackLinkSet<NodeT>>>,
+  /// Indexes of the nodes in `nodes`
+  indexes: IndexStore,
+}
+
+impl<'g, NodeT, Arena> TransactionView<'g, NodeT, Arena>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Make a view of the graph as if the transaction were committed.
+  ///
+  /// The closures of [`mutate`](Transaction::mutate) and [`update`](Transaction::update) can only be called once,
+  /// so they are taken from the transaction and called by the view.
+  /// Each of them is replaced in the transaction by a closure writing the node it produced in the view,
+  /// so the nodes are the same at commit, including the links redirected by the transaction before the view is made.
+  ///
+  /// To keep the changes of other commits from being overwritten, the transaction remembers the version of the graph
+  /// when the view is made. If any of these nodes is changed or removed by another commit before the transaction
+  /// is committed, [`commit`](Graph::commit) panics and [`try_commit`](Graph::try_commit) returns a [`Conflict`].
+  ///
+  /// # Example
+  /// ```
+  /// use ttgraph::*;
+  /// #[derive(TypedNode, Clone)]
+  /// struct NodeA{
+  ///   data: usize,
+  /// }
+  /// node_enum!{
+  ///   #[derive(Clone)]
+  ///   enum Node{
+  ///     A(NodeA)
+  ///   }
+  /// }
+  ///
+  /// # fn main() {
+  /// let ctx = Context::new();
+  /// let mut graph = Graph::<Node>::new(&ctx);
+  /// let mut trans = Transaction::new(&ctx);
+  /// let idx = trans.insert(Node::A(NodeA{ data: 1 }));
+  /// graph.commit(trans);
+  ///
+  /// let mut trans = Transaction::new(&ctx);
+  /// mut_node!(trans, Node::A, idx, |x| { x.data += 1 });
+  /// let view = TransactionView::new(&graph, &mut trans);
+  /// assert_eq!(get_node!(view, Node::A, idx).unwrap().data, 2);
+  ///
+  /// // Another commit changes the node after the view is made
+  /// let mut other = Transaction::new(&ctx);
+  /// mut_node!(other, Node::A, idx, |x| { x.data = 10 });
+  /// graph.commit(other);
+  ///
+  /// let conflict = graph.try_commit(trans).unwrap_err();
+  /// assert_eq!(Vec::from_iter(conflict.nodes), vec![idx]);
+  /// assert_eq!(get_node!(graph, Node::A, idx).unwrap().data, 10);
+  /// # }
+  /// ```
+  pub fn new<'a>(graph: &'g Graph<NodeT, Arena>, trans: &mut Transaction<'a, NodeT, Arena>) -> Self
+  where
+    NodeT: 'a,
+  {
+    assert!(graph.ctx_id == trans.ctx_id, "The transaction and the graph are from different context!");
+    let mut view = TransactionView {
+      graph,
+      nodes: OrderMap::new(),
+      removed: OrderSet::new(),
+      back_links: OrderMap::new(),
+      indexes: IndexStore::default(),
+    };
+
+    let mut_nodes = std::mem::take(&mut trans.mut_nodes);
+    let update_nodes = std::mem::take(&mut trans.update_nodes);
+    let mut_targets = Vec::from_iter(mut_nodes.iter().map(|(x, _)| *x));
+    let update_targets = Vec::from_iter(update_nodes.iter().map(|(x, _)| *x));
+    // The nodes produced by each closure, in the order they are called
+    let mut_results = RefCell::new(Vec::new());
+    let update_results = RefCell::new(Vec::new());
+    let changes = apply::Changes {
+      redirect_links_vec: trans.redirect_links_vec.clone(),
+      inc_nodes: trans.inc_nodes.iter().map(|(x, n)| (x, n.clone())),
+      mut_nodes: mut_nodes.into_iter().map(|(x, f)| {
+        let results = &mut_results;
+        (x, move |n: &mut NodeT| {
+          f(n);
+          results.borrow_mut().push(n.clone());
+        })
+      }),
+      update_nodes: update_nodes.into_iter().map(|(x, f)| {
+        let results = &update_results;
+        (x, move |n: NodeT| {
+          let n = f(n);
+          results.borrow_mut().push(n.clone());
+          n
+        })
+      }),
+      redirect_all_links_vec: trans.redirect_all_links_vec.clone(),
+      dec_nodes: Vec::from_iter(trans.dec_nodes.iter().copied()),
+    };
+    apply::apply_changes(&mut view, changes);
+
+    for (x, node) in mut_targets.iter().zip(mut_results.into_inner()) {
+      trans.mut_nodes.push((*x, Box::new(move |old| *old = node)));
+    }
+    for (x, node) in update_targets.iter().zip(update_results.into_inner()) {
+      trans.update_nodes.push((*x, Box::new(move |_| node)));
+    }
+    for x in mut_targets.into_iter().chain(update_targets) {
+      trans.viewed_nodes.entry(x).or_insert(graph.version());
+    }
+
+    for (x, n) in &view.nodes {
+      view.indexes.insert(*x, n);
+    }
+    view
+  }
+}
+
+impl<'g, NodeT, Arena> TransactionView<'g, NodeT, Arena>
+where
+  NodeT: NodeEnum,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  /// Get the node, see [`Graph::get`]
+  pub fn get(&self, idx: NodeIndex) -> Option<&NodeT> {
+    if let Some(node) = self.nodes.get(&idx) {
+      Some(node)
+    } else if self.removed.contains(&idx) {
+      None
+    } else {
+      self.graph.get(idx)
+    }
+  }
+
+  /// Check if a node is in the view, see [`Graph::contains`]
+  pub fn contains(&self, idx: NodeIndex) -> bool {
+    self.get(idx).is_some()
+  }
+
+  /// Get the number of nodes in the view
+  pub fn len(&self) -> usize {
+    self.graph.len() + self.nodes.keys().filter(|x| !self.graph.contains(**x)).count() - self.removed.len()
+  }
+
+  /// Check if the view has no node
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Iterate all nodes, in the same order as [`Graph::iter`]
+  pub fn iter(&self) -> impl Iterator<Item = (NodeIndex, &NodeT)> {
+    std::iter::successors(Some(NodeT::Discriminant::first()), |d| d.next()).flat_map(move |d| self.iter_type(d))
+  }
+
+  /// Iterate a type of nodes denote by the discriminant in the order of [`NodeIndex`], see [`Graph::iter_type`]
+  pub fn iter_type(&self, d: NodeT::Discriminant) -> impl Iterator<Item = (NodeIndex, &NodeT)> {
+    let mut changed = Vec::from_iter(self.nodes.iter().filter(|(_, n)| n.discriminant() == d).map(|(x, n)| (*x, n)));
+    changed.sort_unstable_by_key(|(x, _)| *x);
+    let unchanged = self.graph.nodes.get_container(d).iter().map(|(k, n)| (NodeIndex(k), n));
+    MergeIter {
+      unchanged: unchanged.filter(|(x, _)| !self.removed.contains(x) && !self.nodes.contains_key(x)).peekable(),
+      changed: changed.into_iter().peekable(),
+    }
+  }
+
+  /// Iterate the nodes within the named group, see [`Graph::iter_group`]
+  pub fn iter_group(&self, name: &'static str) -> impl Iterator<Item = (NodeIndex, &NodeT)> {
+    NodeT::group_discriminants(name).iter().flat_map(move |d| self.iter_type(*d))
+  }
+
+  /// Iterate the nodes linking to the given node, as `(source node, link)`, see [`Graph::back_links`]
+  pub fn back_links(&self, idx: NodeIndex) -> impl Iterator<Item = (NodeIndex, NodeT::LinkMirrorEnum)> + '_ {
+    let links = match self.back_links.get(&idx) {
+      Some(links) => links.as_ref(),
+      None => self.graph.back_links.get(&idx),
+    };
+    links.into_iter().flatten().map(|(x, s)| (*x, NodeT::to_link_mirror_enum(*s)))
+  }
+
+  /// Find the nodes whose indexed data field equals to the key, see [`Graph::lookup`]
+  pub fn lookup<K: Any + Hash + Eq>(&self, name: &str, key: &K) -> impl Iterator<Item = NodeIndex> + '_ {
+    let unchanged = self.graph.lookup(name, key).filter(|x| !self.removed.contains(x) && !self.nodes.contains_key(x));
+    let changed = Vec::from_iter(self.indexes.lookup(name, key).into_iter().flat_map(|nodes| nodes.iter().copied()));
+    unchanged.chain(changed)
+  }
+}
+
+impl<'g, NodeT, Arena> NodeStore<NodeT> for TransactionView<'g, NodeT, Arena>
+where
+  NodeT: NodeEnum + Clone,
+  Arena: CateArena<V = NodeT, D = NodeT::Discriminant>,
+{
+  fn node(&self, x: NodeIndex) -> Option<&NodeT> {
+    self.get(x)
+  }
+
+  fn node_mut(&mut self, x: NodeIndex) -> &mut NodeT {
+    let (graph, removed) = (self.graph, &self.removed);
+    self.nodes.entry(x).or_insert_with(|| graph.get(x).filter(|_| !removed.contains(&x)).unwrap().clone())
+  }
+
+  fn insert_node(&mut self, x: NodeIndex, node: NodeT) {
+    self.nodes.insert(x, node);
+  }
+
+  fn remove_node(&mut self, x: NodeIndex) -> NodeT {
+    let node = match self.nodes.swap_remove(&x) {
+      Some(node) => node,
+      None => self.graph.get(x).filter(|_| !self.removed.contains(&x)).expect("Remove a non-existing node!").clone(),
+    };
+    if self.graph.contains(x) {
+      self.removed.insert(x);
+    }
+    node
+  }
+
+  fn update_node(&mut self, x: NodeIndex, f: impl FnOnce(NodeT) -> NodeT) {
+    self.node_mut(x);
+    let node = self.nodes.swap_remove(&x).unwrap();
+    self.nodes.insert(x, f(node));
+  }
+
+  fn back_links_mut(&mut self, x: NodeIndex) -> &mut BackLinkSet<NodeT> {
+    let graph = self.graph;
+    let links = self.back_links.entry(x).or_insert_with(|| Some(graph.back_links.get(&x).cloned().unwrap_or_default()));
+    links.get_or_insert_with(OrderSet::new)
+  }
+
+  fn take_back_links(&mut self, x: NodeIndex) -> BackLinkSet<NodeT> {
+    match self.back_links.get_mut(&x) {
+      Some(links) => links.take(),
+      None => {
+        self.back_links.insert(x, None);
+        self.graph.back_links.get(&x).cloned()
+      },
+    }
+    .unwrap()
+  }
+}
+
+/// Merge the unchanged nodes of the graph and the changed nodes of the view, both in the order of [`NodeIndex`]
+struct MergeIter<I: Iterator, J: Iterator> {
+  unchanged: Peekable<I>,
+  changed: Peekable<J>,
+}
+
+impl<'a, NodeT: 'a, I, J> Iterator for MergeIter<I, J>
+where
+  I: Iterator<Item = (NodeIndex, &'a NodeT)>,
+  J: Iterator<Item = (NodeIndex, &'a NodeT)>,
+{
+  type Item = (NodeIndex, &'a NodeT);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match (self.unchanged.peek(), self.changed.peek()) {
+      (Some((x, _)), Some((y, _))) if x < y => self.unchanged.next(),
+      (_, Some(_)) => self.changed.next(),
+      _ => self.unchanged.next(),
+    }
+  }
+}
@@ -0,0 +1,148 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_append {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug)]
+  struct NodeA {
+    data: usize,
+    to: BTreeSet<NodeIndex>,
+  }
+
+  node_enum! {
+    #[derive(Debug)]
+    enum Node{
+      A(NodeA),
+    }
+  }
+
+  fn node(data: usize, to: &[NodeIndex]) -> Node {
+    Node::A(NodeA { data, to: BTreeSet::from_iter(to.iter().copied()) })
+  }
+
+  #[test]
+  fn test_append_order() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1, &[]));
+    let b = trans.insert(node(2, &[a]));
+    graph.commit(trans);
+
+    let mut t1 = Transaction::new(&ctx);
+    let c = t1.insert(node(3, &[]));
+    let d = t1.insert(node(4, &[]));
+    mut_node!(t1, Node::A, a, |x| { x.data *= 10 });
+
+    let mut t2 = Transaction::new(&ctx);
+    mut_node!(t2, Node::A, a, |x| { x.data += 1 });
+    mut_node!(t2, Node::A, c, |x| { x.data = 30 });
+    update_node!(t2, Node::A, b, |x| { NodeA { data: x.data + 1, ..x } });
+    t2.remove(d);
+    let e = t2.insert(node(5, &[c]));
+    t2.redirect_links(a, e);
+
+    assert!(t1.append(t2).is_empty());
+    graph.commit(t1);
+
+    assert_eq!(get_node!(graph, Node::A, a).unwrap().data, 11);
+    assert_eq!(get_node!(graph, Node::A, b).unwrap().data, 3);
+    assert_eq!(get_node!(graph, Node::A, b).unwrap().to, BTreeSet::from([e]));
+    assert_eq!(get_node!(graph, Node::A, c).unwrap().data, 30);
+    assert!(graph.get(d).is_none());
+    assert_eq!(get_node!(graph, Node::A, e).unwrap().to, BTreeSet::from([c]));
+  }
+
+  #[test]
+  fn test_append_conflicts() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1, &[]));
+    let b = trans.insert(node(2, &[]));
+    let c = trans.insert(node(3, &[]));
+    graph.commit(trans);
+
+    let mut t1 = Transaction::<Node>::new(&ctx);
+    t1.remove(a);
+    t1.remove(b);
+
+    let mut t2 = Transaction::new(&ctx);
+    t2.remove(a);
+    update_node!(t2, Node::A, b, |x| { x });
+    t2.redirect_all_links(c, b);
+    let d = t2.insert(node(4, &[a, c]));
+
+    let conflicts = t1.append(t2);
+    assert_eq!(
+      conflicts,
+      vec![
+        AppendConflict::RemoveTwice(a),
+        AppendConflict::ModifyRemoved(b),
+        AppendConflict::RedirectToRemoved(b),
+        AppendConflict::LinkToRemoved(d, a),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_append_inserted_then_removed() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1, &[]));
+    graph.commit(trans);
+
+    let mut t1 = Transaction::<Node>::new(&ctx);
+    let x = t1.insert(node(2, &[]));
+    let y = alloc_node!(t1, Node::A);
+    t1.remove(x);
+    t1.remove(y);
+
+    let mut t2 = Transaction::new(&ctx);
+    mut_node!(t2, Node::A, x, |n| { n.data = 3 });
+    t2.remove(y);
+    t2.redirect_links(a, x);
+    let z = t2.insert(node(4, &[y]));
+
+    let conflicts = t1.append(t2);
+    assert_eq!(
+      conflicts,
+      vec![
+        AppendConflict::RemoveTwice(y),
+        AppendConflict::ModifyRemoved(x),
+        AppendConflict::RedirectToRemoved(x),
+        AppendConflict::LinkToRemoved(z, y),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_append_redirect_from_removed() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1, &[]));
+    let b = trans.insert(node(2, &[]));
+    graph.commit(trans);
+
+    let mut t1 = Transaction::<Node>::new(&ctx);
+    t1.remove(a);
+    let mut t2 = Transaction::new(&ctx);
+    t2.redirect_all_links(a, b);
+
+    assert_eq!(t1.append(t2), vec![AppendConflict::RedirectToRemoved(a)]);
+  }
+
+  #[test]
+  #[should_panic(expected = "The transactions are from different context!")]
+  fn test_append_context() {
+    let mut t1 = Transaction::<Node>::new(&Context::new());
+    t1.append(Transaction::new(&Context::new()));
+  }
+}
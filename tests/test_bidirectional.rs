@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 #![allow(unused_mut)]
+#![allow(clippy::enum_variant_names)]
 
 #[cfg(test)]
 mod test_bidirectional {
@@ -0,0 +1,184 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_binary {
+  use serde::{de::DeserializeSeed, Deserialize, Serialize};
+  use std::{cell::Cell, collections::BTreeSet, io};
+
+  use ttgraph::{binary::*, serialize::DeserializeError, *};
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct TreeNode {
+    children: BTreeSet<NodeIndex>,
+    father: NodeIndex,
+    data: usize,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct LeafNode {
+    next: NodeIndex,
+    name: String,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Node{
+      Tree(TreeNode),
+      Leaf(LeafNode),
+    }
+    bidirectional!{
+      Tree.father <-> Tree.children,
+    }
+  }
+
+  mod other {
+    use serde::{Deserialize, Serialize};
+    use ttgraph::*;
+
+    #[derive(TypedNode, Serialize, Deserialize)]
+    pub struct TreeNode {
+      pub father: NodeIndex,
+    }
+
+    node_enum! {
+      #[derive(Serialize, Deserialize)]
+      pub enum Node{
+        Tree(TreeNode),
+      }
+    }
+  }
+
+  fn build(n: usize) -> Graph<Node> {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let root = trans.insert(Node::Tree(TreeNode { children: BTreeSet::new(), father: NodeIndex::empty(), data: 0 }));
+    let mut prev = NodeIndex::empty();
+    for i in 1..n {
+      if i % 2 == 0 {
+        trans.insert(Node::Tree(TreeNode { children: BTreeSet::new(), father: root, data: i }));
+      } else {
+        prev = trans.insert(Node::Leaf(LeafNode { next: prev, name: format!("leaf{}", i) }));
+      }
+    }
+    graph.commit(trans);
+    graph
+  }
+
+  fn assert_same(a: &Graph<Node>, b: &Graph<Node>) {
+    assert_eq!(a.len(), b.len());
+    for (i, x) in a.iter() {
+      assert_eq!(b.get(i), Some(x));
+    }
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let graph = build(1000);
+    let mut buf = Vec::new();
+    write_binary(&graph, &mut buf, &JsonCodec).unwrap();
+    let (ctx, mut graph2) = read_binary::<Node, _, _>(buf.as_slice(), &JsonCodec).unwrap();
+    assert_same(&graph, &graph2);
+
+    // Smaller than the JSON of the graph, as the indexes are delta encoded
+    assert!(buf.len() < serde_json::to_vec(&graph).unwrap().len());
+    // The payloads have neither the variants nor the links
+    let contains = |s: &[u8]| buf.windows(s.len()).any(|x| x == s);
+    assert!(contains(br#"{"children":[],"father":0,"data":2}"#));
+    assert!(contains(br#"{"next":0,"name":"leaf3"}"#));
+    assert!(!contains(b"Tree"));
+    assert!(!contains(b"Leaf"));
+
+    // The context continues after the read nodes
+    let mut trans = Transaction::new(&ctx);
+    let x = trans.insert(Node::Leaf(LeafNode { next: NodeIndex::empty(), name: String::new() }));
+    graph2.commit(trans);
+    assert!(!graph.contains(x));
+    assert_eq!(graph2.len(), 1001);
+
+    // Empty graph
+    let mut buf = Vec::new();
+    write_binary(&Graph::<Node>::new(&Context::new()), &mut buf, &JsonCodec).unwrap();
+    let (ctx, graph3) = read_binary::<Node, _, _>(buf.as_slice(), &JsonCodec).unwrap();
+    assert!(graph3.is_empty());
+  }
+
+  #[test]
+  fn test_removed_nodes() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let nodes = Vec::from_iter(
+      (0..100).map(|i| trans.insert(Node::Leaf(LeafNode { next: NodeIndex::empty(), name: i.to_string() }))),
+    );
+    graph.commit(trans);
+    let mut trans = Transaction::new(&ctx);
+    for x in nodes.iter().filter(|x| x.0 % 3 == 0) {
+      trans.remove(*x);
+    }
+    graph.commit(trans);
+
+    let mut buf = Vec::new();
+    write_binary(&graph, &mut buf, &JsonCodec).unwrap();
+    let (ctx2, graph2) = read_binary::<Node, _, _>(buf.as_slice(), &JsonCodec).unwrap();
+    assert_same(&graph, &graph2);
+  }
+
+  /// A codec counting the encoded payloads
+  struct Counting(Cell<usize>);
+
+  impl BinaryCodec for Counting {
+    fn encode<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> io::Result<()> {
+      self.0.set(self.0.get() + 1);
+      JsonCodec.encode(value, buf)
+    }
+
+    fn decode<'de, S: DeserializeSeed<'de>>(&self, seed: S, buf: &'de [u8]) -> io::Result<S::Value> {
+      JsonCodec.decode(seed, buf)
+    }
+  }
+
+  #[test]
+  fn test_codec() {
+    let graph = build(10);
+    let codec = Counting(Cell::new(0));
+    let mut buf = Vec::new();
+    write_binary(&graph, &mut buf, &codec).unwrap();
+    assert_eq!(codec.0.get(), 10);
+    let (ctx, graph2) = read_binary::<Node, _, _>(buf.as_slice(), &codec).unwrap();
+    assert_same(&graph, &graph2);
+  }
+
+  #[test]
+  fn test_errors() {
+    let graph = build(5);
+    let mut buf = Vec::new();
+    write_binary(&graph, &mut buf, &JsonCodec).unwrap();
+
+    let mut bad_magic = buf.clone();
+    bad_magic[0] = b'X';
+    let err = read_binary::<Node, _, _>(bad_magic.as_slice(), &JsonCodec).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let err = read_binary::<Node, _, _>(&buf[..buf.len() - 3], &JsonCodec).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let err = read_binary::<other::Node, _, _>(buf.as_slice(), &JsonCodec).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Point a link to a missing node
+    // The second leaf 4 links to the first leaf 2, the framing before its payload is the link count 1,
+    // the target difference zigzag(-2) and the payload length
+    let payload = br#"{"next":0,"name":"leaf3"}"#;
+    let pos = buf.windows(payload.len()).position(|x| x == payload).unwrap();
+    assert_eq!(buf[pos - 3..pos], [1, 3, payload.len() as u8]);
+    let mut dangling = buf.clone();
+    dangling[pos - 2] = 10;
+    let err = read_binary::<Node, _, _>(dangling.as_slice(), &JsonCodec).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let inner = err.into_inner().unwrap().downcast::<DeserializeError>().unwrap();
+    assert!(matches!(*inner, DeserializeError::Dangling { link: "next", .. }));
+  }
+}
@@ -0,0 +1,160 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_clone_subgraph {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct ScopeNode {
+    inner: BTreeSet<NodeIndex>,
+    outer: NodeIndex,
+    depth: usize,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct CallNode {
+    args: Vec<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct FactoryNode {
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct WorkerNode {
+    factory: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Node{
+      Scope(ScopeNode),
+      Call(CallNode),
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+    }
+    bidirectional!{
+      Scope.outer <-> Scope.inner,
+      Factory.workers <-> Worker.factory,
+    }
+  }
+
+  fn scope(outer: NodeIndex, depth: usize) -> Node {
+    Node::Scope(ScopeNode { inner: BTreeSet::new(), outer, depth })
+  }
+
+  /// A body to inline: body -> (a -> (c, d), b), and a call with the arguments [c, b, c]
+  fn build(ctx: &Context) -> (Graph<Node>, [NodeIndex; 6]) {
+    let mut graph = Graph::<Node>::new(ctx);
+    let mut trans = Transaction::new(ctx);
+    let body = trans.insert(scope(NodeIndex::empty(), 0));
+    let a = trans.insert(scope(body, 1));
+    let b = trans.insert(scope(body, 1));
+    let c = trans.insert(scope(a, 2));
+    let d = trans.insert(scope(a, 2));
+    let call = trans.insert(Node::Call(CallNode { args: vec![c, b, c] }));
+    graph.commit(trans);
+    (graph, [body, a, b, c, d, call])
+  }
+
+  #[test]
+  fn test_clone_subtree() {
+    let ctx = Context::new();
+    let (mut graph, [body, a, b, c, d, call]) = build(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let id_map = trans.clone_subgraph(&graph, [a, c, d]);
+    graph.commit(trans);
+    assert_eq!(graph.len(), 9);
+
+    let new_a = get_node!(graph, Node::Scope, id_map[&a]).unwrap();
+    assert_eq!(new_a.depth, 1);
+    assert_eq!(new_a.inner, BTreeSet::from([id_map[&c], id_map[&d]]));
+    // The external link is kept, and the bidirectional link is completed
+    assert_eq!(new_a.outer, body);
+    assert_eq!(get_node!(graph, Node::Scope, body).unwrap().inner, BTreeSet::from([a, b, id_map[&a]]));
+    assert_eq!(get_node!(graph, Node::Scope, id_map[&c]).unwrap().outer, id_map[&a]);
+    // The originals are unchanged
+    assert_eq!(get_node!(graph, Node::Scope, a).unwrap().inner, BTreeSet::from([c, d]));
+    assert_eq!(get_node!(graph, Node::Scope, c).unwrap().outer, a);
+  }
+
+  #[test]
+  fn test_clone_father() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { workers: BTreeSet::new() }));
+    let w1 = trans.insert(Node::Worker(WorkerNode { factory }));
+    let w2 = trans.insert(Node::Worker(WorkerNode { factory }));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    let id_map = trans.clone_subgraph(&graph, [factory, w1]);
+    graph.commit(trans);
+
+    // w2 can only have one factory, so it is not a worker of the copy
+    assert_eq!(get_node!(graph, Node::Factory, id_map[&factory]).unwrap().workers, BTreeSet::from([id_map[&w1]]));
+    assert_eq!(get_node!(graph, Node::Worker, id_map[&w1]).unwrap().factory, id_map[&factory]);
+    assert_eq!(get_node!(graph, Node::Factory, factory).unwrap().workers, BTreeSet::from([w1, w2]));
+    assert_eq!(get_node!(graph, Node::Worker, w2).unwrap().factory, factory);
+
+    // Nothing to keep when the factory is cloned alone
+    let mut trans = Transaction::new(&ctx);
+    let id_map = trans.clone_subgraph(&graph, [factory]);
+    graph.commit(trans);
+    assert!(get_node!(graph, Node::Factory, id_map[&factory]).unwrap().workers.is_empty());
+    graph.check_backlinks();
+  }
+
+  #[test]
+  fn test_clone_vec() {
+    let ctx = Context::new();
+    let (mut graph, [body, a, b, c, d, call]) = build(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let id_map = trans.clone_subgraph(&graph, [call, c]);
+    let id_map2 = trans.clone_subgraph(&graph, [call, call]);
+    graph.commit(trans);
+
+    assert_eq!(id_map2.len(), 1);
+    assert_ne!(id_map[&call], id_map2[&call]);
+    assert_eq!(get_node!(graph, Node::Call, id_map[&call]).unwrap().args, vec![id_map[&c], b, id_map[&c]]);
+    assert_eq!(get_node!(graph, Node::Call, id_map2[&call]).unwrap().args, vec![c, b, c]);
+  }
+
+  #[test]
+  fn test_clone_and_redirect() {
+    let ctx = Context::new();
+    let (mut graph, [body, a, b, c, d, call]) = build(&ctx);
+    // Replace c by a copy of itself in one transaction
+    let mut trans = Transaction::new(&ctx);
+    let id_map = trans.clone_subgraph(&graph, [c]);
+    trans.redirect_links(c, id_map[&c]);
+    trans.remove(c);
+    graph.commit(trans);
+
+    assert_eq!(get_node!(graph, Node::Call, call).unwrap().args, vec![id_map[&c], b, id_map[&c]]);
+    assert_eq!(get_node!(graph, Node::Scope, a).unwrap().inner, BTreeSet::from([d, id_map[&c]]));
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_missing_node() {
+    let ctx = Context::new();
+    let (graph, _) = build(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    trans.clone_subgraph(&graph, [NodeIndex(100)]);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_different_context() {
+    let (graph, [body, ..]) = build(&Context::new());
+    let mut trans = Transaction::new(&Context::new());
+    trans.clone_subgraph(&graph, [body]);
+  }
+}
@@ -0,0 +1,150 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_data_mut {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug)]
+  struct WorkerNode {
+    name: String,
+    salary: (usize, usize),
+    factory: NodeIndex,
+  }
+
+  #[derive(TypedNode, Debug)]
+  struct LinkOnlyNode {
+    target: NodeIndex,
+  }
+
+  // The data view of WorkerNode is generated in its own mod, so the name is free to use
+  struct WorkerNodeData;
+
+  node_enum! {
+    #[derive(Debug)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+      LinkOnly(LinkOnlyNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+    index!{
+      Worker.name,
+      Factory.name: unique,
+    }
+  }
+
+  fn lookup_name(graph: &Graph<Node>, name: &str) -> BTreeSet<NodeIndex> {
+    BTreeSet::from_iter(graph.lookup("name", &name.to_string()))
+  }
+
+  #[test]
+  fn test_data_mut() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), salary: (1, 2), factory }));
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), salary: (3, 4), factory }));
+    let link = trans.insert(Node::LinkOnly(LinkOnlyNode { target: alice }));
+    graph.commit(trans);
+
+    let worker = get_data_mut!(graph, Node::Worker, alice).unwrap();
+    worker.salary.0 = 10;
+    assert!(get_data_mut!(graph, Node::Factory, alice).is_none());
+    assert!(get_data_mut!(graph, Node::LinkOnly, link).is_some());
+
+    for (idx, worker) in iter_nodes_data_mut!(graph, Node::Worker) {
+      worker.salary.1 += 1;
+    }
+
+    let worker = get_node!(graph, Node::Worker, alice).unwrap();
+    assert_eq!(worker.salary, (10, 3));
+    assert_eq!(worker.factory, factory);
+    let worker = get_node!(graph, Node::Worker, bob).unwrap();
+    assert_eq!(worker.salary, (3, 5));
+    assert_eq!(get_node!(graph, Node::Factory, factory).unwrap().workers, BTreeSet::from([alice, bob]));
+  }
+
+  #[test]
+  fn test_data_mut_index() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), salary: (1, 2), factory }));
+    graph.commit(trans);
+
+    *get_data_mut!(graph, Node::Worker, alice).unwrap().name = "Bob".to_string();
+    assert_eq!(lookup_name(&graph, "Alice"), BTreeSet::new());
+    assert_eq!(lookup_name(&graph, "Bob"), BTreeSet::from([alice]));
+
+    // Changed again before commit
+    *get_data_mut!(graph, Node::Worker, alice).unwrap().name = "Carol".to_string();
+    assert_eq!(lookup_name(&graph, "Bob"), BTreeSet::new());
+    assert_eq!(lookup_name(&graph, "Carol"), BTreeSet::from([alice]));
+
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Worker, alice, |x| { x.name = "Dave".to_string() });
+    graph.commit(trans);
+    assert_eq!(lookup_name(&graph, "Carol"), BTreeSet::new());
+    assert_eq!(lookup_name(&graph, "Dave"), BTreeSet::from([alice]));
+
+    *get_data_mut!(graph, Node::Worker, alice).unwrap().name = "Eve".to_string();
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(alice);
+    graph.commit(trans);
+    assert_eq!(lookup_name(&graph, "Eve"), BTreeSet::new());
+  }
+
+  #[test]
+  fn test_iter_data_mut_index() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let workers = Vec::from_iter(
+      (0..10).map(|i| trans.insert(Node::Worker(WorkerNode { name: i.to_string(), salary: (i, i), factory }))),
+    );
+    graph.commit(trans);
+
+    for (_, data) in iter_nodes_data_mut!(graph, Node::Worker) {
+      data.name.push('!');
+    }
+    assert_eq!(lookup_name(&graph, "3"), BTreeSet::new());
+    assert_eq!(lookup_name(&graph, "3!"), BTreeSet::from([workers[3]]));
+    assert_eq!(lookup_name(&graph, "F"), BTreeSet::from([factory]));
+
+    // Collected views are indexed again by the lookup after they are dropped
+    let views = Vec::from_iter(iter_nodes_data_mut!(graph, Node::Worker));
+    for (_, data) in views {
+      *data.name = "W".to_string();
+    }
+    assert_eq!(lookup_name(&graph, "W"), BTreeSet::from_iter(workers.iter().copied()));
+  }
+
+  #[test]
+  #[should_panic(expected = "Unique index check failed")]
+  fn test_data_mut_unique() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Factory(FactoryNode { name: "F1".to_string(), workers: BTreeSet::new() }));
+    let f2 = trans.insert(Node::Factory(FactoryNode { name: "F2".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    *get_data_mut!(graph, Node::Factory, f2).unwrap().name = "F1".to_string();
+    graph.commit(Transaction::new(&ctx));
+  }
+}
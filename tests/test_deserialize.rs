@@ -0,0 +1,225 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_deserialize {
+  use serde::{Deserialize, Serialize};
+  use serde_json::{json, Value};
+  use std::collections::BTreeSet;
+
+  use ttgraph::{serialize::*, *};
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct FactoryNode {
+    workers: BTreeSet<NodeIndex>,
+    products: Vec<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct WorkerNode {
+    factory: NodeIndex,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct ProductNode {
+    serial: usize,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+      Product(ProductNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+    link_type!{
+      Factory.products: Product,
+    }
+  }
+
+  fn build() -> (Value, NodeIndex, NodeIndex, NodeIndex) {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let p = trans.insert(Node::Product(ProductNode { serial: 1 }));
+    let f = trans.insert(Node::Factory(FactoryNode { workers: BTreeSet::new(), products: vec![p] }));
+    let w = trans.insert(Node::Worker(WorkerNode { factory: f }));
+    graph.commit(trans);
+    (serde_json::to_value(&graph).unwrap(), f, w, p)
+  }
+
+  fn load(value: &Value, repair: bool) -> Result<(Context, Graph<Node>), DeserializeError> {
+    try_deserialize_graph(serde_json::from_value(value.clone()).unwrap(), repair, true)
+  }
+
+  /// Find the serialized node of the index
+  fn node_mut(value: &mut Value, idx: NodeIndex) -> &mut Value {
+    let nodes = value["nodes"].as_array_mut().unwrap();
+    &mut nodes.iter_mut().find(|x| x[0] == json!(idx)).unwrap()[1]
+  }
+
+  #[test]
+  fn test_valid() {
+    let (value, f, w, p) = build();
+    let (ctx, graph) = load(&value, false).unwrap();
+    assert_eq!(graph.len(), 3);
+    assert_eq!(get_node!(graph, Node::Factory, f).unwrap().workers, BTreeSet::from([w]));
+  }
+
+  #[test]
+  fn test_index() {
+    let (mut value, f, w, p) = build();
+    let first = value["nodes"][0].clone();
+    let idx: NodeIndex = serde_json::from_value(first[0].clone()).unwrap();
+    value["nodes"].as_array_mut().unwrap().push(first);
+    assert_eq!(load(&value, true).err(), Some(DeserializeError::DuplicateIndex(idx)));
+
+    let (mut value, f, w, p) = build();
+    value["nodes"].as_array_mut().unwrap().push(json!([0, {"Product": {"serial": 2}}]));
+    assert_eq!(load(&value, true).err(), Some(DeserializeError::EmptyIndex));
+  }
+
+  #[test]
+  fn test_dangling() {
+    let (mut value, f, w, p) = build();
+    let missing = NodeIndex(100);
+    node_mut(&mut value, w)["Worker"]["factory"] = json!(missing);
+    assert_eq!(
+      load(&value, false).err(),
+      Some(DeserializeError::Dangling { source: w, link: "factory", target: missing })
+    );
+
+    // Repair point, set and vec links
+    node_mut(&mut value, f)["Factory"]["products"] = json!([p, missing, p]);
+    node_mut(&mut value, f)["Factory"]["workers"] = json!([missing]);
+    let (ctx, graph) = load(&value, true).unwrap();
+    assert!(get_node!(graph, Node::Worker, w).unwrap().factory.is_empty());
+    let factory = get_node!(graph, Node::Factory, f).unwrap();
+    assert_eq!(factory.products, vec![p, p]);
+    assert!(factory.workers.is_empty());
+    // The repaired graph still works with the context
+    let mut graph = graph;
+    let mut trans = Transaction::new(&ctx);
+    let w2 = trans.insert(Node::Worker(WorkerNode { factory: f }));
+    graph.commit(trans);
+    assert_eq!(get_node!(graph, Node::Factory, f).unwrap().workers, BTreeSet::from([w2]));
+  }
+
+  #[test]
+  fn test_link_type() {
+    let (mut value, f, w, p) = build();
+    node_mut(&mut value, f)["Factory"]["products"] = json!([p, w]);
+    let err = load(&value, true).err().unwrap();
+    assert_eq!(err, DeserializeError::LinkType { source: f, link: "products", target: w, found: "Worker".to_string() });
+    assert_eq!(err.to_string(), format!("Link {}.products points to node {} of an unexpected type Worker", f, w));
+  }
+
+  #[test]
+  fn test_bidirectional() {
+    let (mut value, f, w, p) = build();
+    node_mut(&mut value, f)["Factory"]["workers"] = json!([]);
+    assert_eq!(
+      load(&value, false).err(),
+      Some(DeserializeError::Bidirectional { source: w, link: "factory", target: f })
+    );
+
+    // Without the check, the missing side is added
+    let input = serde_json::from_value(value.clone()).unwrap();
+    let (ctx, graph) = try_deserialize_graph::<Node>(input, false, false).unwrap();
+    assert_eq!(get_node!(graph, Node::Factory, f).unwrap().workers, BTreeSet::from([w]));
+    let (ctx, graph) = deserialize_graph::<Node>(serde_json::from_value(value).unwrap());
+    assert_eq!(get_node!(graph, Node::Factory, f).unwrap().workers, BTreeSet::from([w]));
+  }
+
+  #[test]
+  fn test_id_count() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let p1 = trans.insert(Node::Product(ProductNode { serial: 1 }));
+    let p2 = trans.insert(Node::Product(ProductNode { serial: 2 }));
+    graph.commit(trans);
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(p2);
+    graph.commit(trans);
+    let mut value = serde_json::to_value(&graph).unwrap();
+
+    // The removed index is not given again
+    let (ctx2, mut graph2) = load(&value, false).unwrap();
+    let mut trans = Transaction::new(&ctx2);
+    let p3 = trans.insert(Node::Product(ProductNode { serial: 3 }));
+    graph2.commit(trans);
+    assert!(p3 != p1 && p3 != p2);
+
+    // The files written before the counter is stored continue from the max index
+    value.as_object_mut().unwrap().remove("id_count");
+    let (ctx3, mut graph3) = load(&value, false).unwrap();
+    let mut trans = Transaction::new(&ctx3);
+    let p3 = trans.insert(Node::Product(ProductNode { serial: 3 }));
+    graph3.commit(trans);
+    assert!(p3 != p1);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_deserialize_graph_panic() {
+    let (mut value, f, w, p) = build();
+    node_mut(&mut value, w)["Worker"]["factory"] = json!(100);
+    deserialize_graph::<Node>(serde_json::from_value(value).unwrap());
+  }
+
+  #[test]
+  fn test_deserialize_into() {
+    let (value, f, w, p) = build();
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let p0 = trans.insert(Node::Product(ProductNode { serial: 0 }));
+    graph.commit(trans);
+
+    let id_map = deserialize_into(&ctx, &mut graph, serde_json::from_value(value.clone()).unwrap()).unwrap();
+    assert_eq!(graph.len(), 4);
+    assert_eq!(id_map.len(), 3);
+    assert!(!id_map.values().any(|x| *x == p0));
+    let factory = get_node!(graph, Node::Factory, id_map[&f]).unwrap();
+    assert_eq!(factory.workers, BTreeSet::from([id_map[&w]]));
+    assert_eq!(factory.products, vec![id_map[&p]]);
+    assert_eq!(get_node!(graph, Node::Worker, id_map[&w]).unwrap().factory, id_map[&f]);
+
+    // Load again, the old ids may collide with the new ids
+    let id_map2 = deserialize_into(&ctx, &mut graph, serde_json::from_value(value).unwrap()).unwrap();
+    assert_eq!(graph.len(), 7);
+    let factory = get_node!(graph, Node::Factory, id_map2[&f]).unwrap();
+    assert_eq!(factory.workers, BTreeSet::from([id_map2[&w]]));
+    assert_eq!(get_node!(graph, Node::Worker, id_map[&w]).unwrap().factory, id_map[&f]);
+
+    // The context keeps working
+    let mut trans = Transaction::new(&ctx);
+    let w2 = trans.insert(Node::Worker(WorkerNode { factory: id_map[&f] }));
+    graph.commit(trans);
+    assert_eq!(get_node!(graph, Node::Factory, id_map[&f]).unwrap().workers, BTreeSet::from([id_map[&w], w2]));
+  }
+
+  #[test]
+  fn test_deserialize_into_error() {
+    let (mut value, f, w, p) = build();
+    node_mut(&mut value, w)["Worker"]["factory"] = json!(100);
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let result = deserialize_into(&ctx, &mut graph, serde_json::from_value(value).unwrap());
+    assert!(matches!(result, Err(DeserializeError::Dangling { .. })));
+    assert!(graph.is_empty());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_deserialize_into_context() {
+    let (value, f, w, p) = build();
+    let mut graph = Graph::<Node>::new(&Context::new());
+    deserialize_into(&Context::new(), &mut graph, serde_json::from_value(value).unwrap()).unwrap();
+  }
+}
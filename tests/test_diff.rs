@@ -0,0 +1,138 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_diff {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct FuncNode {
+    blocks: BTreeSet<NodeIndex>,
+    name: String,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct BlockNode {
+    func: NodeIndex,
+    len: usize,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct PhiNode {
+    incoming: Vec<NodeIndex>,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Node{
+      Func(FuncNode),
+      Block(BlockNode),
+      Phi(PhiNode),
+    }
+    bidirectional!{
+      Func.blocks <-> Block.func,
+    }
+  }
+
+  fn func(name: &str) -> Node {
+    Node::Func(FuncNode { blocks: BTreeSet::new(), name: name.to_string() })
+  }
+
+  fn block(func: NodeIndex, len: usize) -> Node {
+    Node::Block(BlockNode { func, len })
+  }
+
+  #[test]
+  fn test_same() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f = trans.insert(func("main"));
+    trans.insert(block(f, 1));
+    graph.commit(trans);
+
+    let diff = graph_diff(&graph, &graph.fork());
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "no difference\n");
+  }
+
+  #[test]
+  fn test_diff() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f = trans.insert(func("main"));
+    let a = trans.insert(block(f, 1));
+    let b = trans.insert(block(f, 2));
+    let phi = trans.insert(Node::Phi(PhiNode { incoming: vec![a, a, b] }));
+    graph.commit(trans);
+    let before = graph.fork();
+
+    // A pass splits a, and drops b
+    let mut trans = Transaction::new(&ctx);
+    let c = trans.insert(block(f, 3));
+    trans.remove(b);
+    mut_node!(trans, Node::Block, a, |x| { x.len = 10 });
+    mut_node!(trans, Node::Phi, phi, move |x| { x.incoming = vec![a, c] });
+    graph.commit(trans);
+
+    let diff = graph_diff(&before, &graph);
+    assert_eq!(diff.added, BTreeSet::from([c]));
+    assert_eq!(diff.removed, BTreeSet::from([b]));
+    // f and phi only have link changes
+    assert_eq!(diff.changed, BTreeSet::from([a]));
+    assert_eq!(BTreeSet::from_iter(diff.added_links.keys().copied()), BTreeSet::from(["blocks", "func", "incoming"]));
+    assert_eq!(diff.added_links["blocks"], vec![(f, c)]);
+    assert_eq!(diff.added_links["func"], vec![(c, f)]);
+    assert_eq!(diff.added_links["incoming"], vec![(phi, c)]);
+    assert_eq!(BTreeSet::from_iter(diff.removed_links["blocks"].iter().copied()), BTreeSet::from([(f, b)]));
+    assert_eq!(diff.removed_links["func"], vec![(b, f)]);
+    // One of the duplicated targets is removed
+    let removed = BTreeSet::from_iter(diff.removed_links["incoming"].iter().copied());
+    assert_eq!(removed, BTreeSet::from([(phi, a), (phi, b)]));
+    assert_eq!(diff.removed_links["incoming"].len(), 2);
+
+    let text = diff.to_string();
+    assert!(text.contains(&format!("+ node {}\n", c)));
+    assert!(text.contains(&format!("- node {}\n", b)));
+    assert!(text.contains(&format!("~ node {}\n", a)));
+    assert!(text.contains(&format!("+ link {}.incoming -> {}\n", phi, c)));
+    assert!(text.contains(&format!("- link {}.blocks -> {}\n", f, b)));
+    assert_eq!(text.lines().count(), 10);
+
+    // The reversed diff swaps the sides
+    let reversed = graph_diff(&graph, &before);
+    assert_eq!(reversed.added, diff.removed);
+    assert_eq!(reversed.changed, diff.changed);
+    assert_eq!(reversed.removed_links["func"], diff.added_links["func"]);
+  }
+
+  #[test]
+  fn test_reorder_vec() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f = trans.insert(func("main"));
+    let a = trans.insert(block(f, 1));
+    let b = trans.insert(block(f, 2));
+    let phi = trans.insert(Node::Phi(PhiNode { incoming: vec![a, b] }));
+    graph.commit(trans);
+    let before = graph.fork();
+
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Phi, phi, |x| { x.incoming.reverse() });
+    graph.commit(trans);
+    assert!(graph_diff(&before, &graph).is_empty());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_different_context() {
+    let graph1 = Graph::<Node>::new(&Context::new());
+    let graph2 = Graph::<Node>::new(&Context::new());
+    graph_diff(&graph1, &graph2);
+  }
+}
@@ -0,0 +1,102 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_dot {
+  use std::collections::BTreeSet;
+
+  use ttgraph::{dot::Dot, *};
+
+  #[derive(TypedNode)]
+  struct ParentNode {
+    children: BTreeSet<NodeIndex>,
+    name: String,
+  }
+
+  #[derive(TypedNode)]
+  struct ChildNode {
+    parent: NodeIndex,
+    sibling: NodeIndex,
+  }
+
+  #[derive(TypedNode)]
+  struct OtherNode {
+    data: usize,
+  }
+
+  node_enum! {
+    enum Node{
+      Parent(ParentNode),
+      Child(ChildNode),
+      Other(OtherNode),
+    }
+    group!{
+      family{Parent, Child},
+      young{Child},
+    }
+  }
+
+  fn build() -> (Graph<Node>, NodeIndex, NodeIndex, NodeIndex, NodeIndex) {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let p = trans.alloc_untyped();
+    let c1 = trans.alloc_untyped();
+    let c2 = trans.alloc_untyped();
+    trans.fill_back_untyped(p, Node::Parent(ParentNode { children: BTreeSet::from([c1, c2]), name: "\"root\"".to_string() }));
+    trans.fill_back_untyped(c1, Node::Child(ChildNode { parent: p, sibling: c2 }));
+    trans.fill_back_untyped(c2, Node::Child(ChildNode { parent: p, sibling: NodeIndex::empty() }));
+    let o = trans.insert(Node::Other(OtherNode { data: 1 }));
+    graph.commit(trans);
+    (graph, p, c1, c2, o)
+  }
+
+  #[test]
+  fn test_names() {
+    assert_eq!(Node::group_names(), &["family", "young"]);
+    let (graph, p, c1, c2, o) = build();
+    let names = BTreeSet::from_iter(
+      graph.get(c1).unwrap().iter_sources().map(|(_, s)| Node::link_name(Node::to_link_mirror_enum(s))),
+    );
+    assert_eq!(names, BTreeSet::from(["parent", "sibling"]));
+  }
+
+  #[test]
+  fn test_dot() {
+    let (graph, p, c1, c2, o) = build();
+    let dot = Dot::new(&graph).to_string();
+    assert!(dot.starts_with("digraph \"G\" {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains(&format!("n{} -> n{} [label=\"children\"];", p.0, c1.0)));
+    assert!(dot.contains(&format!("n{} -> n{} [label=\"children\"];", p.0, c2.0)));
+    assert!(dot.contains(&format!("n{} -> n{} [label=\"parent\"];", c1.0, p.0)));
+    assert!(dot.contains(&format!("n{} -> n{} [label=\"sibling\"];", c1.0, c2.0)));
+    // Empty links are not drawn
+    assert_eq!(dot.matches("label=\"sibling\"").count(), 1);
+    assert!(dot.contains(&format!("n{} [label=\"Other {}\", style=filled, fillcolor=palegreen];", o.0, o.0)));
+
+    // Children are in the first group, the second group is empty
+    assert!(dot.contains("subgraph cluster_0 {\n    label=\"family\";\n"));
+    assert!(!dot.contains("cluster_1"));
+    assert!(dot.contains(&format!("    n{} [label=\"Child {}\", style=filled, fillcolor=lightpink];", c1.0, c1.0)));
+    assert!(dot.contains(&format!("\n  n{} [label=\"Other", o.0)));
+  }
+
+  #[test]
+  fn test_options() {
+    let (graph, p, c1, c2, o) = build();
+    let dot = Dot::new(&graph)
+      .name("family tree")
+      .clusters(false)
+      .label(|idx, node| match node {
+        Node::Parent(x) => x.name.clone(),
+        _ => format!("{}\n{}", idx, node.discriminant() as usize),
+      })
+      .to_string();
+    assert!(dot.starts_with("digraph \"family tree\" {\n"));
+    assert!(!dot.contains("subgraph"));
+    assert!(dot.contains("label=\"\\\"root\\\"\""));
+    assert!(dot.contains(&format!("label=\"{}\\n1\"", c1.0)));
+  }
+}
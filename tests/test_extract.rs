@@ -0,0 +1,106 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_extract {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct DirNode {
+    subdirs: BTreeSet<NodeIndex>,
+    parent: NodeIndex,
+    name: String,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq)]
+  struct HistoryNode {
+    visits: Vec<NodeIndex>,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Node{
+      Dir(DirNode),
+      History(HistoryNode),
+    }
+    bidirectional!{
+      Dir.parent <-> Dir.subdirs,
+    }
+  }
+
+  fn dir(parent: NodeIndex, name: &str) -> Node {
+    Node::Dir(DirNode { subdirs: BTreeSet::new(), parent, name: name.to_string() })
+  }
+
+  /// / -> (usr -> (bin, lib), home), and a history visiting [bin, home, bin]
+  fn build() -> (Graph<Node>, [NodeIndex; 6]) {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let root = trans.insert(dir(NodeIndex::empty(), "/"));
+    let usr = trans.insert(dir(root, "usr"));
+    let home = trans.insert(dir(root, "home"));
+    let bin = trans.insert(dir(usr, "bin"));
+    let lib = trans.insert(dir(usr, "lib"));
+    let history = trans.insert(Node::History(HistoryNode { visits: vec![bin, home, bin] }));
+    graph.commit(trans);
+    (graph, [root, usr, home, bin, lib, history])
+  }
+
+  #[test]
+  fn test_subtree() {
+    let (graph, [root, usr, home, bin, lib, history]) = build();
+    let ctx = Context::new();
+    let (mut sub, id_map) = graph.extract(&ctx, [usr], Some(&["subdirs"]));
+    assert_eq!(sub.len(), 3);
+    assert_eq!(BTreeSet::from_iter(id_map.keys().copied()), BTreeSet::from([usr, bin, lib]));
+
+    let new_usr = get_node!(sub, Node::Dir, id_map[&usr]).unwrap();
+    assert_eq!(new_usr.name, "usr");
+    assert!(new_usr.parent.is_empty());
+    assert_eq!(new_usr.subdirs, BTreeSet::from([id_map[&bin], id_map[&lib]]));
+    assert_eq!(get_node!(sub, Node::Dir, id_map[&bin]).unwrap().parent, id_map[&usr]);
+
+    // The new graph works with its own context
+    let mut trans = Transaction::new(&ctx);
+    let share = trans.insert(dir(id_map[&usr], "share"));
+    sub.commit(trans);
+    assert_eq!(get_node!(sub, Node::Dir, id_map[&usr]).unwrap().subdirs.len(), 3);
+    // The original graph is unchanged
+    assert_eq!(get_node!(graph, Node::Dir, usr).unwrap().subdirs, BTreeSet::from([bin, lib]));
+  }
+
+  #[test]
+  fn test_all_links() {
+    let (graph, [root, usr, home, bin, lib, history]) = build();
+    // The parent links lead to the whole tree, but not to the history
+    let (sub, id_map) = graph.extract(&Context::new(), [bin], None);
+    assert_eq!(sub.len(), 5);
+    assert!(!id_map.contains_key(&history));
+
+    let (sub, id_map) = graph.extract(&Context::new(), [history], None);
+    assert_eq!(sub.len(), 6);
+    let visits = &get_node!(sub, Node::History, id_map[&history]).unwrap().visits;
+    assert_eq!(*visits, vec![id_map[&bin], id_map[&home], id_map[&bin]]);
+  }
+
+  #[test]
+  fn test_node_set() {
+    let (graph, [root, usr, home, bin, lib, history]) = build();
+    let (sub, id_map) = graph.extract(&Context::new(), [history, bin, history], Some(&[]));
+    assert_eq!(sub.len(), 2);
+    // The targets out of the subgraph are removed from the vec
+    assert_eq!(get_node!(sub, Node::History, id_map[&history]).unwrap().visits, vec![id_map[&bin], id_map[&bin]]);
+    assert!(get_node!(sub, Node::Dir, id_map[&bin]).unwrap().parent.is_empty());
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_missing_root() {
+    let (graph, _) = build();
+    graph.extract(&Context::new(), [NodeIndex(100)], None);
+  }
+}
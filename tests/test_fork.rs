@@ -0,0 +1,148 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_fork {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct WorkerNode {
+    name: String,
+    factory: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+    index!{
+      Worker.name,
+      Factory.name: unique,
+    }
+  }
+
+  fn names(graph: &Graph<Node>) -> BTreeSet<String> {
+    BTreeSet::from_iter(iter_nodes!(graph, Node::Worker).map(|(_, x)| x.name.clone()))
+  }
+
+  #[test]
+  fn test_fork() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), factory }));
+    graph.commit(trans);
+
+    let mut fork = graph.fork();
+    let mut trans = Transaction::new(&ctx);
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), factory }));
+    mut_node!(trans, Node::Worker, alice, |x| { x.name = "Alice2".to_string() });
+    fork.commit(trans);
+
+    // The original graph is untouched
+    assert_eq!(names(&graph), BTreeSet::from(["Alice".to_string()]));
+    assert_eq!(get_node!(graph, Node::Factory, factory).unwrap().workers, BTreeSet::from([alice]));
+    assert!(graph.get(bob).is_none());
+    assert_eq!(Vec::from_iter(graph.lookup("name", &"Alice".to_string())), vec![alice]);
+    assert_eq!(graph.lookup("name", &"Bob".to_string()).next(), None);
+
+    assert_eq!(names(&fork), BTreeSet::from(["Alice2".to_string(), "Bob".to_string()]));
+    assert_eq!(get_node!(fork, Node::Factory, factory).unwrap().workers, BTreeSet::from([alice, bob]));
+    assert_eq!(Vec::from_iter(fork.lookup("name", &"Bob".to_string())), vec![bob]);
+    assert_eq!(fork.lookup("name", &"Alice".to_string()).next(), None);
+
+    // Modify the original graph after forking
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(alice);
+    graph.commit(trans);
+    assert!(graph.get(alice).is_none());
+    assert!(get_node!(graph, Node::Factory, factory).unwrap().workers.is_empty());
+    assert!(fork.get(alice).is_some());
+    *get_data_mut!(graph, Node::Factory, factory).unwrap().name = "G".to_string();
+    assert_eq!(get_node!(fork, Node::Factory, factory).unwrap().name, "F");
+
+    // A failed speculation is simply dropped
+    drop(fork);
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Worker(WorkerNode { name: "Carol".to_string(), factory }));
+    graph.commit(trans);
+    assert_eq!(names(&graph), BTreeSet::from(["Carol".to_string()]));
+  }
+
+  #[test]
+  fn test_clone_into_iter() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), factory }));
+    graph.commit(trans);
+
+    let cloned = graph.clone();
+    let nodes = Vec::from_iter(cloned.into_iter().map(|(x, _)| x));
+    assert_eq!(nodes, vec![factory, alice]);
+    assert_eq!(graph.len(), 2);
+  }
+
+  #[test]
+  fn test_fork_large() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let workers = Vec::from_iter(
+      (0..3000).map(|i| trans.insert(Node::Worker(WorkerNode { name: format!("W{i}"), factory }))),
+    );
+    graph.commit(trans);
+
+    let mut fork = graph.fork();
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(workers[0]);
+    mut_node!(trans, Node::Worker, workers[1500], |x| { x.name = "X".to_string() });
+    let new_worker = trans.insert(Node::Worker(WorkerNode { name: "New".to_string(), factory }));
+    fork.commit(trans);
+
+    assert_eq!(graph.len(), 3001);
+    assert_eq!(get_node!(graph, Node::Worker, workers[1500]).unwrap().name, "W1500");
+    assert_eq!(get_node!(graph, Node::Factory, factory).unwrap().workers.len(), 3000);
+    assert_eq!(fork.len(), 3001);
+    assert_eq!(get_node!(fork, Node::Worker, workers[1500]).unwrap().name, "X");
+    assert!(fork.get(workers[0]).is_none());
+    assert!(get_node!(fork, Node::Factory, factory).unwrap().workers.contains(&new_worker));
+    // Nodes are iterated in the order of NodeIndex
+    assert_eq!(Vec::from_iter(fork.iter().map(|(x, _)| x).skip(1).take(2)), vec![workers[1], workers[2]]);
+    assert_eq!(fork.iter().last().unwrap().0, new_worker);
+    graph.check_backlinks();
+    fork.check_backlinks();
+  }
+
+  #[test]
+  #[should_panic(expected = "Unique index check failed")]
+  fn test_fork_unique() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    let mut fork = graph.fork();
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    fork.commit(trans);
+  }
+}
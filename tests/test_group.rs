@@ -97,6 +97,7 @@ mod test_group {
       third{A, D},
       one{B},
       all{A, B, C, D},
+      reversed{D, A},
     }
   }
 
@@ -118,6 +119,21 @@ mod test_group {
     assert_eq!(Vec::from_iter(graph.iter_group("third").map(|(x, _)| x)), vec![a, d]);
     assert_eq!(Vec::from_iter(graph.iter_group("one").map(|(x, _)| x)), vec![b]);
     assert_eq!(Vec::from_iter(graph.iter_group("all").map(|(x, _)| x)), vec![a, b, c, d]);
+    assert_eq!(Vec::from_iter(graph.iter_group("reversed").map(|(x, _)| x)), vec![a, d]);
+    assert_eq!(graph.iter_group("unknown").count(), 0);
+
+    assert_eq!(graph.len_group("first"), 2);
+    assert_eq!(graph.len_group("one"), 1);
+    assert_eq!(graph.len_group("all"), 4);
+    assert_eq!(graph.len_group("unknown"), 0);
+
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(a);
+    trans.insert(MultiNodes::D(NodeD { d: 5 }));
+    graph.commit(trans);
+    assert_eq!(graph.len_group("first"), 1);
+    assert_eq!(graph.len_group("second"), 3);
+    assert_eq!(graph.len_group("third"), 2);
   }
 
   #[derive(TypedNode)]
@@ -0,0 +1,122 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_index {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug)]
+  struct WorkerNode {
+    name: String,
+    age: usize,
+    factory: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+    index!{
+      Worker.name,
+      Worker.age,
+      Factory.name: unique,
+    }
+  }
+
+  fn lookup_name(graph: &Graph<Node>, name: &str) -> BTreeSet<NodeIndex> {
+    BTreeSet::from_iter(graph.lookup("name", &name.to_string()))
+  }
+
+  #[test]
+  fn test_index_maintain() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), age: 30, factory }));
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), age: 30, factory }));
+    graph.commit(trans);
+
+    assert_eq!(lookup_name(&graph, "F"), BTreeSet::from([factory]));
+    assert_eq!(lookup_name(&graph, "Alice"), BTreeSet::from([alice]));
+    assert_eq!(BTreeSet::from_iter(graph.lookup("age", &30usize)), BTreeSet::from([alice, bob]));
+    assert_eq!(graph.lookup("unknown", &30usize).next(), None);
+
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Worker, alice, |x| {
+      x.name = "Alice2".to_string();
+      x.age = 31;
+    });
+    update_node!(trans, Node::Worker, bob, |x| { WorkerNode { name: "Alice".to_string(), ..x } });
+    graph.commit(trans);
+
+    assert_eq!(lookup_name(&graph, "Alice2"), BTreeSet::from([alice]));
+    assert_eq!(lookup_name(&graph, "Alice"), BTreeSet::from([bob]));
+    assert_eq!(lookup_name(&graph, "Bob"), BTreeSet::new());
+    assert_eq!(BTreeSet::from_iter(graph.lookup("age", &30usize)), BTreeSet::from([bob]));
+    assert_eq!(BTreeSet::from_iter(graph.lookup("age", &31usize)), BTreeSet::from([alice]));
+
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(alice);
+    graph.commit(trans);
+    assert_eq!(lookup_name(&graph, "Alice2"), BTreeSet::new());
+    assert_eq!(graph.lookup("age", &31usize).next(), None);
+  }
+
+  #[test]
+  fn test_unique_swap() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f1 = trans.insert(Node::Factory(FactoryNode { name: "F1".to_string(), workers: BTreeSet::new() }));
+    let f2 = trans.insert(Node::Factory(FactoryNode { name: "F2".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    // Swapping the names only breaks the unique index in the middle of the commit
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Factory, f1, |x| { x.name = "F2".to_string() });
+    mut_node!(trans, Node::Factory, f2, |x| { x.name = "F1".to_string() });
+    graph.commit(trans);
+    assert_eq!(lookup_name(&graph, "F1"), BTreeSet::from([f2]));
+    assert_eq!(lookup_name(&graph, "F2"), BTreeSet::from([f1]));
+  }
+
+  #[test]
+  #[should_panic(expected = "Unique index check failed")]
+  fn test_unique_violation() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+  }
+
+  #[test]
+  #[should_panic(expected = "Index name is looked up by the key type &str")]
+  fn test_lookup_key_type() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+    graph.lookup("name", &"F").next();
+  }
+}
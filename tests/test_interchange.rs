@@ -0,0 +1,198 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_interchange {
+  use serde::{Deserialize, Serialize};
+  use serde_json::json;
+  use std::collections::BTreeSet;
+
+  use ttgraph::{interchange::*, *};
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct WorkerNode {
+    factory: NodeIndex,
+    produced: Vec<NodeIndex>,
+    salary: usize,
+    tags: Vec<String>,
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct ProductNode {
+    serial: usize,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+      Product(ProductNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+  }
+
+  /// A data field named like a node attribute, and data fields of all GraphML types
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct TicketNode {
+    id: String,
+    count: usize,
+    price: f64,
+    paid: bool,
+    note: Option<String>,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Ticket{
+      Ticket(TicketNode),
+    }
+  }
+
+  fn build() -> (Graph<Node>, NodeIndex, NodeIndex, NodeIndex, NodeIndex) {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f = trans.insert(Node::Factory(FactoryNode { name: "<Factory & Co>".to_string(), workers: BTreeSet::new() }));
+    let p1 = trans.insert(Node::Product(ProductNode { serial: 1 }));
+    let p2 = trans.insert(Node::Product(ProductNode { serial: 2 }));
+    let w = trans.insert(Node::Worker(WorkerNode {
+      factory: f,
+      produced: vec![p2, p1, p2],
+      salary: 100,
+      tags: vec!["night".to_string()],
+    }));
+    graph.commit(trans);
+    (graph, f, w, p1, p2)
+  }
+
+  fn assert_same(a: &Graph<Node>, b: &Graph<Node>) {
+    assert_eq!(a.len(), b.len());
+    for (i, x) in a.iter() {
+      assert_eq!(b.get(i), Some(x));
+    }
+  }
+
+  #[test]
+  fn test_node_link() {
+    let (graph, f, w, p1, p2) = build();
+    let json = to_node_link(&graph);
+    assert_eq!(json["directed"], json!(true));
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 4);
+    assert!(json["nodes"].as_array().unwrap().contains(&json!({
+      "id": w, "type": "Worker", "salary": 100, "tags": ["night"]
+    })));
+    let links = json["links"].as_array().unwrap();
+    assert_eq!(links.len(), 5);
+    assert!(links.contains(&json!({"source": f, "target": w, "link": "workers"})));
+    assert!(links.contains(&json!({"source": w, "target": f, "link": "factory"})));
+
+    let (ctx, mut graph2) = from_node_link::<Node>(&json).unwrap();
+    assert_same(&graph, &graph2);
+    assert_eq!(get_node!(graph2, Node::Worker, w).unwrap().produced, vec![p2, p1, p2]);
+
+    // The imported graph has a working context
+    let mut trans = Transaction::new(&ctx);
+    let p3 = trans.insert(Node::Product(ProductNode { serial: 3 }));
+    graph2.commit(trans);
+    assert_eq!(graph2.len(), 5);
+  }
+
+  #[test]
+  fn test_node_link_errors() {
+    let (graph, f, w, p1, p2) = build();
+    let json = to_node_link(&graph);
+
+    let mut unknown_type = json.clone();
+    unknown_type["nodes"][0]["type"] = json!("Shop");
+    assert!(matches!(from_node_link::<Node>(&unknown_type), Err(ImportError::Format(_))));
+
+    let mut unknown_link = json.clone();
+    unknown_link["links"][0]["link"] = json!("owner");
+    assert!(matches!(from_node_link::<Node>(&unknown_link), Err(ImportError::Format(_))));
+
+    let mut dangling = json.clone();
+    dangling["links"][0]["target"] = json!(1000);
+    assert!(matches!(from_node_link::<Node>(&dangling), Err(ImportError::Format(_))));
+
+    let mut missing_data = json.clone();
+    missing_data["nodes"].as_array_mut().unwrap().retain(|x| x["type"] != json!("Factory"));
+    missing_data["links"].as_array_mut().unwrap().clear();
+    missing_data["nodes"].as_array_mut().unwrap().push(json!({"id": f, "type": "Factory"}));
+    assert!(matches!(from_node_link::<Node>(&missing_data), Err(ImportError::Node(x, _)) if x == f));
+  }
+
+  #[cfg(feature = "graphml")]
+  #[test]
+  fn test_graphml() {
+    let (graph, f, w, p1, p2) = build();
+    let xml = to_graphml(&graph);
+    assert!(xml.contains("attr.name=\"salary\" attr.type=\"long\"/>"));
+    assert!(xml.contains("attr.name=\"tags\" attr.type=\"string\"><desc>json</desc></key>"));
+    assert!(xml.contains("[&quot;night&quot;]"));
+    // Strings are written as they are
+    assert!(xml.contains(">&lt;Factory &amp; Co&gt;</data>"));
+    assert!(xml.contains(&format!("source=\"n{}\" target=\"n{}\"", f.0, w.0)));
+
+    let (ctx, graph2) = from_graphml::<Node>(&xml).unwrap();
+    assert_same(&graph, &graph2);
+    assert_eq!(get_node!(graph2, Node::Worker, w).unwrap().produced, vec![p2, p1, p2]);
+
+    assert!(matches!(from_graphml::<Node>("<graphml>"), Err(ImportError::Syntax(_))));
+  }
+
+  #[test]
+  fn test_reserved_names() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Ticket>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let ticket = TicketNode { id: "T-1".to_string(), count: 2, price: 1.5, paid: true, note: None };
+    let t = trans.insert(Ticket::Ticket(ticket.clone()));
+    graph.commit(trans);
+
+    let json = to_node_link(&graph);
+    assert_eq!(
+      json["nodes"][0],
+      json!({"id": t, "type": "Ticket", "data.id": "T-1", "count": 2, "price": 1.5, "paid": true, "note": null})
+    );
+    let (_, graph2) = from_node_link::<Ticket>(&json).unwrap();
+    assert_eq!(get_node!(graph2, Ticket::Ticket, t), Some(&ticket));
+
+    #[cfg(feature = "graphml")]
+    {
+      let xml = to_graphml(&graph);
+      assert!(xml.contains("attr.name=\"data.id\" attr.type=\"string\"/>"));
+      assert!(xml.contains("attr.name=\"count\" attr.type=\"long\"/>"));
+      assert!(xml.contains("attr.name=\"price\" attr.type=\"double\"/>"));
+      assert!(xml.contains("attr.name=\"paid\" attr.type=\"boolean\"/>"));
+      let (_, graph2) = from_graphml::<Ticket>(&xml).unwrap();
+      assert_eq!(get_node!(graph2, Ticket::Ticket, t), Some(&ticket));
+    }
+  }
+
+  #[cfg(feature = "graphml")]
+  #[test]
+  fn test_graphml_raw_string() {
+    // Written by another tool, with a raw string and numeric ids
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+      <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+        <key id="t" for="node" attr.name="type"/>
+        <key id="n" for="node" attr.name="name"/>
+        <key id="l" for="edge" attr.name="link"/>
+        <graph edgedefault="directed">
+          <node id="1"><data key="t">Factory</data><data key="n">Acme</data></node>
+        </graph>
+      </graphml>"#;
+    let (ctx, graph) = from_graphml::<Node>(xml).unwrap();
+    assert_eq!(get_node!(graph, Node::Factory, NodeIndex(1)).unwrap().name, "Acme");
+  }
+}
@@ -0,0 +1,195 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_journal {
+  use serde::{Deserialize, Serialize};
+  use std::collections::BTreeSet;
+  use std::path::PathBuf;
+
+  use ttgraph::{journal::Journal, *};
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct TreeNode {
+    children: BTreeSet<NodeIndex>,
+    father: NodeIndex,
+    data: usize,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Node{
+      Tree(TreeNode),
+    }
+    bidirectional!{
+      Tree.father <-> Tree.children,
+    }
+  }
+
+  fn tree(father: NodeIndex, data: usize) -> Node {
+    Node::Tree(TreeNode { children: BTreeSet::new(), father, data })
+  }
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ttgraph_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+  }
+
+  fn assert_same(a: &Graph<Node>, b: &Graph<Node>) {
+    assert_eq!(a.len(), b.len());
+    for (i, x) in a.iter() {
+      assert_eq!(b.get(i), Some(x));
+    }
+  }
+
+  /// Build a small tree, then change it with redirection, mutation and removal
+  fn build(journal: &mut Journal<Node>, ctx: &Context, graph: &mut Graph<Node>) -> (NodeIndex, NodeIndex, NodeIndex) {
+    let mut trans = Transaction::new(ctx);
+    let root = trans.insert(tree(NodeIndex::empty(), 0));
+    let a = trans.insert(tree(root, 1));
+    let b = trans.insert(tree(root, 2));
+    journal.commit(graph, trans).unwrap();
+
+    let mut trans = Transaction::new(ctx);
+    let c = trans.insert(tree(a, 3));
+    mut_node!(trans, Node::Tree, b, |x| { x.data = 20 });
+    journal.commit(graph, trans).unwrap();
+
+    let mut trans = Transaction::new(ctx);
+    trans.redirect_links(a, b);
+    trans.remove(a);
+    journal.commit(graph, trans).unwrap();
+    (root, b, c)
+  }
+
+  #[test]
+  fn test_recover() {
+    let dir = temp_dir("journal_recover");
+    let (mut journal, ctx, mut graph) = Journal::<Node>::open(&dir).unwrap();
+    let (root, b, c) = build(&mut journal, &ctx, &mut graph);
+    assert_eq!(journal.unsnapshotted(), 3);
+    assert_eq!(get_node!(graph, Node::Tree, c).unwrap().father, b);
+    drop(journal);
+
+    let (mut journal, ctx2, mut graph2) = Journal::<Node>::open(&dir).unwrap();
+    assert_same(&graph, &graph2);
+    assert_eq!(journal.unsnapshotted(), 3);
+
+    // Continue to work on the recovered graph, new indexes do not collide
+    let mut trans = Transaction::new(&ctx2);
+    let d = trans.insert(tree(c, 4));
+    assert!(graph2.get(d).is_none());
+    journal.commit(&mut graph2, trans).unwrap();
+    drop(journal);
+
+    let (journal, ctx3, graph3) = Journal::<Node>::open(&dir).unwrap();
+    assert_same(&graph2, &graph3);
+    assert_eq!(get_node!(graph3, Node::Tree, c).unwrap().children, BTreeSet::from([d]));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_snapshot() {
+    let dir = temp_dir("journal_snapshot");
+    let (mut journal, ctx, mut graph) = Journal::<Node>::open(&dir).unwrap();
+    journal.set_snapshot_interval(2);
+    build(&mut journal, &ctx, &mut graph);
+    assert_eq!(journal.unsnapshotted(), 1);
+
+    journal.snapshot(&graph).unwrap();
+    assert_eq!(journal.unsnapshotted(), 0);
+    assert_eq!(std::fs::metadata(dir.join("journal.jsonl")).unwrap().len(), 0);
+    drop(journal);
+
+    let (journal, ctx2, graph2) = Journal::<Node>::open(&dir).unwrap();
+    assert_same(&graph, &graph2);
+    assert_eq!(journal.unsnapshotted(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_records_before_snapshot_are_skipped() {
+    let dir = temp_dir("journal_skip");
+    let (mut journal, ctx, mut graph) = Journal::<Node>::open(&dir).unwrap();
+    build(&mut journal, &ctx, &mut graph);
+    let records = std::fs::read(dir.join("journal.jsonl")).unwrap();
+    journal.snapshot(&graph).unwrap();
+    drop(journal);
+
+    // Crashed after the snapshot is written, but before the journal is cleared
+    std::fs::write(dir.join("journal.jsonl"), records).unwrap();
+    let (journal, ctx2, graph2) = Journal::<Node>::open(&dir).unwrap();
+    assert_same(&graph, &graph2);
+    assert_eq!(journal.unsnapshotted(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_truncated_record() {
+    let dir = temp_dir("journal_truncated");
+    let (mut journal, ctx, mut graph) = Journal::<Node>::open(&dir).unwrap();
+    build(&mut journal, &ctx, &mut graph);
+    let len = std::fs::metadata(dir.join("journal.jsonl")).unwrap().len();
+
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(tree(NodeIndex::empty(), 5));
+    journal.commit(&mut graph.clone(), trans).unwrap();
+    drop(journal);
+
+    // Crashed in the middle of writing the last record
+    let full = std::fs::read(dir.join("journal.jsonl")).unwrap();
+    let cut = (len as usize + full.len()) / 2;
+    std::fs::write(dir.join("journal.jsonl"), &full[..cut]).unwrap();
+
+    let (mut journal, ctx2, mut graph2) = Journal::<Node>::open(&dir).unwrap();
+    assert_same(&graph, &graph2);
+    assert_eq!(std::fs::metadata(dir.join("journal.jsonl")).unwrap().len(), len);
+
+    // New records are appended after the valid ones
+    let mut trans = Transaction::new(&ctx2);
+    let x = trans.insert(tree(NodeIndex::empty(), 6));
+    journal.commit(&mut graph2, trans).unwrap();
+    drop(journal);
+    let (journal, ctx3, graph3) = Journal::<Node>::open(&dir).unwrap();
+    assert_same(&graph2, &graph3);
+    assert_eq!(get_node!(graph3, Node::Tree, x).unwrap().data, 6);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_broken_record() {
+    let dir = temp_dir("journal_broken");
+    let (mut journal, ctx, mut graph) = Journal::<Node>::open(&dir).unwrap();
+    build(&mut journal, &ctx, &mut graph);
+    drop(journal);
+
+    let mut content = b"not a record\n".to_vec();
+    content.extend(std::fs::read(dir.join("journal.jsonl")).unwrap());
+    std::fs::write(dir.join("journal.jsonl"), content).unwrap();
+    let err = Journal::<Node>::open(&dir).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_invalid_snapshot() {
+    let dir = temp_dir("journal_invalid_snapshot");
+    let (mut journal, ctx, mut graph) = Journal::<Node>::open(&dir).unwrap();
+    build(&mut journal, &ctx, &mut graph);
+    journal.snapshot(&graph).unwrap();
+    drop(journal);
+
+    // Point a link of the snapshot to a missing node
+    let path = dir.join("snapshot.json");
+    let mut snapshot: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    snapshot["graph"]["nodes"][0][1]["Tree"]["father"] = serde_json::json!(100);
+    std::fs::write(&path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+    let err = Journal::<Node>::open(&dir).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let inner = err.into_inner().unwrap().downcast::<serialize::DeserializeError>().unwrap();
+    assert!(matches!(*inner, serialize::DeserializeError::Dangling { link: "father", .. }));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,227 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_migrate {
+  use serde::{Deserialize, Serialize};
+  use serde_json::json;
+  use std::collections::BTreeSet;
+
+  use ttgraph::{serialize::*, *};
+
+  mod v1 {
+    use serde::{Deserialize, Serialize};
+    use ttgraph::*;
+
+    #[derive(TypedNode, Serialize, Deserialize)]
+    pub struct ItemNode {
+      pub next: NodeIndex,
+      pub data: usize,
+    }
+
+    node_enum! {
+      #[derive(Serialize, Deserialize)]
+      pub enum Node{
+        Item(ItemNode),
+      }
+    }
+  }
+
+  mod v2 {
+    use serde::{Deserialize, Serialize};
+    use ttgraph::*;
+
+    #[derive(TypedNode, Serialize, Deserialize)]
+    pub struct ItemNode {
+      pub next: NodeIndex,
+      pub value: usize,
+    }
+
+    node_enum! {
+      #[derive(Serialize, Deserialize)]
+      pub enum Node{
+        Item(ItemNode),
+      }
+    }
+  }
+
+  // Same as v2, but with other type names and paths
+  mod v2_renamed {
+    use serde::{Deserialize, Serialize};
+    use ttgraph::*;
+
+    #[derive(TypedNode, Serialize, Deserialize)]
+    pub struct RenamedNode {
+      pub next: NodeIndex,
+      pub value: std::primitive::usize,
+    }
+
+    node_enum! {
+      #[derive(Serialize, Deserialize)]
+      pub enum Node{
+        Item(RenamedNode),
+      }
+    }
+  }
+
+  // Same as v2, but the value is a string
+  mod v2_retyped {
+    use serde::{Deserialize, Serialize};
+    use ttgraph::*;
+
+    #[derive(TypedNode, Serialize, Deserialize)]
+    pub struct ItemNode {
+      pub next: NodeIndex,
+      pub value: String,
+    }
+
+    node_enum! {
+      #[derive(Serialize, Deserialize)]
+      pub enum Node{
+        Item(ItemNode),
+      }
+    }
+  }
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct EntryNode {
+    next: NodeIndex,
+    prev: BTreeSet<NodeIndex>,
+    value: usize,
+    label: String,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Node{
+      Entry(EntryNode),
+    }
+    bidirectional!{
+      Entry.next <-> Entry.prev,
+    }
+  }
+
+  fn write_v1() -> (String, NodeIndex, NodeIndex) {
+    let ctx = Context::new();
+    let mut graph = Graph::<v1::Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(v1::Node::Item(v1::ItemNode { next: NodeIndex::empty(), data: 1 }));
+    let b = trans.insert(v1::Node::Item(v1::ItemNode { next: a, data: 2 }));
+    graph.commit(trans);
+    (serde_json::to_string(&graph).unwrap(), a, b)
+  }
+
+  fn v1_to_v2(node: &mut DynNode) {
+    let data = node.fields.remove("data").unwrap();
+    node.fields.insert("value".to_string(), data);
+  }
+
+  fn v2_to_v3(node: &mut DynNode) {
+    assert_eq!(node.variant, "Item");
+    node.variant = "Entry".to_string();
+    node.fields.insert("prev".to_string(), json!([]));
+    node.fields.insert("label".to_string(), json!("old"));
+  }
+
+  #[test]
+  fn test_hash() {
+    assert_eq!(Node::schema().hash(), Node::schema().hash());
+    assert_ne!(v1::Node::schema().hash(), v2::Node::schema().hash());
+    assert_ne!(v2::Node::schema().hash(), Node::schema().hash());
+    assert_eq!(v2_renamed::Node::schema().hash(), v2::Node::schema().hash());
+    assert_ne!(v2_retyped::Node::schema().hash(), v2::Node::schema().hash());
+
+    let (text, a, b) = write_v1();
+    let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(value["schema"], json!(v1::Node::schema().hash()));
+
+    let serializer = GraphSerializer::from(Graph::<Node>::new(&Context::new()));
+    let value = serde_json::to_value(&serializer).unwrap();
+    assert_eq!(value["schema"], json!(Node::schema().hash()));
+  }
+
+  #[test]
+  fn test_chain() {
+    let (text, a, b) = write_v1();
+    let migrator = Migrator::<Node>::new()
+      .step(v2::Node::schema().hash(), Node::schema().hash(), v2_to_v3)
+      .step(v1::Node::schema().hash(), v2::Node::schema().hash(), v1_to_v2);
+    let (ctx, mut graph) = migrator.deserialize_graph(serde_json::from_str(&text).unwrap()).unwrap();
+    assert_eq!(
+      graph.get(b),
+      Some(&Node::Entry(EntryNode { next: a, prev: BTreeSet::new(), value: 2, label: "old".to_string() }))
+    );
+    // The bidirectional links are rebuilt
+    assert_eq!(get_node!(graph, Node::Entry, a).unwrap().prev, BTreeSet::from([b]));
+
+    let mut trans = Transaction::new(&ctx);
+    let c = trans.insert(Node::Entry(EntryNode { next: b, prev: BTreeSet::new(), value: 3, label: String::new() }));
+    graph.commit(trans);
+    assert!(c != a && c != b);
+
+    // A file of the current schema needs no step
+    let text = serde_json::to_string(&graph).unwrap();
+    let (ctx2, graph2) = migrator.deserialize_graph(serde_json::from_str(&text).unwrap()).unwrap();
+    assert_eq!(graph2.len(), 3);
+    let (ctx3, graph3) = Migrator::<Node>::new().deserialize_graph(serde_json::from_str(&text).unwrap()).unwrap();
+    assert_eq!(graph3.get(c), graph.get(c));
+  }
+
+  #[test]
+  fn test_legacy_file() {
+    let (text, a, b) = write_v1();
+    let mut value: serde_json::Value = serde_json::from_str(&text).unwrap();
+    value.as_object_mut().unwrap().remove("schema");
+    let text = value.to_string();
+
+    // Still readable by deserialize_graph
+    let (ctx, graph) = deserialize_graph(serde_json::from_str::<GraphSerializer<v1::Node>>(&text).unwrap());
+    assert_eq!(graph.len(), 2);
+
+    let migrator = Migrator::<v2::Node>::new().step(0, v2::Node::schema().hash(), v1_to_v2);
+    let (ctx, graph) = migrator.deserialize_graph(serde_json::from_str(&text).unwrap()).unwrap();
+    assert_eq!(get_node!(graph, v2::Node::Item, b).unwrap().value, 2);
+  }
+
+  #[test]
+  fn test_errors() {
+    let (text, a, b) = write_v1();
+    let input = || serde_json::from_str::<DynGraph>(&text).unwrap();
+
+    let result = Migrator::<Node>::new().deserialize_graph(input());
+    assert!(matches!(result, Err(MigrateError::UnknownSchema(x)) if x == v1::Node::schema().hash()));
+
+    // The chain does not reach the current schema
+    let migrator = Migrator::<Node>::new().step(v1::Node::schema().hash(), v2::Node::schema().hash(), v1_to_v2);
+    assert!(matches!(migrator.deserialize_graph(input()), Err(MigrateError::UnknownSchema(_))));
+
+    // A loop of steps
+    let migrator = Migrator::<Node>::new()
+      .step(v1::Node::schema().hash(), v2::Node::schema().hash(), v1_to_v2)
+      .step(v2::Node::schema().hash(), v1::Node::schema().hash(), |_| {});
+    assert!(matches!(migrator.deserialize_graph(input()), Err(MigrateError::UnknownSchema(_))));
+
+    // The step forgets the label
+    let migrator = Migrator::<Node>::new().step(v1::Node::schema().hash(), Node::schema().hash(), |node| {
+      v1_to_v2(node);
+      node.variant = "Entry".to_string();
+      node.fields.insert("prev".to_string(), json!([]));
+    });
+    assert!(matches!(migrator.deserialize_graph(input()), Err(MigrateError::Node(_, _))));
+
+    // The step makes a dangling link
+    let migrator = Migrator::<v2::Node>::new().step(v1::Node::schema().hash(), v2::Node::schema().hash(), |node| {
+      v1_to_v2(node);
+      node.fields.insert("next".to_string(), json!(100));
+    });
+    let result = migrator.deserialize_graph(input());
+    assert!(matches!(result, Err(MigrateError::Invalid(DeserializeError::Dangling { .. }))));
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_duplicated_step() {
+    Migrator::<Node>::new().step(1, 2, |_| {}).step(1, 3, |_| {});
+  }
+}
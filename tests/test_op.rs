@@ -0,0 +1,156 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_op {
+  use serde::{Deserialize, Serialize};
+  use std::collections::BTreeSet;
+
+  use ttgraph::{serialize::*, *};
+
+  #[derive(TypedNode, Debug, Clone, PartialEq, Serialize, Deserialize)]
+  struct NodeA {
+    data: usize,
+    next: NodeIndex,
+    to: BTreeSet<NodeIndex>,
+    list: Vec<NodeIndex>,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Node{
+      A(NodeA),
+    }
+  }
+
+  fn node(data: usize) -> Node {
+    Node::A(NodeA { data, next: NodeIndex::empty(), to: BTreeSet::new(), list: Vec::new() })
+  }
+
+  #[test]
+  fn test_set_link() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1));
+    let b = trans.insert(node(2));
+    let c = trans.insert(node(3));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    trans.apply_ops([
+      Op::SetLink { node: a, link: "next".to_string(), targets: vec![b] },
+      Op::SetLink { node: a, link: "to".to_string(), targets: vec![b, c] },
+      Op::SetLink { node: a, link: "list".to_string(), targets: vec![c, b, c] },
+      Op::SetLink { node: b, link: "next".to_string(), targets: vec![] },
+    ]);
+    graph.commit(trans);
+
+    let x = get_node!(graph, Node::A, a).unwrap();
+    assert_eq!(x.next, b);
+    assert_eq!(x.to, BTreeSet::from([b, c]));
+    assert_eq!(x.list, vec![c, b, c]);
+    assert!(get_node!(graph, Node::A, b).unwrap().next.is_empty());
+  }
+
+  #[test]
+  #[should_panic(expected = "Insert an existing index")]
+  fn test_insert_existing() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1));
+    let b = trans.insert(Node::A(NodeA { data: 2, next: a, to: BTreeSet::new(), list: Vec::new() }));
+    graph.commit(trans);
+
+    // The index comes from another process, it must not overwrite the node in the graph
+    let mut trans = Transaction::new(&ctx);
+    trans.apply_op(Op::Insert { node: b, value: node(3) });
+    graph.commit(trans);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_set_unknown_link() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    trans.apply_op(Op::SetLink { node: a, link: "data".to_string(), targets: vec![a] });
+    graph.commit(trans);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_set_multiple_targets_to_direct_link() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    trans.apply_op(Op::SetLink { node: a, link: "next".to_string(), targets: vec![a, a] });
+    graph.commit(trans);
+  }
+
+  #[test]
+  fn test_replay() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(node(1));
+    let b = trans.insert(node(2));
+    let c = trans.insert(node(3));
+    graph.commit(trans);
+
+    // Another process starts from a copy of the graph
+    let serialized = serde_json::to_string(&graph).unwrap();
+    let deserialized: GraphSerializer<Node> = serde_json::from_str(&serialized).unwrap();
+    let (ctx2, mut graph2) = deserialize_graph(deserialized);
+
+    // Record the edits as operations
+    let mut trans = Transaction::new(&ctx);
+    let d = trans.alloc_untyped();
+    let ops = vec![
+      Op::Insert { node: d, value: Node::A(NodeA { data: 4, next: a, to: BTreeSet::from([b]), list: vec![c] }) },
+      Op::Replace { node: a, value: node(10) },
+      Op::Redirect { old: c, new: b },
+      Op::Remove { node: c },
+    ];
+    trans.apply_ops(ops.clone());
+    graph.commit(trans);
+
+    // Ship and replay the operations
+    let shipped = serde_json::to_string(&ops).unwrap();
+    let received: Vec<Op<Node>> = serde_json::from_str(&shipped).unwrap();
+    assert_eq!(received, ops);
+    let mut trans = Transaction::new(&ctx2);
+    trans.apply_ops(received);
+    graph2.commit(trans);
+
+    assert_eq!(graph2.len(), 3);
+    assert_eq!(Vec::from_iter(graph.iter()), Vec::from_iter(graph2.iter()));
+    assert_eq!(get_node!(graph2, Node::A, d).unwrap().list, vec![b]);
+
+    // New indexes of the replayed context do not collide with the inserted node
+    let mut trans = Transaction::new(&ctx2);
+    let e = trans.insert(node(5));
+    assert!(e != d);
+    graph2.commit(trans);
+    assert_eq!(graph2.len(), 4);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_insert_twice() {
+    let ctx = Context::new();
+    let mut trans = Transaction::<Node>::new(&ctx);
+    let a = trans.insert(node(1));
+    trans.apply_op(Op::Insert { node: a, value: node(2) });
+  }
+}
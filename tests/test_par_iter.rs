@@ -0,0 +1,59 @@
+#![cfg(feature = "rayon")]
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_par_iter {
+  use std::collections::BTreeSet;
+
+  use ttgraph::rayon::prelude::*;
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug)]
+  struct NodeA {
+    a: usize,
+  }
+  #[derive(TypedNode, Debug)]
+  struct NodeB {
+    b: usize,
+  }
+  #[derive(TypedNode, Debug)]
+  struct NodeC {
+    c: usize,
+  }
+
+  node_enum! {
+    #[derive(Debug)]
+    enum Node{
+      A(NodeA),
+      B(NodeB),
+      C(NodeC),
+    }
+    group!{
+      ac{A, C},
+    }
+  }
+
+  #[test]
+  fn test_par_iter() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    trans.extend((0..100).map(|a| Node::A(NodeA { a })));
+    trans.extend((0..200).map(|b| Node::B(NodeB { b })));
+    trans.extend((0..300).map(|c| Node::C(NodeC { c })));
+    graph.commit(trans);
+
+    let all: BTreeSet<NodeIndex> = graph.par_iter().map(|(x, _)| x).collect();
+    assert_eq!(all, BTreeSet::from_iter(graph.iter().map(|(x, _)| x)));
+
+    let b: Vec<NodeIndex> = graph.par_iter_type(discriminant!(Node::B)).map(|(x, _)| x).collect();
+    assert_eq!(b, Vec::from_iter(graph.iter_type(discriminant!(Node::B)).map(|(x, _)| x)));
+
+    let ac: BTreeSet<NodeIndex> = graph.par_iter_group("ac").map(|(x, _)| x).collect();
+    assert_eq!(ac, BTreeSet::from_iter(graph.iter_group("ac").map(|(x, _)| x)));
+    assert_eq!(ac.len(), 400);
+    assert_eq!(graph.par_iter_group("unknown").count(), 0);
+  }
+}
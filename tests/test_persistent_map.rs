@@ -0,0 +1,79 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_persistent_map {
+  use std::collections::BTreeMap;
+
+  use ttgraph::persistent_map::*;
+
+  #[test]
+  fn test_insert_remove() {
+    let mut map = PersistentMap::<usize, String>::new();
+    let mut expect = BTreeMap::new();
+    // Sparse keys grow the trie by several levels
+    for k in [5, 0, 31, 32, 1000, 3, 1 << 20, 77777, usize::MAX] {
+      assert_eq!(map.insert(k, k.to_string()), None);
+      expect.insert(k, k.to_string());
+    }
+    assert_eq!(map.insert(32, "x".to_string()), Some("32".to_string()));
+    expect.insert(32, "x".to_string());
+    assert_eq!(map.remove(&3), Some("3".to_string()));
+    assert_eq!(map.remove(&3), None);
+    assert_eq!(map.remove(&4), None);
+    expect.remove(&3);
+
+    assert_eq!(map.len(), expect.len());
+    assert_eq!(map.get(&1000), Some(&"1000".to_string()));
+    assert_eq!(map.get(&1001), None);
+    assert!(!map.contains_key(&(1 << 30)));
+    // Iterated in the order of keys
+    assert_eq!(Vec::from_iter(map.iter().map(|(k, v)| (k, v.clone()))), Vec::from_iter(expect.clone()));
+    assert_eq!(map.iter().len(), expect.len());
+
+    for k in Vec::from_iter(map.keys()) {
+      map.remove(&k);
+    }
+    assert!(map.is_empty());
+    assert_eq!(map.iter().next(), None);
+  }
+
+  #[test]
+  fn test_share() {
+    let mut map = PersistentMap::<usize, usize>::from_iter((0..5000).map(|x| (x, x)));
+    let mut shared = map.share();
+    *shared.get_mut(&10).unwrap() = 0;
+    shared.remove(&20);
+    shared.insert(6000, 6000);
+    for (_, v) in shared.iter_mut().take(5) {
+      *v += 1;
+    }
+    map.insert(30, 0);
+
+    assert_eq!(map.get(&10), Some(&10));
+    assert_eq!(map.get(&20), Some(&20));
+    assert_eq!(map.get(&6000), None);
+    assert_eq!(map.get(&0), Some(&0));
+    assert_eq!(map.len(), 5000);
+    assert_eq!(shared.get(&10), Some(&0));
+    assert_eq!(shared.get(&20), None);
+    assert_eq!(shared.get(&30), Some(&30));
+    assert_eq!(shared.get(&0), Some(&1));
+    assert_eq!(shared.len(), 5000);
+
+    let values = Vec::from_iter(shared.share());
+    assert_eq!(values.len(), 5000);
+    assert_eq!(values.last(), Some(&(6000, 6000)));
+    assert_eq!(shared.get(&6000), Some(&6000));
+  }
+
+  #[test]
+  #[should_panic(expected = "A shared map must have a value cloner")]
+  fn test_share_no_cloner() {
+    let mut map = PersistentMap::<usize, usize>::with_cloner(None);
+    map.insert(1, 1);
+    let shared = map.share();
+    map.insert(2, 2);
+  }
+}
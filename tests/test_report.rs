@@ -0,0 +1,114 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_report {
+  use ::ordermap::OrderSet;
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug)]
+  struct TreeNode {
+    children: BTreeSet<NodeIndex>,
+    father: NodeIndex,
+    data: usize,
+  }
+
+  #[derive(TypedNode, Debug)]
+  struct LeafNode {
+    target: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug)]
+    enum Node{
+      Tree(TreeNode),
+      Leaf(LeafNode),
+    }
+    bidirectional!{
+      Tree.father <-> Tree.children,
+    }
+  }
+
+  fn tree(father: NodeIndex) -> Node {
+    Node::Tree(TreeNode { children: BTreeSet::new(), father, data: 0 })
+  }
+
+  fn link(x: NodeIndex, y: NodeIndex, name: &str) -> (NodeIndex, NodeIndex, String) {
+    (x, y, name.to_string())
+  }
+
+  fn links(links: &OrderSet<(NodeIndex, NodeIndex, <Node as NodeEnum>::LinkMirrorEnum)>) -> BTreeSet<(NodeIndex, NodeIndex, String)> {
+    BTreeSet::from_iter(links.iter().map(|(x, y, l)| {
+      let name = match l {
+        ttgraph_gen_node::NodeLinkMirrorEnum::Tree(l) => format!("{:?}", l),
+        ttgraph_gen_node::NodeLinkMirrorEnum::Leaf(l) => format!("{:?}", l),
+      };
+      (*x, *y, name)
+    }))
+  }
+
+  #[test]
+  fn test_report() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let root = trans.insert(tree(NodeIndex::empty()));
+    let a = trans.insert(tree(root));
+    let report = graph.commit_with_report(trans);
+
+    assert_eq!(BTreeSet::from_iter(report.inserted), BTreeSet::from([root, a]));
+    assert!(report.removed.is_empty());
+    assert!(report.modified.is_empty());
+    assert_eq!(links(&report.added_links), BTreeSet::from([link(a, root, "Father"), link(root, a, "Children")]));
+    assert!(report.removed_links.is_empty());
+
+    // Mutate data only
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Tree, a, |x| { x.data = 1 });
+    let report = graph.commit_with_report(trans);
+    assert_eq!(Vec::from_iter(report.modified), vec![a]);
+    assert!(report.added_links.is_empty());
+    assert!(report.removed_links.is_empty());
+
+    // Move a to another father
+    let mut trans = Transaction::new(&ctx);
+    let b = trans.insert(tree(root));
+    let leaf = trans.insert(Node::Leaf(LeafNode { target: a }));
+    mut_node!(trans, Node::Tree, a, |x| { x.father = b });
+    let report = graph.commit_with_report(trans);
+    assert_eq!(BTreeSet::from_iter(report.inserted), BTreeSet::from([b, leaf]));
+    assert_eq!(BTreeSet::from_iter(report.modified), BTreeSet::from([a, root]));
+    assert_eq!(
+      links(&report.added_links),
+      BTreeSet::from([
+        link(a, b, "Father"),
+        link(b, a, "Children"),
+        link(b, root, "Father"),
+        link(root, b, "Children"),
+        link(leaf, a, "Target"),
+      ])
+    );
+    assert_eq!(links(&report.removed_links), BTreeSet::from([link(a, root, "Father"), link(root, a, "Children")]));
+
+    // Remove a, the links to a are removed
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(a);
+    let transient = trans.insert(tree(NodeIndex::empty()));
+    trans.remove(transient);
+    let report = graph.commit_with_report(trans);
+    assert!(report.inserted.is_empty());
+    assert_eq!(Vec::from_iter(report.removed), vec![a]);
+    assert_eq!(BTreeSet::from_iter(report.modified), BTreeSet::from([b, leaf]));
+    assert!(report.added_links.is_empty());
+    assert_eq!(
+      links(&report.removed_links),
+      BTreeSet::from([link(a, b, "Father"), link(b, a, "Children"), link(leaf, a, "Target")])
+    );
+
+    let report = graph.commit_with_report(Transaction::new(&ctx));
+    assert!(report.is_empty());
+  }
+}
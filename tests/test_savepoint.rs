@@ -0,0 +1,164 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_savepoint {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct WorkerNode {
+    name: String,
+    factory: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+  }
+
+  fn worker(name: &str, factory: NodeIndex) -> Node {
+    Node::Worker(WorkerNode { name: name.to_string(), factory })
+  }
+
+  fn names(graph: &Graph<Node>) -> BTreeSet<String> {
+    BTreeSet::from_iter(iter_nodes!(graph, Node::Worker).map(|(_, x)| x.name.clone()))
+  }
+
+  #[test]
+  fn test_nested_savepoints() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f1 = trans.insert(Node::Factory(FactoryNode { name: "F1".to_string(), workers: BTreeSet::new() }));
+    let f2 = trans.insert(Node::Factory(FactoryNode { name: "F2".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(worker("Alice", f1));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    let bob = trans.insert(worker("Bob", f1));
+    let outer = trans.savepoint();
+    let carol = trans.insert(worker("Carol", f1));
+    mut_node!(trans, Node::Worker, bob, |x| { x.name = "Bob2".to_string() });
+    let inner = trans.savepoint();
+    trans.remove(alice);
+    trans.remove(carol);
+    trans.redirect_links(f1, f2);
+    update_node!(trans, Node::Factory, f1, |x| { FactoryNode { name: "G".to_string(), ..x } });
+    trans.rollback_to(inner);
+
+    let view = TransactionView::new(&graph, &mut trans);
+    let view_names = BTreeSet::from_iter(iter_nodes!(view, Node::Worker).map(|(_, x)| x.name.clone()));
+    assert_eq!(view_names, BTreeSet::from(["Alice".to_string(), "Bob2".to_string(), "Carol".to_string()]));
+    assert_eq!(get_node!(view, Node::Factory, f1).unwrap().name, "F1");
+
+    trans.rollback_to(outer);
+    graph.commit(trans);
+    assert_eq!(names(&graph), BTreeSet::from(["Alice".to_string(), "Bob".to_string()]));
+    assert_eq!(get_node!(graph, Node::Factory, f1).unwrap().workers, BTreeSet::from([alice, bob]));
+    assert!(graph.get(carol).is_none());
+  }
+
+  #[test]
+  fn test_rollback_alloc() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let savepoint = trans.savepoint();
+    let f = alloc_node!(trans, Node::Factory);
+    trans.rollback_to(savepoint);
+    assert!(trans.is_empty());
+    graph.commit(trans);
+    assert!(graph.is_empty());
+  }
+
+  #[test]
+  #[should_panic(expected = "Rollback to a savepoint not belonging to this transaction!")]
+  fn test_rollback_invalid() {
+    let ctx = Context::new();
+    let mut trans = Transaction::<Node>::new(&ctx);
+    let outer = trans.savepoint();
+    trans.mutate(NodeIndex::empty(), |_| {});
+    let inner = trans.savepoint();
+    trans.rollback_to(outer);
+    trans.rollback_to(inner);
+  }
+
+  #[test]
+  #[should_panic(expected = "Rollback to a savepoint not belonging to this transaction!")]
+  fn test_rollback_other() {
+    let ctx = Context::new();
+    let mut t1 = Transaction::<Node>::new(&ctx);
+    let mut t2 = Transaction::<Node>::new(&ctx);
+    t2.mutate(NodeIndex::empty(), |_| {});
+    // Same context and no longer than t2, but made by t1
+    t2.rollback_to(t1.savepoint());
+  }
+
+  #[test]
+  fn test_rollback_absorb() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let old = Transaction::<Node>::begin(&ctx, &graph);
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    let mut trans = Transaction::begin(&ctx, &graph);
+    let savepoint = trans.savepoint();
+    trans.absorb(old);
+    assert_eq!(trans.base_version(), Some(0));
+    trans.rollback_to(savepoint);
+    assert_eq!(trans.base_version(), Some(1));
+  }
+
+  #[test]
+  fn test_absorb() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(worker("Alice", f));
+    graph.commit(trans);
+
+    let mut parent = Transaction::begin(&ctx, &graph);
+    let bob = parent.insert(worker("Bob", f));
+
+    let mut child = Transaction::new(&ctx);
+    let carol = child.insert(worker("Carol", f));
+    child.remove(bob);
+    mut_node!(child, Node::Worker, alice, |x| { x.name = "Alice2".to_string() });
+    parent.absorb(child);
+
+    let mut failed = Transaction::<Node>::new(&ctx);
+    failed.remove(alice);
+    drop(failed);
+
+    assert_eq!(parent.base_version(), Some(1));
+    graph.commit(parent);
+    assert_eq!(names(&graph), BTreeSet::from(["Alice2".to_string(), "Carol".to_string()]));
+    assert_eq!(get_node!(graph, Node::Factory, f).unwrap().workers, BTreeSet::from([alice, carol]));
+    assert!(graph.get(bob).is_none());
+  }
+
+  #[test]
+  #[should_panic(expected = "The transactions are from different context!")]
+  fn test_absorb_context() {
+    let mut parent = Transaction::<Node>::new(&Context::new());
+    parent.absorb(Transaction::new(&Context::new()));
+  }
+}
@@ -0,0 +1,112 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_schema {
+  use std::collections::{BTreeSet, HashSet};
+
+  use ttgraph::*;
+
+  #[derive(TypedNode)]
+  struct NodeA {
+    to_a: NodeIndex,
+    to_b: BTreeSet<NodeIndex>,
+    name: String,
+    tags: Vec<String>,
+  }
+
+  #[derive(TypedNode)]
+  struct NodeB {
+    to_a: Vec<NodeIndex>,
+    back: HashSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode)]
+  struct NodeC {
+    x: NodeIndex,
+    value: Option<usize>,
+  }
+
+  node_enum! {
+    enum Node{
+      A(NodeA),
+      B(NodeB),
+      C(NodeC),
+    }
+    group!{
+      AB{A, B},
+      BC{C, B},
+    }
+    bidirectional!{
+      A.to_b <-> B.back,
+    }
+    link_type!{
+      A.to_a: A,
+      B.to_a: {A, C},
+      C.x: AB,
+    }
+  }
+
+  #[test]
+  fn test_variants() {
+    let schema = Node::schema();
+    assert_eq!(schema.name, "Node");
+    assert_eq!(Vec::from_iter(schema.variants.iter().map(|x| x.name.as_str())), vec!["A", "B", "C"]);
+
+    let a = schema.variant("A").unwrap();
+    assert_eq!(a.type_name, "NodeA");
+    assert_eq!(a.data.len(), 2);
+    assert_eq!(a.data("name").unwrap().type_name, "String");
+    assert_eq!(a.data("tags").unwrap().type_name, "Vec<String>");
+    assert_eq!(a.link("to_a").unwrap().link_type, LinkType::Point);
+    assert_eq!(a.link("to_b").unwrap().link_type, LinkType::Set);
+    assert!(a.data("to_a").is_none());
+
+    let b = schema.variant("B").unwrap();
+    assert!(b.data.is_empty());
+    assert_eq!(b.link("to_a").unwrap().link_type, LinkType::Vec);
+    assert_eq!(b.link("back").unwrap().link_type, LinkType::Set);
+
+    let c = schema.variant("C").unwrap();
+    assert_eq!(c.data("value").unwrap().type_name, "Option<usize>");
+    assert!(schema.variant("D").is_none());
+  }
+
+  #[test]
+  fn test_groups_and_constraints() {
+    let schema = Node::schema();
+    assert_eq!(schema.group("AB").unwrap().variants, vec!["A".to_string(), "B".to_string()]);
+    // Ordered by the variants of the enum
+    assert_eq!(schema.group("BC").unwrap().variants, vec!["B".to_string(), "C".to_string()]);
+    assert!(schema.group("AC").is_none());
+
+    let targets = |var: &str, link: &str| {
+      let x = schema.link_types.iter().find(|x| x.variant == var && x.link == link).unwrap();
+      BTreeSet::from_iter(x.targets.iter().cloned())
+    };
+    assert_eq!(schema.link_types.len(), 3);
+    assert_eq!(targets("A", "to_a"), BTreeSet::from(["A".to_string()]));
+    assert_eq!(targets("B", "to_a"), BTreeSet::from(["A".to_string(), "C".to_string()]));
+    // Groups are expanded
+    assert_eq!(targets("C", "x"), BTreeSet::from(["A".to_string(), "B".to_string()]));
+
+    assert_eq!(
+      schema.bidirectional,
+      vec![BidirectionalSchema {
+        variant1: "A".to_string(),
+        link1: "to_b".to_string(),
+        variant2: "B".to_string(),
+        link2: "back".to_string(),
+      }]
+    );
+  }
+
+  #[test]
+  fn test_serde() {
+    let schema = Node::schema();
+    let json = serde_json::to_string(&schema).unwrap();
+    let schema2: GraphSchema = serde_json::from_str(&json).unwrap();
+    assert_eq!(schema, schema2);
+  }
+}
@@ -0,0 +1,133 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_send_transaction {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug)]
+  struct WorkerNode {
+    name: String,
+    factory: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+  }
+
+  fn assert_send<T: Send>() {}
+
+  #[test]
+  fn test_parallel_build() {
+    assert_send::<SendTransaction<Node>>();
+
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factories = Vec::from_iter(
+      (0..4).map(|i| trans.insert(Node::Factory(FactoryNode { name: format!("F{}", i), workers: BTreeSet::new() }))),
+    );
+    graph.commit(trans);
+
+    let suffix = "!".to_string();
+    let results = std::thread::scope(|s| {
+      let handles = Vec::from_iter(factories.iter().map(|&factory| {
+        let ctx = &ctx;
+        let suffix = &suffix;
+        s.spawn(move || {
+          let mut trans = SendTransaction::<Node>::new(ctx);
+          for j in 0..3 {
+            trans.insert(Node::Worker(WorkerNode { name: format!("W{}", j), factory }));
+          }
+          // Borrows from the outer scope
+          mut_node!(trans, Node::Factory, factory, |x| { x.name.push_str(suffix) });
+          trans
+        })
+      }));
+      Vec::from_iter(handles.into_iter().map(|x| x.join().unwrap()))
+    });
+
+    let mut trans = SendTransaction::new(&ctx);
+    assert!(trans.is_empty());
+    for t in results {
+      assert!(trans.append(t).is_empty());
+    }
+    graph.commit(trans.into());
+
+    assert_eq!(graph.len(), 16);
+    for (i, factory) in factories.iter().enumerate() {
+      let node = get_node!(graph, Node::Factory, *factory).unwrap();
+      assert_eq!(node.name, format!("F{}!", i));
+      assert_eq!(node.workers.len(), 3);
+      for worker in &node.workers {
+        assert_eq!(get_node!(graph, Node::Worker, *worker).unwrap().factory, *factory);
+      }
+    }
+  }
+
+  #[test]
+  fn test_send_conflict() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    // The version and the read nodes are kept when converted into a Transaction
+    let mut t1 = SendTransaction::begin(&ctx, &graph);
+    t1.read(&graph, factory);
+    let mut t2 = Transaction::new(&ctx);
+    mut_node!(t2, Node::Factory, factory, |x| { x.name = "G".to_string() });
+    graph.commit(t2);
+
+    let conflict = graph.try_commit(t1.into()).unwrap_err();
+    assert_eq!(Vec::from_iter(conflict.nodes), vec![factory]);
+  }
+
+  #[test]
+  fn test_append_cross_reference() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+
+    let mut t1 = SendTransaction::new(&ctx);
+    let factory = t1.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = t1.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), factory }));
+    let bob = alloc_node!(t1, Node::Worker);
+    t1.fill_back(bob, Node::Worker(WorkerNode { name: "Bob".to_string(), factory }));
+
+    let mut t2 = SendTransaction::new(&ctx);
+    t2.remove(alice);
+    update_node!(t2, Node::Worker, bob, |x| { WorkerNode { name: "Bob2".to_string(), ..x } });
+
+    assert!(t1.append(t2).is_empty());
+    graph.commit(t1.into());
+
+    assert!(graph.get(alice).is_none());
+    assert_eq!(get_node!(graph, Node::Worker, bob).unwrap().name, "Bob2");
+    assert_eq!(get_node!(graph, Node::Factory, factory).unwrap().workers, BTreeSet::from([bob]));
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_append_context() {
+    let mut t1 = SendTransaction::<Node>::new(&Context::new());
+    let t2 = SendTransaction::<Node>::new(&Context::new());
+    t1.append(t2);
+  }
+}
@@ -0,0 +1,144 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_snapshot {
+  use std::collections::BTreeSet;
+  use std::sync::Arc;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct WorkerNode {
+    name: String,
+    factory: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+    index!{
+      Worker.name,
+    }
+  }
+
+  fn assert_send_sync<T: Send + Sync>() {}
+
+  fn names(graph: &Graph<Node>) -> BTreeSet<String> {
+    BTreeSet::from_iter(iter_nodes!(graph, Node::Worker).map(|(_, x)| x.name.clone()))
+  }
+
+  #[test]
+  fn test_snapshot() {
+    assert_send_sync::<GraphSnapshot<Node>>();
+
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), factory }));
+    graph.commit(trans);
+
+    let snapshot = Arc::new(graph.snapshot());
+    let readers = Vec::from_iter((0..4).map(|_| {
+      let snapshot = snapshot.clone();
+      std::thread::spawn(move || {
+        let mut result = Vec::new();
+        for _ in 0..100 {
+          result.push((names(&snapshot), get_node!(snapshot, Node::Factory, factory).unwrap().workers.clone()));
+        }
+        result
+      })
+    }));
+
+    for i in 0..10 {
+      let mut trans = Transaction::new(&ctx);
+      trans.insert(Node::Worker(WorkerNode { name: format!("W{}", i), factory }));
+      graph.commit(trans);
+    }
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(alice);
+    graph.commit(trans);
+
+    for reader in readers {
+      for (names, workers) in reader.join().unwrap() {
+        assert_eq!(names, BTreeSet::from(["Alice".to_string()]));
+        assert_eq!(workers, BTreeSet::from([alice]));
+      }
+    }
+
+    assert_eq!(snapshot.len(), 2);
+    assert_eq!(Vec::from_iter(snapshot.lookup("name", &"Alice".to_string())), vec![alice]);
+    assert_eq!(snapshot.lookup("name", &"W0".to_string()).next(), None);
+    assert_eq!(graph.len(), 11);
+    assert!(graph.get(alice).is_none());
+  }
+
+  #[test]
+  fn test_snapshot_to_graph() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    let snapshot = graph.snapshot();
+    let mut restored = snapshot.to_graph();
+    let mut trans = Transaction::new(&ctx);
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), factory }));
+    restored.commit(trans);
+
+    assert_eq!(names(&restored), BTreeSet::from(["Bob".to_string()]));
+    assert!(snapshot.get(bob).is_none());
+    assert!(graph.get(bob).is_none());
+    let cloned = snapshot.clone();
+    assert!(get_node!(cloned, Node::Factory, factory).unwrap().workers.is_empty());
+  }
+
+  #[test]
+  fn test_snapshot_per_commit() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    graph.commit(trans);
+
+    // Keep the history of the graph by a snapshot after every commit
+    let mut history = vec![graph.snapshot()];
+    let mut workers = Vec::new();
+    for i in 0..500 {
+      let mut trans = Transaction::new(&ctx);
+      workers.push(trans.insert(Node::Worker(WorkerNode { name: format!("W{}", i), factory })));
+      if i % 2 == 1 {
+        trans.remove(workers[i - 1]);
+      }
+      graph.commit(trans);
+      history.push(graph.snapshot());
+    }
+
+    for (i, snapshot) in history.iter().enumerate() {
+      assert_eq!(snapshot.version(), i as u64 + 1);
+      assert_eq!(snapshot.len(), 1 + i.div_ceil(2));
+      assert_eq!(get_node!(snapshot, Node::Factory, factory).unwrap().workers.len(), i.div_ceil(2));
+      if i > 0 {
+        let last = format!("W{}", i - 1);
+        assert_eq!(Vec::from_iter(snapshot.lookup("name", &last)), vec![workers[i - 1]]);
+        assert_eq!(snapshot.lookup("name", &format!("W{}", i)).next(), None);
+      }
+    }
+    assert_eq!(graph.len(), 251);
+  }
+}
@@ -0,0 +1,202 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_version {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug)]
+  struct WorkerNode {
+    name: String,
+    factory: NodeIndex,
+  }
+
+  #[derive(TypedNode, Debug)]
+  struct PointerNode {
+    target: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+      Pointer(PointerNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+  }
+
+  fn setup() -> (Context, Graph<Node>, NodeIndex, NodeIndex) {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let factory = trans.insert(Node::Factory(FactoryNode { name: "F".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), factory }));
+    graph.commit(trans);
+    (ctx, graph, factory, alice)
+  }
+
+  #[test]
+  fn test_versions() {
+    let (ctx, mut graph, factory, alice) = setup();
+    assert_eq!(graph.version(), 1);
+    assert_eq!(graph.node_version(factory), Some(1));
+    assert_eq!(graph.node_version(alice), Some(1));
+
+    // The factory is changed by the bidirectional link
+    let mut trans = Transaction::new(&ctx);
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), factory }));
+    graph.commit(trans);
+    assert_eq!(graph.version(), 2);
+    assert_eq!(graph.node_version(factory), Some(2));
+    assert_eq!(graph.node_version(alice), Some(1));
+    assert_eq!(graph.node_version(bob), Some(2));
+
+    *get_data_mut!(graph, Node::Worker, alice).unwrap().name = "Alice2".to_string();
+    assert_eq!(graph.version(), 3);
+    assert_eq!(graph.node_version(alice), Some(3));
+
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(bob);
+    graph.commit(trans);
+    // No transaction began before the removal, so the version of bob is dropped
+    assert_eq!(graph.node_version(bob), None);
+    assert_eq!(graph.node_version(factory), Some(4));
+  }
+
+  #[test]
+  fn test_prune_versions() {
+    let (ctx, mut graph, factory, alice) = setup();
+    let old = Transaction::<Node>::begin(&ctx, &graph);
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(alice);
+    graph.commit(trans);
+    let mut trans = Transaction::new(&ctx);
+    trans.insert(Node::Pointer(PointerNode { target: NodeIndex::empty() }));
+    graph.commit(trans);
+    // Kept for the transaction which began before the removal
+    assert_eq!(graph.node_version(alice), Some(2));
+
+    let mut old = old;
+    old.read(&graph, alice);
+    assert!(graph.try_commit(old).is_err());
+    graph.commit(Transaction::new(&ctx));
+    assert_eq!(graph.node_version(alice), None);
+    assert_eq!(graph.node_version(factory), Some(2));
+
+    // Also dropped when the transaction which began before the removal is dropped
+    let mut trans = Transaction::new(&ctx);
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), factory }));
+    graph.commit(trans);
+    let new = Transaction::<Node>::begin(&ctx, &graph);
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(bob);
+    graph.commit(trans);
+    assert_eq!(graph.node_version(bob), Some(6));
+    drop(new);
+    graph.commit(Transaction::new(&ctx));
+    assert_eq!(graph.node_version(bob), None);
+  }
+
+  #[test]
+  fn test_write_conflict() {
+    let (ctx, mut graph, factory, alice) = setup();
+    let mut t1 = Transaction::begin(&ctx, &graph);
+    let mut t2 = Transaction::begin(&ctx, &graph);
+    assert_eq!(t1.base_version(), Some(1));
+    mut_node!(t1, Node::Worker, alice, |x| { x.name = "A1".to_string() });
+    update_node!(t2, Node::Worker, alice, |x| { WorkerNode { name: "A2".to_string(), ..x } });
+
+    graph.try_commit(t1).unwrap();
+    let conflict = graph.try_commit(t2).unwrap_err();
+    assert_eq!(conflict.base_version, 1);
+    assert_eq!(conflict.graph_version, 2);
+    assert_eq!(Vec::from_iter(conflict.nodes), vec![alice]);
+    assert_eq!(get_node!(graph, Node::Worker, alice).unwrap().name, "A1");
+    assert_eq!(graph.version(), 2);
+  }
+
+  #[test]
+  fn test_removed_link_conflict() {
+    let (ctx, mut graph, factory, alice) = setup();
+    let mut t1 = Transaction::begin(&ctx, &graph);
+    let mut t2 = Transaction::begin(&ctx, &graph);
+    t1.remove(alice);
+    let r = t2.insert(Node::Pointer(PointerNode { target: alice }));
+
+    graph.commit(t1);
+    let conflict = graph.try_commit(t2).unwrap_err();
+    assert_eq!(Vec::from_iter(conflict.nodes), vec![alice]);
+    assert!(graph.get(r).is_none());
+  }
+
+  #[test]
+  fn test_removed_link_conflict_reversed() {
+    let (ctx, mut graph, factory, alice) = setup();
+    let mut t1 = Transaction::begin(&ctx, &graph);
+    let mut t2 = Transaction::begin(&ctx, &graph);
+    let r = t1.insert(Node::Pointer(PointerNode { target: alice }));
+    t2.remove(alice);
+
+    graph.commit(t1);
+    let conflict = graph.try_commit(t2).unwrap_err();
+    assert_eq!(Vec::from_iter(conflict.nodes), vec![alice]);
+    assert_eq!(get_node!(graph, Node::Pointer, r).unwrap().target, alice);
+  }
+
+  #[test]
+  fn test_read_conflict() {
+    let (ctx, mut graph, factory, alice) = setup();
+    let mut t1 = Transaction::begin(&ctx, &graph);
+    let mut t2 = Transaction::begin(&ctx, &graph);
+    mut_node!(t1, Node::Worker, alice, |x| { x.name = "A1".to_string() });
+    let name = match t2.read(&graph, alice).unwrap() {
+      Node::Worker(x) => x.name.clone(),
+      _ => panic!(),
+    };
+    mut_node!(t2, Node::Factory, factory, move |x| { x.name = name });
+
+    graph.commit(t1);
+    assert!(graph.try_commit(t2).is_err());
+  }
+
+  #[test]
+  fn test_no_conflict() {
+    let (ctx, mut graph, factory, alice) = setup();
+    let mut t1 = Transaction::begin(&ctx, &graph);
+    let mut t2 = Transaction::begin(&ctx, &graph);
+    let mut t3 = Transaction::new(&ctx);
+    mut_node!(t1, Node::Worker, alice, |x| { x.name = "A1".to_string() });
+    t2.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), factory: NodeIndex::empty() }));
+    mut_node!(t3, Node::Worker, alice, |x| { x.name = "A3".to_string() });
+
+    graph.try_commit(t1).unwrap();
+    graph.try_commit(t2).unwrap();
+    // Transactions made by new never conflict
+    graph.try_commit(t3).unwrap();
+    assert_eq!(get_node!(graph, Node::Worker, alice).unwrap().name, "A3");
+  }
+
+  #[test]
+  #[should_panic(expected = "Transaction conflict")]
+  fn test_commit_panic() {
+    let (ctx, mut graph, factory, alice) = setup();
+    let mut t1 = Transaction::begin(&ctx, &graph);
+    let mut t2 = Transaction::begin(&ctx, &graph);
+    t1.remove(factory);
+    t2.remove(factory);
+    graph.commit(t1);
+    graph.commit(t2);
+  }
+}
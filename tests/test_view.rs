@@ -0,0 +1,204 @@
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+
+#[cfg(test)]
+mod test_view {
+  use std::collections::BTreeSet;
+
+  use ttgraph::*;
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct FactoryNode {
+    name: String,
+    workers: BTreeSet<NodeIndex>,
+  }
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct WorkerNode {
+    name: String,
+    factory: NodeIndex,
+  }
+
+  #[derive(TypedNode, Debug, Clone)]
+  struct PointerNode {
+    target: NodeIndex,
+  }
+
+  node_enum! {
+    #[derive(Debug, Clone)]
+    enum Node{
+      Factory(FactoryNode),
+      Worker(WorkerNode),
+      Pointer(PointerNode),
+    }
+    bidirectional!{
+      Factory.workers <-> Worker.factory,
+    }
+    index!{
+      Worker.name,
+    }
+  }
+
+  fn worker_name(node: Option<&Node>) -> String {
+    if let Some(Node::Worker(x)) = node {
+      x.name.clone()
+    } else {
+      panic!()
+    }
+  }
+
+  #[test]
+  fn test_view() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f1 = trans.insert(Node::Factory(FactoryNode { name: "F1".to_string(), workers: BTreeSet::new() }));
+    let f2 = trans.insert(Node::Factory(FactoryNode { name: "F2".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), factory: f1 }));
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), factory: f1 }));
+    let p = trans.insert(Node::Pointer(PointerNode { target: bob }));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Worker, alice, |x| { x.factory = f2 });
+    update_node!(trans, Node::Worker, alice, |x| { WorkerNode { name: "Alice2".to_string(), ..x } });
+    trans.remove(bob);
+    let carol = trans.insert(Node::Worker(WorkerNode { name: "Carol".to_string(), factory: f2 }));
+
+    let view = TransactionView::new(&graph, &mut trans);
+    assert_eq!(worker_name(view.get(alice)), "Alice2");
+    assert!(!view.contains(bob));
+    assert!(view.contains(carol));
+    assert_eq!(get_node!(view, Node::Pointer, p).unwrap().target, NodeIndex::empty());
+    assert!(get_node!(view, Node::Factory, f1).unwrap().workers.is_empty());
+    assert_eq!(get_node!(view, Node::Factory, f2).unwrap().workers, BTreeSet::from([alice, carol]));
+    assert_eq!(BTreeSet::from_iter(view.back_links(f2).map(|(x, _)| x)), BTreeSet::from([alice, carol]));
+    assert_eq!(Vec::from_iter(view.lookup("name", &"Alice2".to_string())), vec![alice]);
+    assert_eq!(view.len(), 5);
+
+    // The graph is untouched
+    assert_eq!(worker_name(graph.get(alice)), "Alice");
+    assert!(graph.contains(bob));
+    assert_eq!(graph.len(), 5);
+
+    // Continue with the transaction after reading the view
+    let name = worker_name(view.get(alice));
+    update_node!(trans, Node::Worker, alice, move |x| { WorkerNode { name: name + "!", ..x } });
+    trans.redirect_links(f1, f2);
+
+    let view = TransactionView::new(&graph, &mut trans);
+    assert_eq!(worker_name(view.get(alice)), "Alice2!");
+
+    let expected = BTreeSet::from_iter(view.iter().map(|(x, n)| (x, format!("{:?}", n))));
+    graph.commit(trans);
+    assert_eq!(BTreeSet::from_iter(graph.iter().map(|(x, n)| (x, format!("{:?}", n)))), expected);
+    assert_eq!(worker_name(graph.get(alice)), "Alice2!");
+    assert_eq!(get_node!(graph, Node::Factory, f2).unwrap().workers, BTreeSet::from([alice, carol]));
+  }
+
+  #[test]
+  fn test_view_conflict() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f1 = trans.insert(Node::Factory(FactoryNode { name: "F1".to_string(), workers: BTreeSet::new() }));
+    let alice = trans.insert(Node::Worker(WorkerNode { name: "Alice".to_string(), factory: f1 }));
+    let bob = trans.insert(Node::Worker(WorkerNode { name: "Bob".to_string(), factory: f1 }));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Worker, alice, |x| { x.name += "!" });
+    trans.remove(bob);
+    let view = TransactionView::new(&graph, &mut trans);
+    assert_eq!(worker_name(view.get(alice)), "Alice!");
+    assert!(!view.contains(bob));
+    assert_eq!(get_node!(view, Node::Factory, f1).unwrap().workers, BTreeSet::from([alice]));
+
+    // Changes to other nodes are kept
+    let mut other = Transaction::new(&ctx);
+    mut_node!(other, Node::Factory, f1, |x| { x.name = "F2".to_string() });
+    graph.commit(other);
+    let mut snapshot = graph.fork();
+    snapshot.commit(trans);
+    assert_eq!(worker_name(snapshot.get(alice)), "Alice!");
+    assert_eq!(get_node!(snapshot, Node::Factory, f1).unwrap().name, "F2");
+
+    // The node written by the view is changed by another commit, so it is not overwritten
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Worker, alice, |x| { x.name += "!" });
+    let view = TransactionView::new(&graph, &mut trans);
+    let mut other = Transaction::new(&ctx);
+    mut_node!(other, Node::Worker, alice, |x| { x.name = "Alice2".to_string() });
+    graph.commit(other);
+    let conflict = graph.try_commit(trans).unwrap_err();
+    assert_eq!(Vec::from_iter(conflict.nodes), vec![alice]);
+    assert_eq!(worker_name(graph.get(alice)), "Alice2");
+
+    // So is a removed node
+    let mut trans = Transaction::new(&ctx);
+    mut_node!(trans, Node::Worker, alice, |x| { x.name += "!" });
+    let view = TransactionView::new(&graph, &mut trans);
+    let mut other = Transaction::new(&ctx);
+    other.remove(alice);
+    graph.commit(other);
+    assert!(graph.try_commit(trans).is_err());
+  }
+
+  #[test]
+  fn test_view_iter() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let f1 = trans.insert(Node::Factory(FactoryNode { name: "F1".to_string(), workers: BTreeSet::new() }));
+    let workers = Vec::from_iter(
+      (0..10).map(|i| trans.insert(Node::Worker(WorkerNode { name: format!("W{}", i), factory: f1 }))),
+    );
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    trans.remove(workers[3]);
+    mut_node!(trans, Node::Worker, workers[5], |x| { x.name = "W3".to_string() });
+    let w10 = trans.insert(Node::Worker(WorkerNode { name: "W10".to_string(), factory: f1 }));
+    let view = TransactionView::new(&graph, &mut trans);
+
+    let names = Vec::from_iter(iter_nodes!(view, Node::Worker).map(|(x, n)| (x, n.name.clone())));
+    assert_eq!(names.len(), 10);
+    assert_eq!(names.len(), view.len() - 1);
+    // Iterated in the order of indexes, as the graph does
+    assert!(names.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_eq!(names[4], (workers[5], "W3".to_string()));
+    assert_eq!(names[9], (w10, "W10".to_string()));
+    assert_eq!(Vec::from_iter(view.lookup("name", &"W3".to_string())), vec![workers[5]]);
+    assert_eq!(view.lookup("name", &"W5".to_string()).count(), 0);
+    assert_eq!(view.back_links(f1).count(), 10);
+    assert_eq!(view.iter().count(), 11);
+
+    let expected = BTreeSet::from_iter(view.iter().map(|(x, n)| (x, format!("{:?}", n))));
+    graph.commit(trans);
+    assert_eq!(BTreeSet::from_iter(graph.iter().map(|(x, n)| (x, format!("{:?}", n)))), expected);
+  }
+
+  #[test]
+  fn test_view_redirect() {
+    let ctx = Context::new();
+    let mut graph = Graph::<Node>::new(&ctx);
+    let mut trans = Transaction::new(&ctx);
+    let a = trans.insert(Node::Pointer(PointerNode { target: NodeIndex::empty() }));
+    let b = trans.insert(Node::Pointer(PointerNode { target: NodeIndex::empty() }));
+    let c = trans.insert(Node::Pointer(PointerNode { target: a }));
+    graph.commit(trans);
+
+    let mut trans = Transaction::new(&ctx);
+    trans.redirect_links(a, b);
+    mut_node!(trans, Node::Pointer, c, |x| { assert_eq!(x.target, b) });
+
+    let view = TransactionView::new(&graph, &mut trans);
+    assert_eq!(get_node!(view, Node::Pointer, c).unwrap().target, b);
+    assert_eq!(Vec::from_iter(view.back_links(b).map(|(x, _)| x)), vec![c]);
+    assert_eq!(view.back_links(a).count(), 0);
+
+    graph.commit(trans);
+    assert_eq!(get_node!(graph, Node::Pointer, c).unwrap().target, b);
+  }
+}
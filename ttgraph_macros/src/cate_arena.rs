@@ -5,7 +5,7 @@ use quote::{format_ident, quote};
 use syn::Visibility;
 
 pub(crate) fn make_node_discriminant(
-  result: &mut TokenStream, vars: &Vec<(Ident, Type)>, enumt: &Ident, vis: &Visibility,
+  result: &mut TokenStream, vars: &[(Ident, Type)], enumt: &Ident, vis: &Visibility,
 ) -> Ident {
   let enum_name = format_ident!("{}Discriminant", enumt);
   let mut v = Vec::new();
@@ -47,21 +47,21 @@ pub(crate) fn make_node_discriminant(
 }
 
 pub(crate) fn make_cate_arena_iter(
-  generated: &mut TokenStream, vars: &Vec<(Ident, Type)>, enumt: &Ident, discriminant: &Ident, vis: &Visibility,
+  generated: &mut TokenStream, vars: &[(Ident, Type)], enumt: &Ident, discriminant: &Ident, vis: &Visibility,
 ) -> Ident {
   let iter_name = format_ident!("{}Iter", enumt);
 
   let iterators = Vec::from_iter(vars.iter().map(|(ident, _)| snake_case(ident)));
   let mut fields = Vec::new();
   for iter in iterators.iter() {
-    fields.push(quote! {#iter: ttgraph::ordermap::map::Iter<'a, usize, #enumt>});
+    fields.push(quote! {#iter: ttgraph::persistent_map::Iter<'a, usize, #enumt>});
   }
 
   let mut next_arms = Vec::new();
   for (iter, (ident, _)) in iterators.iter().zip(vars.iter()) {
     next_arms.push(quote! {Some(#discriminant::#ident) => {
       if let Some((k, v)) = std::iter::Iterator::next(&mut self.#iter) {
-        Some((ttgraph::NodeIndex(*k), v))
+        Some((ttgraph::NodeIndex(k), v))
       } else {
         self._iter_state = ttgraph::NodeDiscriminant::next(&#discriminant::#ident);
         self.next()
@@ -108,21 +108,21 @@ pub(crate) fn make_cate_arena_iter(
 }
 
 pub(crate) fn make_cate_arena_iter_mut(
-  result: &mut TokenStream, vars: &Vec<(Ident, Type)>, enumt: &Ident, discriminant: &Ident, vis: &Visibility,
+  result: &mut TokenStream, vars: &[(Ident, Type)], enumt: &Ident, discriminant: &Ident, vis: &Visibility,
 ) -> Ident {
   let iter_name = format_ident!("{}IterMut", enumt);
 
   let iterators = Vec::from_iter(vars.iter().map(|(ident, _)| snake_case(ident)));
   let mut fields = Vec::new();
   for iter in iterators.iter() {
-    fields.push(quote! {#iter: ttgraph::ordermap::map::IterMut<'a, usize, #enumt>});
+    fields.push(quote! {#iter: ttgraph::persistent_map::IterMut<'a, usize, #enumt>});
   }
 
   let mut next_arms = Vec::new();
   for (iter, (ident, _)) in iterators.iter().zip(vars.iter()) {
     next_arms.push(quote! {Some(#discriminant::#ident) => {
       if let Some((k, v)) = std::iter::Iterator::next(&mut self.#iter) {
-        Some((ttgraph::NodeIndex(*k), v))
+        Some((ttgraph::NodeIndex(k), v))
       } else {
         self._iter_state = ttgraph::NodeDiscriminant::next(&#discriminant::#ident);
         self.next()
@@ -168,14 +168,14 @@ pub(crate) fn make_cate_arena_iter_mut(
 }
 
 pub(crate) fn make_cate_arena_intoiter(
-  result: &mut TokenStream, vars: &Vec<(Ident, Type)>, enumt: &Ident, discriminant: &Ident, vis: &Visibility,
+  result: &mut TokenStream, vars: &[(Ident, Type)], enumt: &Ident, discriminant: &Ident, vis: &Visibility,
 ) -> Ident {
   let iter_name = format_ident!("{}IntoIter", enumt);
 
   let iterators = Vec::from_iter(vars.iter().map(|(ident, _)| snake_case(ident)));
   let mut fields = Vec::new();
   for iter in iterators.iter() {
-    fields.push(quote! {#iter: ttgraph::ordermap::map::IntoIter<usize, #enumt>});
+    fields.push(quote! {#iter: ttgraph::persistent_map::IntoIter<usize, #enumt>});
   }
 
   let mut next_arms = Vec::new();
@@ -228,7 +228,7 @@ pub(crate) fn make_cate_arena_intoiter(
 }
 
 pub(crate) fn make_cate_arena(
-  result: &mut TokenStream, vars: &Vec<(Ident, Type)>, enumt: &Ident, discriminant: &Ident, vis: &Visibility,
+  result: &mut TokenStream, vars: &[(Ident, Type)], enumt: &Ident, discriminant: &Ident, vis: &Visibility,
 ) -> Ident {
   let arena_name = format_ident!("{}Arena", enumt);
 
@@ -240,12 +240,12 @@ pub(crate) fn make_cate_arena(
 
   let mut fields = Vec::new();
   for cont in containers.iter() {
-    fields.push(quote! {#cont: ttgraph::ordermap::OrderMap<usize, #enumt>});
+    fields.push(quote! {#cont: ttgraph::persistent_map::PersistentMap<usize, #enumt>});
   }
 
   let mut fields_new = Vec::new();
   for cont in &containers {
-    fields_new.push(quote! {#cont: ttgraph::ordermap::OrderMap::new()});
+    fields_new.push(quote! {#cont: ttgraph::persistent_map::PersistentMap::with_cloner(Self::_cloner())});
   }
 
   let mut get_container_arms = Vec::new();
@@ -259,7 +259,14 @@ pub(crate) fn make_cate_arena(
 
   let mut merge_arms = Vec::new();
   for cont in &containers {
-    merge_arms.push(quote! {self.#cont.append(&mut other.#cont);});
+    merge_arms.push(quote! {
+      self.#cont.append(&mut other.#cont);
+    });
+  }
+
+  let mut fork_arms = Vec::new();
+  for cont in containers.iter() {
+    fork_arms.push(quote! { #cont: self.#cont.share() });
   }
 
   let mut lens = Vec::new();
@@ -279,7 +286,7 @@ pub(crate) fn make_cate_arena(
 
   let mut intoiter_arms = Vec::new();
   for cont in containers.iter() {
-    intoiter_arms.push(quote! { #cont: self.#cont.into_iter() });
+    intoiter_arms.push(quote! { #cont: std::iter::IntoIterator::into_iter(self.#cont) });
   }
 
   // let mut contains = Vec::new();
@@ -307,10 +314,18 @@ pub(crate) fn make_cate_arena(
   quote! {
     #vis struct #arena_name {
       _id_distributer: ttgraph::id_distributer::IdDistributer,
-      _dispatcher: ttgraph::ordermap::OrderMap<ttgraph::NodeIndex, #discriminant>,
+      _dispatcher: ttgraph::persistent_map::PersistentMap<ttgraph::NodeIndex, #discriminant>,
       #(#fields,)*
     }
 
+    impl #arena_name {
+      // Containers are only shared by fork, which requires the nodes to be Clone
+      fn _cloner() -> Option<ttgraph::persistent_map::ValueCloner<#enumt>> {
+        use ttgraph::{ProbeClone, ProbeNoClone};
+        (&ttgraph::ClonerProbe::<#enumt>::new()).cloner()
+      }
+    }
+
     impl ttgraph::CateArena for #arena_name {
       type V = #enumt;
       type D = #discriminant;
@@ -319,7 +334,11 @@ pub(crate) fn make_cate_arena(
       type IntoIter = #intoiter;
 
       fn new(id_distributer: ttgraph::id_distributer::IdDistributer) -> Self {
-        Self{ _id_distributer: id_distributer, _dispatcher: ttgraph::ordermap::OrderMap::new(), #(#fields_new),* }
+        Self{
+          _id_distributer: id_distributer,
+          _dispatcher: ttgraph::persistent_map::PersistentMap::new(),
+          #(#fields_new),*
+        }
       }
       fn new_from_iter(id_distributer: ttgraph::id_distributer::IdDistributer, iter: impl std::iter::IntoIterator<Item=(ttgraph::NodeIndex, Self::V)>) -> Self {
         let mut result = Self::new(id_distributer);
@@ -334,13 +353,16 @@ pub(crate) fn make_cate_arena(
         }
         result
       }
+      fn id_distributer(&self) -> &ttgraph::id_distributer::IdDistributer {
+        &self._id_distributer
+      }
       fn dispatch(&self, i: NodeIndex) -> Option<Self::D> {
         self._dispatcher.get(&i).map(|x|*x)
       }
-      fn get_container<'a>(&'a self, d: Self::D) -> &'a ttgraph::ordermap::OrderMap<usize, Self::V> {
+      fn get_container(&self, d: Self::D) -> &ttgraph::persistent_map::PersistentMap<usize, Self::V> {
         match d { #(#get_container_arms),* }
       }
-      fn get_container_mut<'a>(&'a mut self, d: Self::D) -> &'a mut ttgraph::ordermap::OrderMap<usize, Self::V> {
+      fn get_container_mut(&mut self, d: Self::D) -> &mut ttgraph::persistent_map::PersistentMap<usize, Self::V> {
         match d { #(#get_container_mut_arms),* }
       }
       fn alloc(&mut self, d: Self::D) -> NodeIndex {
@@ -361,13 +383,20 @@ pub(crate) fn make_cate_arena(
         }
       }
       fn remove(&mut self, i: NodeIndex) -> Option<Self::V> {
-        let d = self._dispatcher.swap_remove(&i);
-        d.and_then(|d| self.get_container_mut(d).swap_remove(&i.0))
+        let d = self._dispatcher.remove(&i);
+        d.and_then(|d| self.get_container_mut(d).remove(&i.0))
       }
       fn merge(&mut self, mut other: Self) where Self: Sized{
         self._dispatcher.append(&mut other._dispatcher);
         #(#merge_arms)*
       }
+      fn fork(&self) -> Self {
+        Self{
+          _id_distributer: self._id_distributer.clone(),
+          _dispatcher: self._dispatcher.clone(),
+          #(#fork_arms),*
+        }
+      }
       fn len(&self) -> usize {
         #(#lens)+*
       }
@@ -91,7 +91,7 @@ pub(crate) fn make_get_link_or_group(links: &[LinkType], group_map: &BTreeMap<Id
       LinkType::Empty => {},
     }
   }
-  for (g, _) in group_map {
+  for g in group_map.keys() {
     let c = upper_camel(g);
     arms.push(quote! {std::stringify!(#g) => Some(Self::LoGMirror::#c),});
   }
@@ -128,3 +128,33 @@ pub(crate) fn make_in_group(groups: &[NamedGroup]) -> TokenStream {
     }
   }
 }
+
+pub(crate) fn make_group_discriminants(
+  vars: &[(Ident, syn::Type)], groups: &[NamedGroup], discriminant: &Ident,
+) -> TokenStream {
+  let mut name_arms = Vec::new();
+  for NamedGroup { name, idents } in groups {
+    // Keep the order of variants, so the containers are visited in the same order as iterating all nodes
+    let members = vars.iter().filter(|(var, _)| idents.contains(var)).map(|(var, _)| var);
+    name_arms.push(quote! {
+      std::stringify!(#name) => &[#(#discriminant::#members),*],
+    });
+  }
+  quote! {
+    fn group_discriminants(name: &str) -> &'static [Self::Discriminant] {
+      match name {
+        #(#name_arms)*
+        _ => &[],
+      }
+    }
+  }
+}
+
+pub(crate) fn make_group_names(groups: &[NamedGroup]) -> TokenStream {
+  let names = groups.iter().map(|NamedGroup { name, .. }| quote! {std::stringify!(#name)});
+  quote! {
+    fn group_names() -> &'static [&'static str] {
+      &[#(#names),*]
+    }
+  }
+}
@@ -0,0 +1,95 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use proc_macro2::TokenStream;
+use proc_macro_error::emit_error;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{self, Ident, Token, Type};
+
+use crate::group::NamedGroup;
+
+pub(crate) struct IndexDecl {
+  pub var: Ident,
+  pub field: Ident,
+  pub unique: bool,
+}
+
+pub(crate) struct IndexDeclVec {
+  pub decls: Vec<IndexDecl>,
+}
+
+impl Parse for IndexDecl {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let var = input.parse()?;
+    let _: Token![.] = input.parse()?;
+    let field = input.parse()?;
+    let unique = if input.peek(Token![:]) {
+      let _: Token![:] = input.parse()?;
+      let modifier: Ident = input.parse()?;
+      if modifier != "unique" {
+        return Err(syn::Error::new(modifier.span(), "Expect `unique`"));
+      }
+      true
+    } else {
+      false
+    };
+    Ok(IndexDecl { var, field, unique })
+  }
+}
+
+impl Parse for IndexDeclVec {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let result = input.parse_terminated(IndexDecl::parse, Token![,])?;
+    Ok(IndexDeclVec { decls: result.into_iter().collect() })
+  }
+}
+
+pub(crate) fn check_index_decls(vars: &[(Ident, Type)], decls: &[IndexDecl], groups: &[NamedGroup]) {
+  let mut names = BTreeSet::from_iter(vars.iter().map(|(ident, _)| ident.clone()));
+  names.extend(groups.iter().map(|x| x.name.clone()));
+  for d in decls {
+    if !names.contains(&d.var) {
+      emit_error!(d.var, "Unknown identifier, not a variant or a group of the NodeEnum");
+    }
+  }
+}
+
+pub(crate) fn make_visit_indexes(vars: &[(Ident, Type)], decls: &[IndexDecl], groups: &[NamedGroup]) -> TokenStream {
+  let group_map = BTreeMap::from_iter(groups.iter().map(|x| (x.name.clone(), x.idents.clone())));
+
+  // An index is unique if any of its declarations is unique
+  let mut unique_map: BTreeMap<String, bool> = BTreeMap::new();
+  for d in decls {
+    *unique_map.entry(d.field.to_string()).or_default() |= d.unique;
+  }
+
+  let mut field_map: BTreeMap<Ident, BTreeSet<Ident>> = BTreeMap::new();
+  for d in decls {
+    let vars = if let Some(g) = group_map.get(&d.var) { g.clone() } else { vec![d.var.clone()] };
+    for v in vars {
+      field_map.entry(v).or_default().insert(d.field.clone());
+    }
+  }
+
+  let mut arms = Vec::new();
+  for (var, _) in vars {
+    if let Some(fields) = field_map.get(var) {
+      let mut visits = Vec::new();
+      for field in fields {
+        let unique = unique_map[&field.to_string()];
+        visits.push(quote! {visitor.visit(std::stringify!(#field), #unique, &x.#field);});
+      }
+      arms.push(quote! {Self::#var(x) => { #(#visits)* },});
+    } else {
+      arms.push(quote! {Self::#var(_) => {},});
+    }
+  }
+
+  quote! {
+    fn visit_indexes<TGIndexVisitorT: ttgraph::IndexVisitor>(&self, visitor: &mut TGIndexVisitorT) {
+      match self {
+        #(#arms)*
+      }
+    }
+  }
+}
@@ -4,7 +4,7 @@ use proc_macro::TokenStream;
 use std::collections::BTreeMap;
 // use proc_macro2;
 use proc_macro_error::*;
-use quote::{quote, ToTokens};
+use quote::{format_ident, quote, ToTokens};
 use syn::{parse2, parse_macro_input, parse_quote, Fields, Ident, Item, ItemStruct, Path, Type};
 
 mod node_enum;
@@ -25,6 +25,11 @@ use link_check::*;
 mod group;
 use group::*;
 
+mod index;
+use index::*;
+
+mod schema;
+
 mod utils;
 use utils::*;
 
@@ -46,6 +51,12 @@ use utils::*;
 ///     $group_name{$var1, $var2, ...}
 ///     // ...
 ///   }
+///   // optional, to declare secondary indexes on data fields
+///   index!{
+///     $var.$field,
+///     $var.$field: unique,
+///     // ...
+///   }
 /// }
 /// ```
 #[proc_macro]
@@ -88,6 +99,7 @@ pub fn node_enum(macro_input: TokenStream) -> TokenStream {
   let mut bidirectional_links = Vec::new();
   let mut groups = Vec::new();
   let mut type_annotations = Vec::new();
+  let mut index_decls = Vec::new();
   for item in macro_input.items.iter().skip(1) {
     if let Item::Macro(the_macro) = item {
       if the_macro.mac.path.is_ident("bidirectional") {
@@ -110,6 +122,14 @@ pub fn node_enum(macro_input: TokenStream) -> TokenStream {
             emit_error!(err.span(), "{}", err);
           },
         }
+      } else if the_macro.mac.path.is_ident("index") {
+        let result: syn::Result<IndexDeclVec> = parse2(the_macro.mac.tokens.clone());
+        match result {
+          Ok(x) => index_decls.extend(x.decls),
+          Err(err) => {
+            emit_error!(err.span(), "{}", err);
+          },
+        }
       } else {
         abort!(the_macro.mac.path, "Unsupported macro");
       }
@@ -119,12 +139,14 @@ pub fn node_enum(macro_input: TokenStream) -> TokenStream {
   }
 
   check_bidirectional_links(&vars, &bidirectional_links, &groups);
+  check_index_decls(&vars, &index_decls, &groups);
   abort_if_dirty();
 
   let mut generated = proc_macro2::TokenStream::new();
   let source_enum = make_source_enum(&mut generated, &generics, &vars, &enumt);
   let link_mirror_enum = make_link_mirror_enum(&mut generated, &generics, &vars, &enumt);
   let log_mirror_enum = make_log_mirror_enum(&mut generated, &generics, &vars, &enumt);
+  let data_mut_enum = make_data_mut_enum(&mut generated, &generics, &vars, &enumt);
   // let node_type_mirror = make_node_type_mirror_enum(&mut generated, &vars, &enumt);
   let discriminant = make_node_discriminant(&mut result, &vars, &enumt, &vis);
   let cate_arena = make_cate_arena(&mut result, &vars, &enumt, &discriminant, &vis);
@@ -139,12 +161,14 @@ pub fn node_enum(macro_input: TokenStream) -> TokenStream {
     &source_enum,
     &link_mirror_enum,
     &log_mirror_enum,
+    &data_mut_enum,
     &gen_mod,
     &cate_arena,
     &discriminant,
     &bidirectional_links,
     &groups,
     type_annotations,
+    &index_decls,
   );
 
   result.into()
@@ -165,6 +189,7 @@ pub fn typed_node(input: TokenStream) -> TokenStream {
   let Fields::Named(fields) = &input.fields else { panic!("Impossible!") };
   let mut links = Vec::new();
   let mut data = Vec::new();
+  let mut data_fields = Vec::new();
   let mut groups = Vec::new();
   let mut group_map: BTreeMap<Ident, Vec<Ident>> = BTreeMap::new();
   let direct_paths = vec![parse_quote!(ttgraph::NodeIndex), parse_quote!(NodeIndex)];
@@ -218,6 +243,9 @@ pub fn typed_node(input: TokenStream) -> TokenStream {
         data.push((ident.clone(), p.clone()));
       }
     }
+    if !is_link {
+      data_fields.push((ident.clone(), f.ty.clone(), f.vis.clone()));
+    }
     let mut have_group = false;
     for attr in &f.attrs {
       if attr.path().is_ident("group") {
@@ -258,11 +286,10 @@ pub fn typed_node(input: TokenStream) -> TokenStream {
       a.parse_nested_meta(|meta| {
         if let Some(ident) = meta.path.get_ident() {
           group_map.entry(ident.clone()).or_default();
-          return Ok(());
         } else {
           emit_error!(meta.path, "Group can not be neseted!");
-          return Ok(());
         }
+        Ok(())
       })
       .unwrap();
     }
@@ -276,10 +303,14 @@ pub fn typed_node(input: TokenStream) -> TokenStream {
   let link_mirror = make_link_mirror(&mut generated, &links, &name);
   let log_mirror = make_log_mirror(&mut generated, &links, &group_map, &name);
 
+  let data_mut = make_data_mut(&mut generated, &data_fields, &name, &generics, &vis);
+
   let gen_mod = make_generated_mod(&mut result, generated, &name, &vis);
   make_typed_node(
     &links,
     &data,
+    &data_fields,
+    &data_mut,
     &groups,
     &group_map,
     &name,
@@ -315,6 +346,40 @@ pub fn discriminant(input: TokenStream) -> TokenStream {
   .into()
 }
 
+/// Unwrap the data view of a variant, `data_mut_of!(Node::Type, data)`, returns `Option<NodeTypeData>`.
+/// `data` is the `DataMut` of the NodeEnum. Intented to be used in [`get_data_mut!`] and [`iter_nodes_data_mut!`].
+#[doc(hidden)]
+#[proc_macro]
+#[proc_macro_error]
+pub fn data_mut_of(input: TokenStream) -> TokenStream {
+  let DataMutOf { path, expr } = parse_macro_input!(input);
+  if path.segments.len() < 2 {
+    abort!(path, "Requires a path to a variant of a NodeEnum");
+  }
+  let mut segs = path.segments.clone();
+  let last = segs.pop().unwrap().into_tuple().0.ident;
+  let enumt = segs.pop().unwrap().into_value().ident;
+  let gen_mod = generated_mod_ident(&enumt);
+  let data_mut_enum = format_ident!("{}DataMut", enumt);
+  segs.push(parse_quote!(#gen_mod));
+  segs.push(parse_quote!(#data_mut_enum));
+  segs.push(parse_quote!(#last));
+  let p = Path {
+    leading_colon: path.leading_colon,
+    segments: segs,
+  };
+  quote! {
+    {
+      #[allow(unreachable_patterns)]
+      match #expr {
+        #p(x) => Some(x),
+        _ => None,
+      }
+    }
+  }
+  .into()
+}
+
 // /// Mark a phantom group (a group that does not have a link) in a TypedNode
 // #[proc_macro_attribute]
 // pub fn phantom_group(_attr: TokenStream, input: TokenStream) -> TokenStream {
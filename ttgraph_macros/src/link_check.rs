@@ -8,6 +8,7 @@ use syn::{self, braced, token, Ident, Token, Type};
 use crate::group::NamedGroup;
 use crate::utils::upper_camel;
 
+#[derive(Clone)]
 pub(crate) struct TypeAnnotation {
   pub var: Ident,
   pub link: Ident,
@@ -45,7 +46,7 @@ impl Parse for TypeAnnotationVec {
   }
 }
 
-fn expand_group(annotations: Vec<TypeAnnotation>, group_map: &BTreeMap<Ident, Vec<Ident>>) -> Vec<TypeAnnotation> {
+pub(crate) fn expand_group(annotations: Vec<TypeAnnotation>, group_map: &BTreeMap<Ident, Vec<Ident>>) -> Vec<TypeAnnotation> {
   let mut result = Vec::new();
   for TypeAnnotation{var, link ,var2} in annotations {
     let mut expanded_var2 = Vec::new();
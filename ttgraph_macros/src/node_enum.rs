@@ -1,10 +1,12 @@
 use proc_macro2::{self, TokenStream};
 use quote::{format_ident, quote, ToTokens};
-use syn::{Generics, Ident, Type};
+use syn::{parse_quote, Generics, Ident, Type};
 
 use crate::bidirectional::*;
 use crate::group::*;
+use crate::index::*;
 use crate::link_check::*;
+use crate::schema::*;
 
 pub(crate) fn make_source_enum(
   result: &mut TokenStream, generics: &Generics, vars: &Vec<(Ident, Type)>, enumt: &Ident,
@@ -67,6 +69,27 @@ pub(crate) fn make_log_mirror_enum(
   log_mirror_enum
 }
 
+pub(crate) fn make_data_mut_enum(
+  result: &mut TokenStream, generics: &Generics, vars: &Vec<(Ident, Type)>, enumt: &Ident,
+) -> Ident {
+  let data_mut_enum = format_ident!("{}DataMut", enumt);
+  let mut data_generics = generics.clone();
+  data_generics.params.insert(0, parse_quote!('tg_data));
+  let mut v = Vec::new();
+  for (ident, ty) in vars {
+    v.push(quote! {#ident(<self::super::#ty as ttgraph::TypedNode>::DataMut<'tg_data>),});
+  }
+
+  quote! {
+    pub enum #data_mut_enum #data_generics{
+      #(#v)*
+    }
+  }
+  .to_tokens(result);
+
+  data_mut_enum
+}
+
 // pub(crate) fn make_node_type_mirror_enum(result: &mut TokenStream, vars: &Vec<(Ident, Type)>, enumt: &Ident) -> Ident {
 //   let enum_name = format_ident!("{}NodeTypeMirror", enumt);
 //   let mut v = Vec::new();
@@ -85,10 +108,12 @@ pub(crate) fn make_log_mirror_enum(
 //   enum_name
 // }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn make_node_enum(
   result: &mut TokenStream, generics: &Generics, vars: &Vec<(Ident, Type)>, enumt: &Ident, source_enum: &Ident,
-  link_mirror_enum: &Ident, log_mirror_enum: &Ident, gen_mod: &Ident, cate_arena: &Ident, discriminant: &Ident,
+  link_mirror_enum: &Ident, log_mirror_enum: &Ident, data_mut_enum: &Ident, gen_mod: &Ident, cate_arena: &Ident, discriminant: &Ident,
   bidirectional_links: &[BidirectionalLink], groups: &[NamedGroup], type_annotations: Vec<TypeAnnotation>,
+  index_decls: &[IndexDecl],
 ) {
   let mut iter_src_arms = Vec::new();
   for (ident, ty) in vars {
@@ -187,6 +212,13 @@ pub(crate) fn make_node_enum(
     });
   }
 
+  let mut set_link_by_name_arms = Vec::new();
+  for (ident, _) in vars {
+    set_link_by_name_arms.push(quote! {
+      Self::#ident(x) => x.set_links_by_name(name, targets),
+    });
+  }
+
   let mut get_link_by_group_arms = Vec::new();
   for (ident, _) in vars {
     get_link_by_group_arms.push(quote! {
@@ -211,6 +243,20 @@ pub(crate) fn make_node_enum(
     to_link_arms.push(quote! {Self::SourceEnum::#ident(x) => Self::LinkMirrorEnum::#ident(x.to_link_mirror()), });
   }
 
+  let mut link_name_arms = Vec::new();
+  for (ident, ty) in vars {
+    link_name_arms.push(quote! {Self::LinkMirrorEnum::#ident(x) => <#ty as ttgraph::TypedNode>::link_name(x), });
+  }
+
+  let mut data_names_of_arms = Vec::new();
+  let mut link_names_of_arms = Vec::new();
+  let mut link_types_of_arms = Vec::new();
+  for (ident, ty) in vars {
+    data_names_of_arms.push(quote! {#discriminant::#ident => <#ty as ttgraph::TypedNode>::data_names(), });
+    link_names_of_arms.push(quote! {#discriminant::#ident => <#ty as ttgraph::TypedNode>::link_names(), });
+    link_types_of_arms.push(quote! {#discriminant::#ident => <#ty as ttgraph::TypedNode>::link_types(), });
+  }
+
   let mut to_log_arms = Vec::new();
   for (ident, ty) in vars {
     to_log_arms.push(quote! {Self::LinkMirrorEnum::#ident(x) => Vec::from_iter(<#ty as ttgraph::TypedNode>::to_link_or_groups(x).iter().map(|l|Self::LoGMirrorEnum::#ident(*l))), });
@@ -232,6 +278,16 @@ pub(crate) fn make_node_enum(
     })
   }
 
+  let mut data_mut_arms = Vec::new();
+  for (ident, ty) in vars {
+    data_mut_arms.push(quote! {
+      Self::#ident(x) => #gen_mod::#data_mut_enum::#ident(<#ty as ttgraph::TypedNode>::data_mut(x)),
+    });
+  }
+  let mut data_generics = generics.clone();
+  data_generics.params.insert(0, parse_quote!('tg_data));
+  let (_, data_ty_generics, _) = data_generics.split_for_impl();
+
   let mut disc_arms = Vec::new();
   for (ident, _) in vars {
     disc_arms.push(quote! { Self::#ident(_) => #discriminant::#ident })
@@ -239,7 +295,11 @@ pub(crate) fn make_node_enum(
 
   let bidirectional_link = make_bidirectional_link(vars, bidirectional_links);
   let in_group = make_in_group(groups);
+  let group_discriminants = make_group_discriminants(vars, groups, discriminant);
+  let group_names = make_group_names(groups);
+  let schema = make_schema(vars, enumt, groups, &type_annotations, bidirectional_links);
   let link_check = make_check_link_type(vars, type_annotations, groups);
+  let visit_indexes = make_visit_indexes(vars, index_decls, groups);
 
   let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
   quote!{
@@ -250,6 +310,7 @@ pub(crate) fn make_node_enum(
       type LoGMirrorEnum = #gen_mod::#log_mirror_enum #ty_generics;
       type Discriminant = #discriminant;
       type GenArena = #cate_arena;
+      type DataMut<'tg_data> = #gen_mod::#data_mut_enum #data_ty_generics where Self: 'tg_data;
       fn iter_sources(&self) -> Box<dyn Iterator<Item = (NodeIndex, Self::SourceEnum)>> {
         match self {
           #(#iter_src_arms)*
@@ -290,6 +351,11 @@ pub(crate) fn make_node_enum(
           #(#get_link_by_name_arms)*
         }
       }
+      fn set_links_by_name(&mut self, name: &str, targets: Vec<ttgraph::NodeIndex>) -> bool {
+        match self{
+          #(#set_link_by_name_arms)*
+        }
+      }
       fn get_links_by_group(&self, name: &'static str) -> Vec<ttgraph::NodeIndex>{
         match self{
           #(#get_link_by_group_arms)*
@@ -297,12 +363,19 @@ pub(crate) fn make_node_enum(
       }
 
       #in_group
+      #group_discriminants
+      #group_names
 
       fn data_ref_by_name<T: std::any::Any>(&self, name: &'static str) -> Option<&T> {
         match self{
           #(#data_ref_arms)*
         }
       }
+      fn data_mut(&mut self) -> Self::DataMut<'_> {
+        match self{
+          #(#data_mut_arms)*
+        }
+      }
 
       fn to_link_mirror_enum(input: Self::SourceEnum) -> Self::LinkMirrorEnum {
         match input {
@@ -314,6 +387,26 @@ pub(crate) fn make_node_enum(
           #(#to_src_arms)*
         }
       }
+      fn data_names_of(d: Self::Discriminant) -> &'static [&'static str] {
+        match d {
+          #(#data_names_of_arms)*
+        }
+      }
+      fn link_names_of(d: Self::Discriminant) -> &'static [&'static str] {
+        match d {
+          #(#link_names_of_arms)*
+        }
+      }
+      fn link_types_of(d: Self::Discriminant) -> &'static [ttgraph::LinkType] {
+        match d {
+          #(#link_types_of_arms)*
+        }
+      }
+      fn link_name(input: Self::LinkMirrorEnum) -> &'static str {
+        match input {
+          #(#link_name_arms)*
+        }
+      }
       fn to_log_mirror_enums(input: Self::LinkMirrorEnum) -> Vec<Self::LoGMirrorEnum> {
         match input {
           #(#to_log_arms)*
@@ -329,6 +422,10 @@ pub(crate) fn make_node_enum(
 
       #link_check
 
+      #visit_indexes
+
+      #schema
+
       fn match_bd_link_group(&self, links: Vec<Self::LinkMirrorEnum>) -> Vec<Self::LinkMirrorEnum> {
         let mut result = Vec::new();
         match self {
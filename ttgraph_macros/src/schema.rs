@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+use crate::bidirectional::BidirectionalLink;
+use crate::group::NamedGroup;
+use crate::link_check::{expand_group, TypeAnnotation};
+use crate::utils::type_string;
+
+pub(crate) fn make_schema(
+  vars: &[(Ident, Type)], enumt: &Ident, groups: &[NamedGroup], type_annotations: &[TypeAnnotation],
+  bidirectional_links: &[BidirectionalLink],
+) -> TokenStream {
+  let mut variants = Vec::new();
+  for (ident, ty) in vars {
+    let type_name = type_string(ty);
+    variants.push(quote! {
+      ttgraph::VariantSchema::of::<#ty>(std::stringify!(#ident), #type_name)
+    });
+  }
+
+  let mut group_schemas = Vec::new();
+  for NamedGroup { name, idents } in groups {
+    // Keep the order of variants, same as group_discriminants
+    let members = vars.iter().filter(|(var, _)| idents.contains(var)).map(|(var, _)| var);
+    group_schemas.push(quote! {
+      ttgraph::GroupSchema {
+        name: std::stringify!(#name).to_string(),
+        variants: vec![#(std::stringify!(#members).to_string()),*],
+      }
+    });
+  }
+
+  let group_map = BTreeMap::from_iter(groups.iter().map(|x| (x.name.clone(), x.idents.clone())));
+  let mut link_types = Vec::new();
+  for TypeAnnotation { var, link, var2 } in expand_group(type_annotations.to_vec(), &group_map) {
+    link_types.push(quote! {
+      ttgraph::LinkTypeSchema {
+        variant: std::stringify!(#var).to_string(),
+        link: std::stringify!(#link).to_string(),
+        targets: vec![#(std::stringify!(#var2).to_string()),*],
+      }
+    });
+  }
+
+  let mut bidirectional = Vec::new();
+  for BidirectionalLink { var1, link1, var2, link2 } in bidirectional_links {
+    bidirectional.push(quote! {
+      ttgraph::BidirectionalSchema {
+        variant1: std::stringify!(#var1).to_string(),
+        link1: std::stringify!(#link1).to_string(),
+        variant2: std::stringify!(#var2).to_string(),
+        link2: std::stringify!(#link2).to_string(),
+      }
+    });
+  }
+
+  quote! {
+    fn schema() -> ttgraph::GraphSchema {
+      ttgraph::GraphSchema {
+        name: std::stringify!(#enumt).to_string(),
+        variants: vec![#(#variants),*],
+        groups: vec![#(#group_schemas),*],
+        link_types: vec![#(#link_types),*],
+        bidirectional: vec![#(#bidirectional),*],
+      }
+    }
+  }
+}
@@ -1,11 +1,11 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, ToTokens};
-use syn::{Generics, Ident, TypePath};
+use syn::{parse_quote, Generics, Ident, Type, TypePath, Visibility};
 
 use std::collections::BTreeMap;
 
 use super::group::*;
-use super::upper_camel;
+use super::{nested_vis, type_string, upper_camel};
 
 #[derive(Debug, Clone)]
 pub(crate) enum LinkType {
@@ -153,8 +153,47 @@ pub(crate) fn make_log_mirror(
   log_mirror
 }
 
+pub(crate) fn make_data_mut(
+  result: &mut TokenStream, data_fields: &[(Ident, Type, Visibility)], name: &Ident, generics: &Generics,
+  vis: &Visibility,
+) -> Ident {
+  let data_mut = format_ident!("{}Data", name);
+  let mut data_generics = generics.clone();
+  data_generics.params.insert(0, parse_quote!('tg_data));
+  let where_clause = &generics.where_clause;
+
+  let mut fields = Vec::new();
+  for (ident, ty, field_vis) in data_fields {
+    let field_vis = nested_vis(field_vis);
+    fields.push(quote! {#field_vis #ident: &'tg_data mut #ty,});
+  }
+  // Keep the lifetime and generics used even if there is no data field
+  if data_fields.is_empty() || !generics.params.is_empty() {
+    let (_, ty_generics, _) = generics.split_for_impl();
+    fields.push(quote! {pub(super) _marker: std::marker::PhantomData<&'tg_data mut #name #ty_generics>,});
+  }
+
+  // Generated inside the generated mod, so the name does not collide with the types of the user
+  let doc = format!("Mutable references to the data fields of [`{}`], generated by `TypedNode` derive", name);
+  let vis = nested_vis(vis);
+  quote! {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[doc = #doc]
+    #vis struct #data_mut #data_generics #where_clause {
+      #(#fields)*
+    }
+  }
+  .to_tokens(result);
+
+  data_mut
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn make_typed_node(
-  links: &[LinkType], data: &[(Ident, TypePath)], groups: &[Vec<Ident>], group_map: &BTreeMap<Ident, Vec<Ident>>,
+  links: &[LinkType], data: &[(Ident, TypePath)], data_fields: &[(Ident, Type, Visibility)], data_mut: &Ident,
+  groups: &[Vec<Ident>], group_map: &BTreeMap<Ident, Vec<Ident>>,
   name: &Ident, generics: &Generics, gen_mod: &Ident, source_enum: &Ident, link_mirror: &Ident, log_mirror: &Ident,
 ) -> TokenStream {
   let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
@@ -382,6 +421,26 @@ pub(crate) fn make_typed_node(
     });
   }
 
+  let mut set_link_by_name_vec = Vec::new();
+  for s in links {
+    match s {
+      LinkType::Direct(name, _) => set_link_by_name_vec.push(quote! {
+        std::stringify!(#name) => {
+          assert!(targets.len() <= 1, "Set multiple targets to the single link {}", name);
+          self.#name = targets.first().copied().unwrap_or_else(ttgraph::NodeIndex::empty);
+          true
+        },
+      }),
+      LinkType::Set(name, _) | LinkType::Vec(name, _) => set_link_by_name_vec.push(quote! {
+        std::stringify!(#name) => {
+          self.#name = std::iter::FromIterator::from_iter(targets);
+          true
+        },
+      }),
+      _ => {},
+    }
+  }
+
   let get_links_by_group = make_get_links_by_group(links, groups);
   let get_log_by_name = make_get_link_or_group(links, group_map);
 
@@ -391,6 +450,12 @@ pub(crate) fn make_typed_node(
     data_type_vec.push(quote! {std::any::TypeId::of::<#ty>()});
   }
 
+  // Generate the static data type name vec
+  let mut data_type_name_vec = Vec::new();
+  for (_, ty) in data {
+    data_type_name_vec.push(type_string(ty));
+  }
+
   // Generate the static data name vec
   let mut data_name_vec = Vec::new();
   for (ident, _) in data {
@@ -405,6 +470,17 @@ pub(crate) fn make_typed_node(
     });
   }
 
+  let mut data_mut_fields = Vec::new();
+  for (ident, _, _) in data_fields {
+    data_mut_fields.push(quote! {#ident: &mut self.#ident,});
+  }
+  if data_fields.is_empty() || !generics.params.is_empty() {
+    data_mut_fields.push(quote! {_marker: std::marker::PhantomData,});
+  }
+  let mut data_generics = generics.clone();
+  data_generics.params.insert(0, parse_quote!('tg_data));
+  let (_, data_ty_generics, _) = data_generics.split_for_impl();
+
   let mut to_log_arms = Vec::new();
   for (s, gs) in links.iter().zip(groups.iter()) {
     let mut logs = Vec::new();
@@ -426,6 +502,7 @@ pub(crate) fn make_typed_node(
       type Source = #gen_mod::#source_enum;
       type LinkMirror = #gen_mod::#link_mirror;
       type LoGMirror = #gen_mod::#log_mirror;
+      type DataMut<'tg_data> = #gen_mod::#data_mut #data_ty_generics where Self: 'tg_data;
       fn iter_sources(&self) -> std::vec::IntoIter<(ttgraph::NodeIndex, Self::Source)>  {
         let mut sources = Vec::new();
         #(#add_source_ops)*
@@ -472,6 +549,12 @@ pub(crate) fn make_typed_node(
           _ => Box::new([].into_iter())
         }
       }
+      fn set_links_by_name(&mut self, name: &str, targets: Vec<ttgraph::NodeIndex>) -> bool {
+        match name {
+          #(#set_link_by_name_vec)*
+          _ => false
+        }
+      }
       #get_links_by_group
       #get_log_by_name
 
@@ -481,12 +564,20 @@ pub(crate) fn make_typed_node(
       fn data_names() -> &'static [&'static str] {
         &[#(#data_name_vec),*]
       }
+      fn data_type_names() -> &'static [&'static str] {
+        &[#(#data_type_name_vec),*]
+      }
       fn data_ref_by_name<TGDataRefT:std::any::Any>(&self, name: &'static str) -> Option<&TGDataRefT> {
         match name {
           #(#data_ref_arms)*
           _ => None
         }
       }
+      fn data_mut(&mut self) -> Self::DataMut<'_> {
+        #gen_mod::#data_mut {
+          #(#data_mut_fields)*
+        }
+      }
 
       fn to_source(input: Self::LinkMirror) -> Self::Source {
         input.to_source()
@@ -1,6 +1,7 @@
 use change_case::{pascal_case, self};
 use quote::{format_ident, quote, ToTokens};
-use syn::{Ident, Visibility};
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, Path, Token, Visibility};
 use proc_macro2::TokenStream;
 
 pub(crate) fn upper_camel(ident: &Ident) -> Ident {
@@ -11,8 +12,46 @@ pub(crate) fn snake_case(ident: &Ident) -> Ident {
   format_ident!("{}", change_case::snake_case(&ident.to_string()), span = ident.span())
 }
 
+/// Print a type as it is usually written, e.g. `Vec<String>` instead of `Vec < String >`
+pub(crate) fn type_string(ty: &impl ToTokens) -> String {
+  ty.to_token_stream()
+    .to_string()
+    .replace(" :: ", "::")
+    .replace(" <", "<")
+    .replace("< ", "<")
+    .replace(" >", ">")
+    .replace(" ,", ",")
+    .replace("& ", "&")
+}
+
+pub(crate) fn generated_mod_ident(ident: &Ident) -> Ident {
+  format_ident!("ttgraph_gen_{}", change_case::snake_case(&ident.to_string()), span=ident.span())
+}
+
+/// The visibility inside the generated mod which is the same as `vis` outside of it
+pub(crate) fn nested_vis(vis: &Visibility) -> TokenStream {
+  match vis {
+    Visibility::Public(_) => quote! {pub},
+    Visibility::Inherited => quote! {pub(super)},
+    Visibility::Restricted(r) => {
+      let path = &r.path;
+      if path.is_ident("crate") {
+        quote! {pub(crate)}
+      } else if path.is_ident("self") {
+        quote! {pub(super)}
+      } else if path.segments.first().is_some_and(|x| x.ident == "crate") {
+        quote! {pub(in #path)}
+      } else {
+        // self or super relative paths, e.g. `pub(super)` or `pub(in super::super)`
+        let path = path.segments.iter().skip_while(|x| x.ident == "self");
+        quote! {pub(in super #(::#path)*)}
+      }
+    },
+  }
+}
+
 pub(crate) fn make_generated_mod(result: &mut TokenStream, generated: TokenStream, ident: &Ident, vis: &Visibility) -> Ident {
-  let gen_ident = format_ident!("ttgraph_gen_{}", change_case::snake_case(&ident.to_string()), span=ident.span());
+  let gen_ident = generated_mod_ident(ident);
   quote!{
     #vis mod #gen_ident {
       #generated
@@ -20,3 +59,17 @@ pub(crate) fn make_generated_mod(result: &mut TokenStream, generated: TokenStrea
   }.to_tokens(result);
   gen_ident
 }
+
+pub(crate) struct DataMutOf {
+  pub path: Path,
+  pub expr: Expr,
+}
+
+impl Parse for DataMutOf {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let path = input.parse()?;
+    let _: Token![,] = input.parse()?;
+    let expr = input.parse()?;
+    Ok(DataMutOf { path, expr })
+  }
+}